@@ -0,0 +1,46 @@
+mod common;
+
+use common::TicTacToe;
+use oxymcts::{
+    AmafLiteBackProp, BackPropPolicy, DefaultBackProp, DefaultPlayout, DefaultUctEvaluator,
+    GameTrait, TreePolicyMcts,
+};
+
+type Mcts<'a, BP> = TreePolicyMcts<'a, TicTacToe, DefaultUctEvaluator, DefaultPlayout, BP>;
+
+/// Returns the share of the seeds for which the search found the winning move after
+/// `iterations` iterations.
+fn success_rate<BP>(state: &TicTacToe, winning_move: usize, iterations: usize) -> f64
+where
+    BP: BackPropPolicy<Vec<usize>, usize, u32, ()>,
+{
+    let seeds = 200;
+    let successes = (0..seeds)
+        .filter(|&seed| {
+            let mcts = Mcts::<BP>::deterministic(state, seed);
+            for _ in 0..iterations {
+                mcts.execute(&2f64.sqrt(), ());
+            }
+            mcts.best_move(&2f64.sqrt()) == winning_move
+        })
+        .count();
+    successes as f64 / seeds as f64
+}
+
+fn main() {
+    // X has to complete its first row in the cell 2, O would win in the cell 5 otherwise.
+    let mut state = TicTacToe::new();
+    for m in &[0, 3, 1, 4] {
+        state.do_move(m);
+    }
+
+    println!("iterations  default  amaf-lite");
+    for &iterations in &[10, 20, 40, 80, 160] {
+        println!(
+            "{:>10}  {:>7.2}  {:>9.2}",
+            iterations,
+            success_rate::<DefaultBackProp>(&state, 2, iterations),
+            success_rate::<AmafLiteBackProp>(&state, 2, iterations),
+        );
+    }
+}
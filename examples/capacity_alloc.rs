@@ -0,0 +1,95 @@
+mod common;
+
+use std::sync::atomic::Ordering;
+
+use common::{CountingAllocator, FillTheBoard, ALLOCATIONS};
+use oxymcts::{DefaultMcts, GameTrait};
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Counts the allocations of `iterations` iterations of `mcts`.
+fn count_allocations(mcts: DefaultMcts<FillTheBoard>, iterations: usize) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..iterations {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// Counts the allocations of a full game on a board of `side` x `side` cells, the search of each
+/// move running `iterations` iterations from the tree kept by `advance_root`.
+fn count_game_allocations(side: usize, iterations: usize, recycle: bool) -> usize {
+    let mut state = FillTheBoard::new(side);
+    let root_state = state.clone();
+    let mut mcts = DefaultMcts::new(&root_state);
+    if recycle {
+        mcts.recycle_nodes();
+    }
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    while !state.is_final() {
+        for _ in 0..iterations {
+            mcts.execute(&2f64.sqrt(), ());
+        }
+        let mv = mcts.best_move(&2f64.sqrt());
+        mcts.advance_root(&mv);
+        state.do_move(&mv);
+    }
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// Counts the allocations of generating the moves of `state` `times` times, with `legals_moves`
+/// or, as the new nodes do, with `legal_moves_into` in a buffer sized by `MAX_BRANCHING` then
+/// shrunk.
+fn count_move_generation(state: &FillTheBoard, times: usize, into_buffer: bool) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..times {
+        let moves = if into_buffer {
+            let mut moves = Vec::with_capacity(FillTheBoard::MAX_BRANCHING);
+            state.legal_moves_into(&mut moves);
+            moves.shrink_to_fit();
+            moves
+        } else {
+            state.legals_moves()
+        };
+        drop(moves);
+    }
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn main() {
+    // The unvisited moves of the new nodes are generated in a buffer sized by `MAX_BRANCHING`,
+    // and the arena of the tree grows with the tree or is sized by `with_capacity`.
+    let iterations = 4000;
+    let state = FillTheBoard::new(20);
+    let growing = count_allocations(DefaultMcts::new(&state), iterations);
+    let sized = count_allocations(
+        DefaultMcts::with_capacity(&state, iterations + 1),
+        iterations,
+    );
+    println!("{} iterations on a 20x20 board", iterations);
+    println!("allocations with new:           {}", growing);
+    println!("allocations with with_capacity: {}", sized);
+    println!(
+        "allocations per iteration:      {:.2} and {:.2}",
+        growing as f64 / iterations as f64,
+        sized as f64 / iterations as f64
+    );
+    let collected = count_move_generation(&state, iterations, false);
+    let buffered = count_move_generation(&state, iterations, true);
+    println!("generating the unvisited moves of {} nodes", iterations);
+    println!("allocations with legals_moves:     {}", collected);
+    println!("allocations with legal_moves_into: {}", buffered);
+
+    // A full game with tree reuse, the arena being regrown or recycled after each move.
+    let (side, iterations) = (8, 500);
+    let regrown = count_game_allocations(side, iterations, false);
+    let recycled = count_game_allocations(side, iterations, true);
+    println!();
+    println!(
+        "a full game on a 8x8 board, {} iterations per move",
+        iterations
+    );
+    println!("allocations without recycle_nodes: {}", regrown);
+    println!("allocations with recycle_nodes:    {}", recycled);
+}
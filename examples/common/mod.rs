@@ -0,0 +1,219 @@
+//! The games shared by the examples and the allocator counting their allocations, each example
+//! uses some of them.
+#![allow(dead_code)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use noisy_float::prelude::n64;
+use oxymcts::{GameTrait, Num};
+
+/// A 3x3 tic-tac-toe, the cells are numbered from 0 to 8 row by row.
+#[derive(Clone)]
+pub struct TicTacToe {
+    cells: [u8; 9],
+    turn: u8,
+}
+
+const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+impl TicTacToe {
+    /// Returns the empty board, the first player to move.
+    pub fn new() -> Self {
+        TicTacToe {
+            cells: [0; 9],
+            turn: 1,
+        }
+    }
+
+    pub fn winner(&self) -> Option<u8> {
+        LINES.iter().find_map(|line| {
+            let player = self.cells[line[0]];
+            if player != 0 && line.iter().all(|&cell| self.cells[cell] == player) {
+                Some(player)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl GameTrait for TicTacToe {
+    type Player = u8;
+    type Move = usize;
+
+    fn legals_moves(&self) -> Vec<Self::Move> {
+        (0..9).filter(|&cell| self.cells[cell] == 0).collect()
+    }
+
+    fn player_turn(&self) -> Self::Player {
+        self.turn
+    }
+
+    fn hash(&self) -> u64 {
+        0
+    }
+
+    fn is_final(&self) -> bool {
+        self.winner().is_some() || self.cells.iter().all(|&cell| cell != 0)
+    }
+
+    fn do_move(&mut self, m: &Self::Move) {
+        self.cells[*m] = self.turn;
+        self.turn = 3 - self.turn;
+    }
+
+    fn get_winner(&self) -> Self::Player {
+        self.winner().unwrap_or(0)
+    }
+}
+
+/// Players take turns removing one to three sticks, the player who takes the last stick wins.
+/// Leaving a multiple of 4 sticks wins, which is the static evaluation of the game.
+#[derive(Clone)]
+pub struct Nim {
+    sticks: u8,
+    turn: u8,
+}
+
+impl Nim {
+    /// Returns a heap of `sticks` sticks, the first player to move.
+    pub fn new(sticks: u8) -> Self {
+        Nim { sticks, turn: 1 }
+    }
+}
+
+impl GameTrait for Nim {
+    type Player = u8;
+    type Move = u8;
+
+    fn legals_moves(&self) -> Vec<Self::Move> {
+        (1..=self.sticks.min(3)).collect()
+    }
+
+    fn player_turn(&self) -> Self::Player {
+        self.turn
+    }
+
+    fn hash(&self) -> u64 {
+        0
+    }
+
+    fn is_final(&self) -> bool {
+        self.sticks == 0
+    }
+
+    fn do_move(&mut self, m: &Self::Move) {
+        self.sticks -= m;
+        self.turn = 3 - self.turn;
+    }
+
+    fn get_winner(&self) -> Self::Player {
+        3 - self.turn
+    }
+
+    fn static_eval(&self) -> Option<Num> {
+        Some(n64(if self.sticks.is_multiple_of(4) {
+            1.
+        } else {
+            0.
+        }))
+    }
+}
+
+/// Players take turns filling the cells of a board, the player who fills the last cell wins.
+/// The playouts fill the whole board, so on a large board they dominate the cost of an iteration,
+/// and the branching factor starts at the number of cells, 400 on a 20x20 board.
+#[derive(Clone)]
+pub struct FillTheBoard {
+    cells: Vec<bool>,
+    turn: u8,
+    /// When false, `legal_moves_into` behaves like the default implementation.
+    reuse_buffer: bool,
+}
+
+impl FillTheBoard {
+    /// Returns an empty board of `side` x `side` cells, the first player to move.
+    pub fn new(side: usize) -> Self {
+        FillTheBoard {
+            cells: vec![false; side * side],
+            turn: 1,
+            reuse_buffer: true,
+        }
+    }
+
+    /// Like `new`, but `legal_moves_into` allocates a new vector like the default implementation.
+    pub fn without_buffer_reuse(side: usize) -> Self {
+        FillTheBoard {
+            reuse_buffer: false,
+            ..FillTheBoard::new(side)
+        }
+    }
+}
+
+impl GameTrait for FillTheBoard {
+    type Player = u8;
+    type Move = usize;
+
+    const MAX_BRANCHING: usize = 400;
+
+    fn legals_moves(&self) -> Vec<Self::Move> {
+        (0..self.cells.len()).filter(|&i| !self.cells[i]).collect()
+    }
+
+    fn legal_moves_into(&self, buf: &mut Vec<Self::Move>) {
+        buf.clear();
+        if self.reuse_buffer {
+            buf.extend((0..self.cells.len()).filter(|&i| !self.cells[i]));
+        } else {
+            buf.extend(self.legals_moves());
+        }
+    }
+
+    fn player_turn(&self) -> Self::Player {
+        self.turn
+    }
+
+    fn hash(&self) -> u64 {
+        0
+    }
+
+    fn is_final(&self) -> bool {
+        self.cells.iter().all(|&c| c)
+    }
+
+    fn do_move(&mut self, m: &Self::Move) {
+        self.cells[*m] = true;
+        self.turn = 3 - self.turn;
+    }
+
+    fn get_winner(&self) -> Self::Player {
+        3 - self.turn
+    }
+}
+
+/// Counts every allocation made by the program, installed with `#[global_allocator]` by the
+/// examples counting the allocations of the search.
+pub struct CountingAllocator;
+
+pub static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
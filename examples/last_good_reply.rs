@@ -0,0 +1,60 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::TicTacToe;
+use oxymcts::{
+    DefaultMcts, DefaultUctEvaluator, GameTrait, LastGoodReplyPlayout, ReplyTable, TreePolicyMcts,
+};
+
+type LgrMcts<'a> = TreePolicyMcts<'a, TicTacToe, DefaultUctEvaluator, LastGoodReplyPlayout>;
+
+const ITERATIONS: usize = 60;
+
+fn default_move(state: &TicTacToe, seed: u64) -> usize {
+    let mcts = DefaultMcts::deterministic(state, seed);
+    for _ in 0..ITERATIONS {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    mcts.best_move(&2f64.sqrt())
+}
+
+fn lgr_move(state: &TicTacToe, seed: u64, table: &Arc<ReplyTable<usize>>) -> usize {
+    let mcts = LgrMcts::deterministic(state, seed);
+    for _ in 0..ITERATIONS {
+        mcts.execute(&2f64.sqrt(), table.clone());
+    }
+    mcts.best_move(&2f64.sqrt())
+}
+
+fn main() {
+    let games = 500;
+    let (mut wins, mut draws, mut losses) = (0, 0, 0);
+    for game in 0..games {
+        // The last good reply player starts one game out of two.
+        let lgr_player = 1 + (game % 2) as u8;
+        let mut state = TicTacToe::new();
+        // The replies are kept from one move to the next.
+        let table = Arc::new(ReplyTable::new());
+        let mut seed = game;
+        while !state.is_final() {
+            let m = if state.player_turn() == lgr_player {
+                lgr_move(&state, seed, &table)
+            } else {
+                default_move(&state, seed)
+            };
+            state.do_move(&m);
+            seed += games;
+        }
+        match state.winner() {
+            Some(winner) if winner == lgr_player => wins += 1,
+            Some(_) => losses += 1,
+            None => draws += 1,
+        }
+    }
+    println!(
+        "last good reply against random playouts, {} iterations per move, {} games:",
+        ITERATIONS, games
+    );
+    println!("wins: {}, draws: {}, losses: {}", wins, draws, losses);
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use std::time::Instant;
+
+use common::FillTheBoard;
+use oxymcts::DefaultMcts;
+
+fn main() {
+    let state = FillTheBoard::new(15);
+    let playouts = 4000;
+    let playouts_per_leaf = 8;
+
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    let start = Instant::now();
+    for _ in 0..playouts {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    println!("{} sequential playouts: {:?}", playouts, start.elapsed());
+
+    for &threads in &[1, 2, 4, 8] {
+        let mcts = DefaultMcts::deterministic(&state, 0);
+        let start = Instant::now();
+        for _ in 0..playouts / playouts_per_leaf {
+            mcts.execute_leaf_parallel(&2f64.sqrt(), (), playouts_per_leaf, threads);
+        }
+        println!(
+            "{} playouts, {} per leaf on {} threads: {:?}",
+            playouts,
+            playouts_per_leaf,
+            threads,
+            start.elapsed()
+        );
+    }
+
+    // The same iterations on a thread pool created once for the search.
+    #[cfg(feature = "rayon")]
+    for &threads in &[1, 2, 4, 8] {
+        let mcts = DefaultMcts::with_playout_pool(&state, threads, None).unwrap();
+        let start = Instant::now();
+        for _ in 0..playouts / playouts_per_leaf {
+            mcts.execute_leaf_pooled(&2f64.sqrt(), (), playouts_per_leaf);
+        }
+        println!(
+            "{} playouts, {} per leaf on a pool of {} threads: {:?}",
+            playouts,
+            playouts_per_leaf,
+            threads,
+            start.elapsed()
+        );
+    }
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use std::sync::atomic::Ordering;
+
+use common::{CountingAllocator, FillTheBoard, ALLOCATIONS};
+use oxymcts::DefaultMcts;
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn count_allocations(reuse_buffer: bool, playouts: usize) -> usize {
+    let state = if reuse_buffer {
+        FillTheBoard::new(20)
+    } else {
+        FillTheBoard::without_buffer_reuse(20)
+    };
+    let mcts = DefaultMcts::new(&state);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..playouts {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn main() {
+    let playouts = 1000;
+    let owning = count_allocations(false, playouts);
+    let buffered = count_allocations(true, playouts);
+    println!("{} playouts on a 20x20 board", playouts);
+    println!("allocations with legals_moves:     {}", owning);
+    println!("allocations with legal_moves_into: {}", buffered);
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::Nim;
+use oxymcts::{ProgressiveBiasArgs, ProgressiveBiasEvaluator, TreePolicyMcts};
+
+type Mcts<'a> = TreePolicyMcts<'a, Nim, ProgressiveBiasEvaluator>;
+
+/// Returns the share of the seeds for which the search found the winning move after
+/// `iterations` iterations.
+fn success_rate(state: &Nim, weight: f64, iterations: usize) -> f64 {
+    let args = ProgressiveBiasArgs {
+        c: 2f64.sqrt(),
+        weight,
+    };
+    let seeds = 200;
+    let successes = (0..seeds)
+        .filter(|&seed| {
+            let mcts = Mcts::deterministic(state, seed);
+            for _ in 0..iterations {
+                mcts.execute(&args, ());
+            }
+            mcts.best_move(&args) == 1
+        })
+        .count();
+    successes as f64 / seeds as f64
+}
+
+fn main() {
+    // Taking 1 stick leaves 20 sticks, the only winning move.
+    let state = Nim::new(21);
+    println!("iterations  no bias  progressive bias");
+    for &iterations in &[25, 50, 100, 200, 400] {
+        println!(
+            "{:>10}  {:>7.2}  {:>16.2}",
+            iterations,
+            success_rate(&state, 0., iterations),
+            success_rate(&state, 10., iterations),
+        );
+    }
+}
@@ -6,7 +6,7 @@ use num_traits::FloatConst;
 use rand::thread_rng;
 use rayon::prelude::*;
 
-use oxymcts::{GameTrait, mcts_uct_agent, random_agent};
+use oxymcts::{GameResult, GameTrait, mcts_uct_agent, random_agent};
 
 #[derive(Debug, Clone, Default)]
 struct TicTacToe {
@@ -129,6 +129,14 @@ impl GameTrait for TicTacToe {
     fn get_winner(&self) -> Self::Player {
         TicTacToe::get_winner(self)
     }
+
+    fn terminal_result(&self) -> Option<GameResult<Self::Player>> {
+        match TicTacToe::get_winner(self) {
+            0 if self.legal_moves().is_empty() => Some(GameResult::Draw),
+            0 => None,
+            winner => Some(GameResult::Win(winner)),
+        }
+    }
 }
 
 impl Display for TicTacToe {
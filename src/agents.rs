@@ -1,19 +1,586 @@
-use rand::prelude::{SliceRandom, ThreadRng};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::{DefaultMcts, GameTrait};
+use noisy_float::prelude::n64;
+use rand::prelude::{SliceRandom, StdRng};
+use rand::{RngCore, SeedableRng};
 
+use crate::{
+    normal_quantile, BestMoveCriterion, DefaultMcts, FactoredGame, GameResult, GameTrait, Nat, Num,
+    StagedGame,
+};
+
+/// Searches during `playouts` iterations and returns the best move, a forced move is returned
+/// without searching.
 pub fn mcts_uct_agent<Game: GameTrait>(state: &Game, playouts: usize, c: f64) -> Game::Move {
-    let mut mcts = DefaultMcts::new(state);
+    mcts_uct_agent_with_rng(state, playouts, c, &mut StdRng::from_entropy())
+}
+
+/// Like `mcts_uct_agent`, but searches until `budget` has elapsed instead of during a fixed
+/// number of iterations, for the games played in real time. See
+/// `mcts_uct_agent_timed_with_iterations`.
+pub fn mcts_uct_agent_timed<Game: GameTrait>(state: &Game, budget: Duration, c: f64) -> Game::Move {
+    mcts_uct_agent_timed_with_iterations(state, budget, c).0
+}
+
+/// The number of iterations of `mcts_uct_agent_timed` between two reads of the clock.
+const TIME_CHECK_INTERVAL: usize = 16;
+
+/// Searches until `budget` has elapsed and returns the best move with the number of iterations
+/// executed, to tune the budget. The clock is read after the first iteration then every
+/// `TIME_CHECK_INTERVAL` iterations to keep its overhead low, so the search overruns the budget
+/// by at most that many iterations, and one iteration is executed even with a budget of zero so
+/// the root has a child to choose. A forced move is returned without searching, with 0
+/// iterations.
+pub fn mcts_uct_agent_timed_with_iterations<Game: GameTrait>(
+    state: &Game,
+    budget: Duration,
+    c: f64,
+) -> (Game::Move, usize) {
+    if state.legal_moves_count() == 1 {
+        return (state.legals_moves().remove(0), 0);
+    }
+    let start = Instant::now();
+    let mcts = DefaultMcts::new(state);
+    let mut iterations = 0;
+    loop {
+        mcts.execute(&c, ());
+        iterations += 1;
+        if (iterations - 1) % TIME_CHECK_INTERVAL == 0 && start.elapsed() >= budget {
+            break;
+        }
+    }
+    (mcts.best_move(&c), iterations)
+}
+
+/// Like `mcts_uct_agent`, but the move is chosen by `criterion`, for example
+/// `BestMoveCriterion::HighestMeanWithMinVisits` to ignore the children with too few visits.
+pub fn mcts_uct_agent_by<Game: GameTrait>(
+    state: &Game,
+    playouts: usize,
+    c: f64,
+    criterion: BestMoveCriterion,
+) -> Game::Move {
+    if state.legal_moves_count() == 1 {
+        return state.legals_moves().remove(0);
+    }
+    let mcts = DefaultMcts::new(state);
     for _ in 0..playouts {
         mcts.execute(&c, ());
     }
-    mcts.best_move(&c)
+    mcts.best_move_by(criterion)
+}
+
+/// Searches each state of `states` independently during `playouts` iterations, and returns the
+/// best move and the root value of each, see `LazyMcts::root_value`, in the order of the states.
+/// The states are split between `threads` threads, which can be 1 to search them one after the
+/// other. Each state must have a legal move.
+pub fn analyze_positions<Game>(
+    states: Vec<Game>,
+    playouts: usize,
+    c: f64,
+    threads: usize,
+) -> Vec<(Game::Move, Num)>
+    where
+        Game: GameTrait + Sync,
+        Game::Move: Send,
+{
+    let analyze = |state: &Game| {
+        let mcts = DefaultMcts::new(state);
+        for _ in 0..playouts {
+            mcts.execute(&c, ());
+        }
+        (mcts.best_move(&c), mcts.root_value().unwrap_or(n64(0.)))
+    };
+    let chunk_size = states.len().div_ceil(threads.max(1)).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = states
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(analyze).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
 }
 
-pub fn random_agent<Game: GameTrait>(state: &Game, thread_rng: &mut ThreadRng) -> Game::Move {
+/// Chooses a move of a factored game stage by stage: each stage is chosen by its own search of
+/// the `StagedGame` during `playouts` iterations, starting from the stages already chosen, until
+/// they complete a move.
+pub fn mcts_staged_agent<Game: FactoredGame>(state: &Game, playouts: usize, c: f64) -> Game::Move {
+    let mut staged = StagedGame::new(state.clone());
+    loop {
+        let stage = mcts_uct_agent(&staged, playouts, c);
+        staged.stages.push(stage);
+        if let Some(mv) = state.complete_move(&staged.stages) {
+            return mv;
+        }
+    }
+}
+
+/// Plays a legal move drawn uniformly by `rng`, e.g. a `rand::thread_rng()` or a seeded
+/// `StdRng` to replay the same moves.
+pub fn random_agent<Game: GameTrait>(state: &Game, rng: &mut dyn RngCore) -> Game::Move {
     state
         .legals_moves()
-        .choose(thread_rng)
+        .choose(rng)
         .unwrap()
         .clone()
 }
+
+/// Determinization for the games with hidden information: `worlds` states consistent with the
+/// `observation` are sampled by `sampler`, each is searched during `playouts_per_world`
+/// iterations, and the move with the most visits summed over all the worlds is returned. The
+/// hidden information is only sampled at the root, the search of a world sees all of its
+/// information, so information sets are not handled inside the tree. The worlds must have the
+/// same player to move.
+pub fn mcts_determinized_agent<Observation, Game: GameTrait>(
+    observation: &Observation,
+    sampler: impl Fn(&Observation, &mut dyn RngCore) -> Game,
+    rng: &mut dyn RngCore,
+    worlds: usize,
+    playouts_per_world: usize,
+    c: f64,
+) -> Game::Move
+    where
+        Game::Move: PartialEq,
+{
+    let mut visits: Vec<(Game::Move, Nat)> = vec![];
+    for _ in 0..worlds {
+        let world = sampler(observation, rng);
+        let mcts = DefaultMcts::new(&world);
+        for _ in 0..playouts_per_world {
+            mcts.execute(&c, ());
+        }
+        for (mv, n_visits) in mcts.move_visits() {
+            match visits.iter_mut().find(|(m, _)| *m == mv) {
+                Some((_, total)) => *total += n_visits,
+                None => visits.push((mv, n_visits)),
+            }
+        }
+    }
+    visits
+        .into_iter()
+        .max_by_key(|&(_, n_visits)| n_visits)
+        .expect("No move was searched, there must be at least one world")
+        .0
+}
+
+/// The tracer of `play_full_game`, called with each state of the game and the move played in it.
+pub type GameTracer<'a, Game> = &'a mut dyn FnMut(&Game, &<Game as GameTrait>::Move);
+
+/// Plays a game from `GameTrait::initial_state` until its end, see `play_full_game_from`, and
+/// returns its result. An agent is any function choosing a move of a state, e.g.
+/// `|state: &Game| mcts_uct_agent(state, 1000, 2f64.sqrt())`.
+pub fn play_full_game<Game: GameTrait>(
+    agent_a: impl FnMut(&Game) -> Game::Move,
+    agent_b: impl FnMut(&Game) -> Game::Move,
+    trace: Option<GameTracer<Game>>,
+) -> GameResult<Game::Player> {
+    play_full_game_from(&Game::initial_state(), agent_a, agent_b, trace)
+}
+
+/// Plays a game from `start` until its end and returns its result. `agent_a` plays for the
+/// player to move in `start` and `agent_b` for the others, each move being chosen by the agent of
+/// `GameTrait::player_turn`, so a player moving several times in a row keeps its agent. The
+/// `trace` is called before each move with the state and the move, e.g. to print the move in its
+/// `GameTrait::move_notation` with the player who played it.
+pub fn play_full_game_from<Game: GameTrait>(
+    start: &Game,
+    mut agent_a: impl FnMut(&Game) -> Game::Move,
+    mut agent_b: impl FnMut(&Game) -> Game::Move,
+    mut trace: Option<GameTracer<Game>>,
+) -> GameResult<Game::Player> {
+    let player_a = start.player_turn();
+    let mut state = start.clone();
+    while !state.is_final() {
+        let mv = if state.player_turn() == player_a {
+            agent_a(&state)
+        } else {
+            agent_b(&state)
+        };
+        if let Some(trace) = trace.as_mut() {
+            trace(&state, &mv);
+        }
+        state.do_move(&mv);
+    }
+    state
+        .terminal_result()
+        .expect("A final state has a result")
+}
+
+/// The results of a match between two agents, see `play_match`, counted for the first agent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchReport {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+/// The decision of a sequential probability ratio test, see `MatchReport::sprt`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtDecision {
+    /// The Elo difference is `elo0`, the match can stop.
+    H0,
+    /// The Elo difference is `elo1`, the match can stop.
+    H1,
+    /// More games are needed to decide.
+    Continue,
+}
+
+impl MatchReport {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Returns the mean points of the first agent per game, 1 per win and 0.5 per draw, or 0.5
+    /// before the first game.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.) / self.games() as f64
+    }
+
+    /// Returns the Elo difference between the first agent and the second estimated from the
+    /// score, infinite when an agent won or drew all the games and the other never won.
+    pub fn elo(&self) -> f64 {
+        elo_difference(self.score())
+    }
+
+    /// Returns the half width of the 95% confidence interval of `elo`, from the variance of the
+    /// points of the games carried to the Elo scale by the slope of `elo_difference` at the
+    /// score, or infinite with less than two games or when `elo` is.
+    pub fn elo_margin(&self) -> f64 {
+        let games = self.games() as f64;
+        let score = self.score();
+        if games < 2. || score <= 0. || score >= 1. {
+            return f64::INFINITY;
+        }
+        let score_margin = normal_quantile(0.975) * (self.variance() / games).sqrt();
+        score_margin * 400. / (std::f64::consts::LN_10 * score * (1. - score))
+    }
+
+    /// Returns the log-likelihood ratio of the Elo difference being `elo1` rather than `elo0`,
+    /// with the usual normal approximation of the scores of the games, or 0 while the points of
+    /// the games are all the same.
+    pub fn sprt_llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let variance = self.variance();
+        if self.games() == 0 || variance <= 0. {
+            return 0.;
+        }
+        let (score0, score1) = (expected_score(elo0), expected_score(elo1));
+        self.games() as f64 * (score1 - score0) * (2. * self.score() - score0 - score1)
+            / (2. * variance)
+    }
+
+    /// Returns the variance of the points of the games, 0 before the first game.
+    fn variance(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.;
+        }
+        let score = self.score();
+        (self.wins as f64 + self.draws as f64 / 4.) / self.games() as f64 - score * score
+    }
+
+    /// Runs the sequential probability ratio test of the Elo difference being `elo0` against
+    /// `elo1`, with the error rates `alpha` of accepting `H1` when `H0` holds and `beta` of
+    /// accepting `H0` when `H1` holds. A match can be played in small batches until it decides.
+    pub fn sprt(&self, elo0: f64, elo1: f64, alpha: f64, beta: f64) -> SprtDecision {
+        let llr = self.sprt_llr(elo0, elo1);
+        if llr >= ((1. - beta) / alpha).ln() {
+            SprtDecision::H1
+        } else if llr <= (beta / (1. - alpha)).ln() {
+            SprtDecision::H0
+        } else {
+            SprtDecision::Continue
+        }
+    }
+}
+
+/// Returns the Elo difference of the players for which `score` is expected.
+fn elo_difference(score: f64) -> f64 {
+    -400. * (1. / score - 1.).log10()
+}
+
+/// Returns the score expected by a player `elo` points stronger than its opponent.
+fn expected_score(elo: f64) -> f64 {
+    1. / (1. + 10f64.powf(-elo / 400.))
+}
+
+/// Plays `n_games` full games between two agents, see `play_full_game`, and counts the results of
+/// `agent_a`. With `alternate_colors` the agents start in turn, `agent_a` starting the first
+/// game, otherwise `agent_a` starts all of them. The games are split between `threads` threads,
+/// which can be 1 to play them one after the other, so the agents are shared between the
+/// threads and choose their moves without mutable state, drawing their random numbers from
+/// `rand::thread_rng()` for example.
+pub fn play_match<Game, A, B>(
+    agent_a: A,
+    agent_b: B,
+    n_games: usize,
+    alternate_colors: bool,
+    threads: usize,
+) -> MatchReport
+    where
+        Game: GameTrait,
+        A: Fn(&Game) -> Game::Move + Sync,
+        B: Fn(&Game) -> Game::Move + Sync,
+{
+    let threads = threads.max(1);
+    let play = |game: usize| {
+        let a_starts = !alternate_colors || game.is_multiple_of(2);
+        let start = Game::initial_state();
+        let first = |state: &Game| if a_starts { agent_a(state) } else { agent_b(state) };
+        let second = |state: &Game| if a_starts { agent_b(state) } else { agent_a(state) };
+        let result = play_full_game_from(&start, first, second, None);
+        // The points of the player the first agent played for.
+        let first_points = result.reward(&start.player_turn());
+        if a_starts {
+            first_points
+        } else {
+            1. - first_points
+        }
+    };
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|thread| {
+                let play = &play;
+                scope.spawn(move || {
+                    let mut report = MatchReport::default();
+                    for game in (thread..n_games).step_by(threads) {
+                        let points = play(game);
+                        if points > 0.5 {
+                            report.wins += 1;
+                        } else if points < 0.5 {
+                            report.losses += 1;
+                        } else {
+                            report.draws += 1;
+                        }
+                    }
+                    report
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .fold(MatchReport::default(), |total, report| MatchReport {
+                wins: total.wins + report.wins,
+                draws: total.draws + report.draws,
+                losses: total.losses + report.losses,
+            })
+    })
+}
+
+/// Tunes the exploration constant of `mcts_uct_agent` for a game by a round-robin tournament:
+/// each pair of `candidates` plays a `play_match` of `games_per_pairing` games, both agents
+/// searching during `playouts` iterations with their own constant, and starting in turn.
+/// Returns the candidate scoring the most points over all its games, 1 per win and 0.5 per
+/// draw, with the win matrix whose cell `[i][j]` holds the points of candidate `i` against
+/// candidate `j`. The searches draw their random numbers from generators seeded by `seed`, so
+/// the tournament can be reproduced for a deterministic game. The first candidate wins ties.
+///
+/// # Panics
+///
+/// Panics if there is no candidate.
+pub fn tune_exploration<Game: GameTrait>(
+    candidates: &[f64],
+    games_per_pairing: usize,
+    playouts: usize,
+    seed: u64,
+) -> (f64, Vec<Vec<f64>>) {
+    assert!(!candidates.is_empty(), "There must be at least one candidate");
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut wins = vec![vec![0.; candidates.len()]; candidates.len()];
+    for i in 0..candidates.len() {
+        for j in i + 1..candidates.len() {
+            let pairing_rng = Mutex::new(StdRng::seed_from_u64(rng.next_u64()));
+            let agent = |c: f64| {
+                let pairing_rng = &pairing_rng;
+                move |state: &Game| {
+                    mcts_uct_agent_with_rng(state, playouts, c, &mut *pairing_rng.lock().unwrap())
+                }
+            };
+            let report = play_match(
+                agent(candidates[i]),
+                agent(candidates[j]),
+                games_per_pairing,
+                true,
+                1,
+            );
+            wins[i][j] = report.wins as f64 + report.draws as f64 / 2.;
+            wins[j][i] = report.losses as f64 + report.draws as f64 / 2.;
+        }
+    }
+    let points: Vec<f64> = wins.iter().map(|row| row.iter().sum()).collect();
+    let best = (0..candidates.len()).fold(0, |best, i| {
+        if points[i] > points[best] {
+            i
+        } else {
+            best
+        }
+    });
+    (candidates[best], wins)
+}
+
+/// Like `mcts_uct_agent`, with the search seeded by a number drawn from `rng`, so a seeded `rng`
+/// makes the agent play the same moves in the same states, see `LazyMcts::deterministic`.
+pub fn mcts_uct_agent_with_rng<Game: GameTrait>(
+    state: &Game,
+    playouts: usize,
+    c: f64,
+    rng: &mut dyn RngCore,
+) -> Game::Move {
+    if state.legal_moves_count() == 1 {
+        return state.legals_moves().remove(0);
+    }
+    let mcts = DefaultMcts::deterministic(state, rng.next_u64());
+    for _ in 0..playouts {
+        mcts.execute(&c, ());
+    }
+    mcts.best_move(&c)
+}
+
+#[test]
+fn test_tune_exploration() {
+    use crate::test_game::Nim;
+
+    let candidates = [0., 0.5, 2f64.sqrt()];
+    let (best, wins) = tune_exploration::<Nim>(&candidates, 4, 30, 0);
+    assert!(candidates.contains(&best));
+    for (i, row) in wins.iter().enumerate() {
+        assert_eq!(row[i], 0.);
+        for (j, &points) in row.iter().enumerate().filter(|&(j, _)| j != i) {
+            assert_eq!(points + wins[j][i], 4.);
+        }
+    }
+    assert_eq!(tune_exploration::<Nim>(&candidates, 4, 30, 0), (best, wins));
+
+    // A huge constant spreads the iterations over all the moves and misses the wins, the first
+    // candidate winning ties would be kept if tuning did not compare the candidates.
+    let (best, wins) = tune_exploration::<Nim>(&[100., 2f64.sqrt()], 6, 100, 0);
+    assert_eq!(best, 2f64.sqrt());
+    assert!(wins[1][0] > wins[0][1]);
+}
+
+#[test]
+fn test_mcts_uct_agent_timed() {
+    use crate::test_game::Nim;
+
+    let (mv, iterations) =
+        mcts_uct_agent_timed_with_iterations(&Nim::new(10), Duration::ZERO, 2f64.sqrt());
+    assert!((1..=3).contains(&mv));
+    assert_eq!(iterations, 1);
+
+    let budget = Duration::from_millis(20);
+    let start = Instant::now();
+    let (_, iterations) = mcts_uct_agent_timed_with_iterations(&Nim::new(10), budget, 2f64.sqrt());
+    assert!(start.elapsed() >= budget);
+    assert!(iterations > 1);
+    // The clock was read after the first iteration and then every `TIME_CHECK_INTERVAL`.
+    assert_eq!((iterations - 1) % TIME_CHECK_INTERVAL, 0);
+    assert_eq!(mcts_uct_agent_timed(&Nim::new(1), budget, 2f64.sqrt()), 1);
+}
+
+#[test]
+fn test_play_full_game() {
+    use crate::test_game::Nim;
+
+    let greedy = |state: &Nim| state.sticks.min(3);
+    let cautious = |_: &Nim| 1;
+    let mut trace = vec![];
+    let result = play_full_game(
+        greedy,
+        cautious,
+        Some(&mut |state: &Nim, &mv: &u8| trace.push((state.player_turn(), mv))),
+    );
+    // 10 -> 7 -> 6 -> 3 -> 2 -> 0, the greedy agent takes the last stick.
+    assert_eq!(result, GameResult::Win(1));
+    assert_eq!(trace, [(1, 3), (2, 1), (1, 3), (2, 1), (1, 2)]);
+    // 10 -> 9 -> 6 -> 5 -> 2 -> 1 -> 0
+    assert_eq!(play_full_game(cautious, greedy, None), GameResult::Win(2));
+    // The first agent plays for the player to move in the start state, here the second one.
+    let start = Nim { sticks: 10, turn: 2 };
+    assert_eq!(play_full_game_from(&start, greedy, cautious, None), GameResult::Win(2));
+
+    let mut rng = rand::thread_rng();
+    let result = play_full_game(
+        |state: &Nim| mcts_uct_agent(state, 100, 2f64.sqrt()),
+        |state: &Nim| random_agent(state, &mut rng),
+        None,
+    );
+    assert!(matches!(result, GameResult::Win(1 | 2)));
+}
+
+#[test]
+fn test_forced_moves_skip_the_search() {
+    use crate::test_game::Nim;
+
+    for sticks in 1..5 {
+        let state = Nim::new(sticks);
+        assert_eq!(state.legal_moves_count(), state.legals_moves().len());
+    }
+    // A single stick leaves a single move, returned at once whatever the budget.
+    assert_eq!(mcts_uct_agent(&Nim::new(1), usize::MAX, 2f64.sqrt()), 1);
+}
+
+#[test]
+fn test_seeded_agents_replay_their_games() {
+    use crate::test_game::Nim;
+
+    let play = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut moves = vec![];
+        let mut state = Nim::new(20);
+        while !state.is_final() {
+            let mv = if moves.len() % 2 == 0 {
+                mcts_uct_agent_with_rng(&state, 20, 2f64.sqrt(), &mut rng)
+            } else {
+                random_agent(&state, &mut rng)
+            };
+            state.do_move(&mv);
+            moves.push(mv);
+        }
+        moves
+    };
+    assert_eq!(play(0), play(0));
+    assert!((0..10).any(|seed| play(seed) != play(0)));
+}
+
+#[test]
+fn test_play_match() {
+    use crate::test_game::Nim;
+
+    let greedy = |state: &Nim| state.sticks.min(3);
+    let cautious = |_: &Nim| 1;
+    // The greedy agent wins from 10 sticks whoever starts, see `test_play_full_game`.
+    let report = play_match(greedy, cautious, 5, true, 2);
+    assert_eq!(report, MatchReport { wins: 5, draws: 0, losses: 0 });
+    assert_eq!(report.elo(), f64::INFINITY);
+    assert_eq!(play_match(cautious, greedy, 3, false, 1).losses, 3);
+
+    let report = play_match(
+        |state: &Nim| mcts_uct_agent(state, 50, 2f64.sqrt()),
+        |state: &Nim| random_agent(state, &mut rand::thread_rng()),
+        6,
+        true,
+        3,
+    );
+    assert_eq!(report.games(), 6);
+
+    let report = MatchReport { wins: 300, draws: 0, losses: 100 };
+    assert!((report.score() - 0.75).abs() < 1e-12);
+    assert!((report.elo() - 190.85).abs() < 0.01);
+    assert!(report.elo_margin() > 0. && report.elo_margin() < 50.);
+    assert_eq!(report.sprt(0., 50., 0.05, 0.05), SprtDecision::H1);
+    let even = MatchReport { wins: 200, draws: 0, losses: 200 };
+    assert_eq!(even.elo(), 0.);
+    assert_eq!(even.sprt(0., 50., 0.05, 0.05), SprtDecision::H0);
+    let lopsided = MatchReport { wins: 9, draws: 0, losses: 1 };
+    assert!(lopsided.elo_margin().is_finite() && lopsided.elo_margin() > 0.);
+    assert_eq!(MatchReport { wins: 5, draws: 0, losses: 0 }.elo_margin(), f64::INFINITY);
+    let short = MatchReport { wins: 1, draws: 0, losses: 1 };
+    assert_eq!(short.sprt(0., 50., 0.05, 0.05), SprtDecision::Continue);
+}
@@ -0,0 +1,60 @@
+use noisy_float::prelude::n64;
+use num_traits::ToPrimitive;
+
+use crate::aliases::{Nat, Num};
+use crate::mcts_node::MctsNode;
+
+/// How `LazyMcts::best_move_by` chooses the move to play among the children of the root.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BestMoveCriterion {
+    /// The most visited child, the robust child. Visits are often tied early in the search or in
+    /// balanced positions, the tie is broken by the mean reward.
+    MostVisited,
+    /// The child with the highest mean reward.
+    HighestMean,
+    /// The child maximizing `mean - k * standard deviation` of its rewards, a positive `k`
+    /// prefers reliable moves and a negative `k` prefers gambles. Only the children with at
+    /// least 2 visits are considered, if there is none the most visited child is chosen.
+    RiskAdjusted(f64),
+    /// The child with the highest mean reward among those with at least the given visits, so a
+    /// child lucky in its few visits isn't chosen in a short search. If there is none the most
+    /// visited child is chosen.
+    HighestMeanWithMinVisits(Nat),
+    /// The child maximizing the lower confidence bound `mean - a / sqrt(visits)` of its reward,
+    /// the secure child: the mean of a child with few visits is discounted, more for a larger
+    /// `a`, and `a = 0` is the highest mean.
+    HighestLowerBound(f64),
+}
+
+impl BestMoveCriterion {
+    /// Returns the score of the child for this criterion, the best move is the child with the
+    /// highest score. Returns `None` if the child can't be chosen with this criterion.
+    pub(crate) fn score<T, M, R, A>(&self, child: &MctsNode<T, M, R, A>) -> Option<Num>
+    where
+        T: Clone,
+        M: Clone,
+        R: Clone + ToPrimitive,
+        A: Clone + Default,
+    {
+        match *self {
+            BestMoveCriterion::MostVisited => Some(n64(f64::from(child.n_visits))),
+            BestMoveCriterion::HighestMean => child.mean_reward(),
+            BestMoveCriterion::RiskAdjusted(k) => {
+                let mean = child.mean_reward()?;
+                child
+                    .reward_variance()
+                    .map(|variance| mean - k * variance.sqrt())
+            }
+            BestMoveCriterion::HighestMeanWithMinVisits(min_visits) => {
+                if child.n_visits >= min_visits {
+                    child.mean_reward()
+                } else {
+                    None
+                }
+            }
+            BestMoveCriterion::HighestLowerBound(a) => child
+                .mean_reward()
+                .map(|mean| mean - a / f64::from(child.n_visits).sqrt()),
+        }
+    }
+}
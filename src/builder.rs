@@ -0,0 +1,428 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::aliases::Num;
+use crate::best_move::BestMoveCriterion;
+use crate::chance::{ChanceBackProp, ChanceInfo, ChancePlayout, ChanceTreePolicy};
+use crate::defaults::{
+    DefaultBackProp, DefaultLazyTreePolicy, DefaultPlayout, DepthCutoff, DepthCutoffPlayout,
+    GameResultEvaluator, MaxBackProp, MinimaxBackProp, NoPlayout,
+};
+use crate::heavy_playout::{DecisivePlayout, WeightedRandomArgs, WeightedRandomPlayout};
+use crate::rave::{RaveArgs, RaveBackProp, RaveEvaluator, RaveInfo, RaveTreePolicy};
+use crate::solver::{SolverBackProp, SolverEvaluator, SolverInfo, SolverTreePolicy};
+use crate::traits::{BackPropPolicy, Evaluator, GameTrait, LazyTreePolicy, Playout};
+use crate::tree_search::{LazyMcts, RootMoveInfo, SearchLimits, SearchRun};
+use crate::widening::{ProgressiveWideningTreePolicy, WideningArgs, WideningEvaluator};
+
+/// The tree policy of a search built by the `MctsBuilder`, whose end states are evaluated by the
+/// `GameResultEvaluator` unless it has its own evaluator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TreePolicyKind {
+    /// The `DefaultLazyTreePolicy` with UCT and the exploration constant `c`.
+    Uct { c: f64 },
+    /// The `ProgressiveWideningTreePolicy` with UCT, see the `WideningArgs`.
+    ProgressiveWidening { c: f64, k: f64, alpha: f64 },
+    /// The `RaveTreePolicy` with the `RaveEvaluator` instead, see the `RaveArgs`, and its own
+    /// `RaveBackProp`.
+    Rave { c: f64, k: f64 },
+    /// The `SolverTreePolicy` with the `SolverEvaluator` instead and its own `SolverBackProp`.
+    Solver { c: f64 },
+    /// The `ChanceTreePolicy` with UCT and its own `ChanceBackProp`, for the games with chance
+    /// nodes, usually with the `PlayoutKind::Chance`.
+    Chance { c: f64 },
+}
+
+type UctPolicy<State> = DefaultLazyTreePolicy<State, GameResultEvaluator, (), f64>;
+type WideningPolicy<State> = ProgressiveWideningTreePolicy<State, GameResultEvaluator, (), f64>;
+
+/// The playout policy of a search built by the `MctsBuilder`.
+#[derive(Debug, Clone)]
+pub enum PlayoutKind<State: GameTrait> {
+    /// The `DefaultPlayout`.
+    Random,
+    /// The `DepthCutoffPlayout`.
+    DepthCutoff(DepthCutoff),
+    /// The `WeightedRandomPlayout`.
+    WeightedRandom(WeightedRandomArgs<State>),
+    /// The `DecisivePlayout` stopping after the given number of moves, if any.
+    Decisive(Option<usize>),
+    /// The `ChancePlayout`.
+    Chance,
+    /// The `NoPlayout`.
+    None,
+}
+
+/// The backprop policy of a search built by the `MctsBuilder`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackPropKind {
+    /// The `DefaultBackProp`.
+    Classic,
+    /// The `MaxBackProp`.
+    Max,
+    /// The `MinimaxBackProp`.
+    Minimax,
+}
+
+/// A search whose policies were chosen at runtime by the `MctsBuilder`, with its arguments.
+/// The methods are those of the `LazyMcts` it wraps, with the arguments given to the builder.
+pub trait Search<State: GameTrait> {
+    fn execute(&self);
+
+    fn run(&self, limits: SearchLimits) -> SearchRun;
+
+    /// Returns the best child of the root for the tree policy, see `LazyMcts::best_move`.
+    fn best_move(&self) -> State::Move;
+
+    fn best_move_by(&self, criterion: BestMoveCriterion) -> State::Move;
+
+    fn root_stats(&self) -> Vec<RootMoveInfo<State::Move>>;
+
+    fn root_value(&self) -> Option<Num>;
+
+    /// Moves the root to the move for which `matches` returns true, see
+    /// `LazyMcts::advance_root_matching`.
+    fn advance_root_matching(&mut self, matches: &dyn Fn(&State::Move) -> bool);
+
+    fn node_count(&self) -> usize;
+}
+
+/// Builds a search from policies chosen at runtime, e.g. read from a configuration file, instead
+/// of the type parameters of the `LazyMcts`, which stays the zero-cost way to compose a search
+/// and the only way to use the policies given as types, like the `PriorPolicy` of the PUCT, or
+/// the other evaluators of the crate. The rewards are floats. Defaults to UCT with an exploration constant of
+/// `sqrt(2)`, random playouts and the classic backpropagation, with a random seed.
+///
+/// ```ignore
+/// let search = MctsBuilder::new()
+///     .tree_policy(TreePolicyKind::Uct { c: 1. })
+///     .playout(PlayoutKind::Decisive(None))
+///     .backprop(BackPropKind::Classic)
+///     .build(&state);
+/// search.run(SearchLimits {
+///     iterations: Some(10000),
+///     ..Default::default()
+/// });
+/// let mv = search.best_move();
+/// ```
+#[derive(Debug, Clone)]
+pub struct MctsBuilder<State: GameTrait> {
+    tree_policy: TreePolicyKind,
+    playout: PlayoutKind<State>,
+    backprop: BackPropKind,
+    seed: Option<u64>,
+}
+
+impl<State: GameTrait> Default for MctsBuilder<State> {
+    fn default() -> Self {
+        MctsBuilder {
+            tree_policy: TreePolicyKind::Uct { c: 2f64.sqrt() },
+            playout: PlayoutKind::Random,
+            backprop: BackPropKind::Classic,
+            seed: None,
+        }
+    }
+}
+
+impl<State: GameTrait> MctsBuilder<State> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tree_policy(mut self, tree_policy: TreePolicyKind) -> Self {
+        self.tree_policy = tree_policy;
+        self
+    }
+
+    pub fn playout(mut self, playout: PlayoutKind<State>) -> Self {
+        self.playout = playout;
+        self
+    }
+
+    pub fn backprop(mut self, backprop: BackPropKind) -> Self {
+        self.backprop = backprop;
+        self
+    }
+
+    /// Seeds the random choices of the search, see `LazyMcts::deterministic`.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Builds the search from `root_state`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree policy has its own backprop, like the `TreePolicyKind::Solver`, and
+    /// another backprop than the `BackPropKind::Classic` is chosen.
+    pub fn build<'a>(self, root_state: &'a State) -> Box<dyn Search<State> + 'a>
+    where
+        State: 'a,
+        State::Move: PartialEq,
+    {
+        match self.tree_policy {
+            TreePolicyKind::Uct { c } => {
+                self.with_backprop::<UctPolicy<State>, GameResultEvaluator>(root_state, c)
+            }
+            TreePolicyKind::ProgressiveWidening { c, k, alpha } => self
+                .with_backprop::<WideningPolicy<State>, WideningEvaluator<GameResultEvaluator>>(
+                    root_state,
+                    WideningArgs { k, alpha, args: c },
+                ),
+            TreePolicyKind::Rave { c, k } => self
+                .with_own_backprop::<RaveTreePolicy<State>, RaveEvaluator, RaveBackProp, RaveInfo>(
+                    root_state,
+                    RaveArgs { c, k },
+                ),
+            TreePolicyKind::Solver { c } => self.with_own_backprop::<
+                SolverTreePolicy<State>,
+                SolverEvaluator,
+                SolverBackProp,
+                SolverInfo,
+            >(root_state, c),
+            TreePolicyKind::Chance { c } => self.with_own_backprop::<
+                ChanceTreePolicy<State, GameResultEvaluator>,
+                GameResultEvaluator,
+                ChanceBackProp,
+                ChanceInfo,
+            >(root_state, c),
+        }
+    }
+
+    fn with_backprop<'a, TP, EV>(
+        self,
+        root_state: &'a State,
+        evaluator_args: EV::Args,
+    ) -> Box<dyn Search<State> + 'a>
+    where
+        State: 'a,
+        TP: LazyTreePolicy<State, EV, (), f64> + 'a,
+        EV: Evaluator<State, f64, (), EvalResult = f64> + 'a,
+    {
+        match self.backprop {
+            BackPropKind::Classic => {
+                self.with_policies::<TP, EV, DefaultBackProp, ()>(root_state, evaluator_args)
+            }
+            BackPropKind::Max => {
+                self.with_policies::<TP, EV, MaxBackProp, ()>(root_state, evaluator_args)
+            }
+            BackPropKind::Minimax => {
+                self.with_policies::<TP, EV, MinimaxBackProp, ()>(root_state, evaluator_args)
+            }
+        }
+    }
+
+    fn with_own_backprop<'a, TP, EV, BP, A>(
+        self,
+        root_state: &'a State,
+        evaluator_args: EV::Args,
+    ) -> Box<dyn Search<State> + 'a>
+    where
+        State: 'a,
+        TP: LazyTreePolicy<State, EV, A, f64> + 'a,
+        EV: Evaluator<State, f64, A> + 'a,
+        BP: BackPropPolicy<Vec<State::Move>, State::Move, f64, A, EV::EvalResult> + 'a,
+        A: Clone + Default + 'a,
+    {
+        assert_eq!(
+            self.backprop,
+            BackPropKind::Classic,
+            "The tree policy {:?} has its own backprop",
+            self.tree_policy
+        );
+        self.with_policies::<TP, EV, BP, A>(root_state, evaluator_args)
+    }
+
+    fn with_policies<'a, TP, EV, BP, A>(
+        self,
+        root_state: &'a State,
+        evaluator_args: EV::Args,
+    ) -> Box<dyn Search<State> + 'a>
+    where
+        State: 'a,
+        TP: LazyTreePolicy<State, EV, A, f64> + 'a,
+        EV: Evaluator<State, f64, A> + 'a,
+        BP: BackPropPolicy<Vec<State::Move>, State::Move, f64, A, EV::EvalResult> + 'a,
+        A: Clone + Default + 'a,
+    {
+        let seed = self.seed;
+        match self.playout {
+            PlayoutKind::Random => {
+                Self::finish::<TP, EV, DefaultPlayout, BP, A>(root_state, evaluator_args, (), seed)
+            }
+            PlayoutKind::DepthCutoff(cutoff) => Self::finish::<TP, EV, DepthCutoffPlayout, BP, A>(
+                root_state,
+                evaluator_args,
+                cutoff,
+                seed,
+            ),
+            PlayoutKind::WeightedRandom(args) => {
+                Self::finish::<TP, EV, WeightedRandomPlayout, BP, A>(
+                    root_state,
+                    evaluator_args,
+                    args,
+                    seed,
+                )
+            }
+            PlayoutKind::Decisive(max_moves) => Self::finish::<TP, EV, DecisivePlayout, BP, A>(
+                root_state,
+                evaluator_args,
+                max_moves,
+                seed,
+            ),
+            PlayoutKind::Chance => {
+                Self::finish::<TP, EV, ChancePlayout, BP, A>(root_state, evaluator_args, (), seed)
+            }
+            PlayoutKind::None => {
+                Self::finish::<TP, EV, NoPlayout, BP, A>(root_state, evaluator_args, (), seed)
+            }
+        }
+    }
+
+    fn finish<'a, TP, EV, PP, BP, A>(
+        root_state: &'a State,
+        evaluator_args: EV::Args,
+        playout_args: PP::Args,
+        seed: Option<u64>,
+    ) -> Box<dyn Search<State> + 'a>
+    where
+        State: 'a,
+        TP: LazyTreePolicy<State, EV, A, f64> + 'a,
+        EV: Evaluator<State, f64, A> + 'a,
+        PP: Playout<State> + 'a,
+        PP::Args: Clone,
+        BP: BackPropPolicy<Vec<State::Move>, State::Move, f64, A, EV::EvalResult> + 'a,
+        A: Clone + Default + 'a,
+    {
+        let mcts = match seed {
+            Some(seed) => LazyMcts::with_rng(root_state, 0, StdRng::seed_from_u64(seed)),
+            None => LazyMcts::new(root_state),
+        };
+        Box::new(BuiltSearch::<TP, PP, BP, EV, A, State> {
+            mcts,
+            evaluator_args,
+            playout_args,
+        })
+    }
+}
+
+struct BuiltSearch<'a, TP, PP, BP, EV, A, State>
+where
+    State: GameTrait,
+    TP: LazyTreePolicy<State, EV, A, f64>,
+    PP: Playout<State>,
+    BP: BackPropPolicy<Vec<State::Move>, State::Move, f64, A, EV::EvalResult>,
+    EV: Evaluator<State, f64, A>,
+    A: Clone + Default,
+{
+    mcts: LazyMcts<'a, State, TP, PP, BP, EV, A, f64>,
+    evaluator_args: EV::Args,
+    playout_args: PP::Args,
+}
+
+impl<State, TP, PP, BP, EV, A> Search<State> for BuiltSearch<'_, TP, PP, BP, EV, A, State>
+where
+    State: GameTrait,
+    TP: LazyTreePolicy<State, EV, A, f64>,
+    PP: Playout<State>,
+    PP::Args: Clone,
+    BP: BackPropPolicy<Vec<State::Move>, State::Move, f64, A, EV::EvalResult>,
+    EV: Evaluator<State, f64, A>,
+    A: Clone + Default,
+{
+    fn execute(&self) {
+        self.mcts
+            .execute(&self.evaluator_args, self.playout_args.clone())
+    }
+
+    fn run(&self, limits: SearchLimits) -> SearchRun {
+        self.mcts
+            .run(limits, &self.evaluator_args, self.playout_args.clone())
+    }
+
+    fn best_move(&self) -> State::Move {
+        self.mcts.best_move(&self.evaluator_args)
+    }
+
+    fn best_move_by(&self, criterion: BestMoveCriterion) -> State::Move {
+        self.mcts.best_move_by(criterion)
+    }
+
+    fn root_stats(&self) -> Vec<RootMoveInfo<State::Move>> {
+        self.mcts.root_stats()
+    }
+
+    fn root_value(&self) -> Option<Num> {
+        self.mcts.root_value()
+    }
+
+    fn advance_root_matching(&mut self, matches: &dyn Fn(&State::Move) -> bool) {
+        self.mcts.advance_root_matching(matches)
+    }
+
+    fn node_count(&self) -> usize {
+        self.mcts.node_count()
+    }
+}
+
+#[test]
+fn test_builder_chooses_the_policies_at_runtime() {
+    use std::sync::Arc;
+
+    use crate::test_game::Nim;
+
+    let state = Nim::new(5);
+    let builders = vec![
+        MctsBuilder::new(),
+        MctsBuilder::new()
+            .playout(PlayoutKind::Decisive(Some(10)))
+            .backprop(BackPropKind::Minimax),
+        MctsBuilder::new()
+            .tree_policy(TreePolicyKind::ProgressiveWidening {
+                c: 1.,
+                k: 2.,
+                alpha: 0.5,
+            })
+            .playout(PlayoutKind::WeightedRandom(WeightedRandomArgs {
+                score: Arc::new(|_, _| 1.),
+                max_moves: None,
+            })),
+        MctsBuilder::new()
+            .backprop(BackPropKind::Max)
+            .playout(PlayoutKind::DepthCutoff(DepthCutoff {
+                max_moves: 100,
+                max_extension: 0,
+            })),
+        MctsBuilder::new().tree_policy(TreePolicyKind::Rave { c: 1., k: 100. }),
+        MctsBuilder::new().tree_policy(TreePolicyKind::Solver { c: 1. }),
+        MctsBuilder::new()
+            .tree_policy(TreePolicyKind::Chance { c: 1. })
+            .playout(PlayoutKind::Chance),
+    ];
+    for builder in builders {
+        let mut search = builder.seed(0).build(&state);
+        let run = search.run(SearchLimits {
+            iterations: Some(1000),
+            ..Default::default()
+        });
+        assert_eq!(run.iterations, 1000);
+        assert!((1..=3).contains(&search.best_move()));
+        assert!((1..=3).contains(&search.best_move_by(BestMoveCriterion::MostVisited)));
+        assert_eq!(search.root_stats().len(), 3);
+        search.advance_root_matching(&|&mv| mv == 1);
+        search.execute();
+        assert!(search.node_count() > 1);
+    }
+}
+
+#[test]
+#[should_panic(expected = "has its own backprop")]
+fn test_builder_refuses_another_backprop_for_the_solver() {
+    use crate::test_game::Nim;
+
+    MctsBuilder::new()
+        .tree_policy(TreePolicyKind::Solver { c: 1. })
+        .backprop(BackPropKind::Max)
+        .build(&Nim::new(5));
+}
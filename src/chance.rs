@@ -0,0 +1,273 @@
+use std::marker::PhantomData;
+
+use ego_tree::{NodeId, Tree};
+use rand::distributions::WeightedIndex;
+use rand::prelude::SliceRandom;
+use rand::{Rng, RngCore};
+
+use crate::aliases::LazyMctsTree;
+use crate::defaults::{DefaultBackProp, DefaultLazyTreePolicy};
+use crate::mcts_node::{historic, MctsNode};
+use crate::traits::{BackPropPolicy, Evaluator, GameTrait, LazyTreePolicy, Playout};
+
+/// The additional info of the nodes of a search with chance nodes, see the `ChanceTreePolicy`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChanceInfo {
+    /// Whether the node is a chance node, set by the `ChanceTreePolicy` when it visits the node.
+    pub chance: bool,
+    /// The probability of the outcome leading to the node, 1 for the root and for the children
+    /// of a decision node.
+    pub probability: f64,
+}
+
+impl Default for ChanceInfo {
+    fn default() -> Self {
+        ChanceInfo {
+            chance: false,
+            probability: 1.,
+        }
+    }
+}
+
+/// Selects like the `DefaultLazyTreePolicy` in the decision nodes, but in a chance node, whose
+/// `GameTrait::chance_outcomes` aren't empty, draws an outcome by its probability and goes down
+/// to its child, expanding it if it's not yet, so each outcome is visited in proportion to its
+/// probability instead of being chosen by the evaluator. The outcomes must be the legal moves of
+/// the chance node. Use it with the `ChanceBackProp`, which values the chance nodes by the
+/// expectation of their outcomes, and for example the `ChancePlayout`.
+pub struct ChanceTreePolicy<State, EV> {
+    _marker: PhantomData<(State, EV)>,
+}
+
+impl<State, EV> LazyTreePolicy<State, EV, ChanceInfo, f64> for ChanceTreePolicy<State, EV>
+where
+    State: GameTrait,
+    State::Move: PartialEq,
+    EV: Evaluator<State, f64, ChanceInfo>,
+{
+    fn tree_policy(
+        tree: &mut LazyMctsTree<State, f64, ChanceInfo>,
+        mut state: State,
+        evaluator_args: &EV::Args,
+        rng: &mut dyn RngCore,
+    ) -> (NodeId, State) {
+        let turn = state.player_turn();
+        let mut node_id = tree.root().id();
+        loop {
+            let outcomes = state.chance_outcomes();
+            let mut node = tree.get_mut(node_id).unwrap();
+            node.value().additional_info.chance = !outcomes.is_empty();
+            let node = tree.get(node_id).unwrap();
+            let (index, probability) = if !outcomes.is_empty() {
+                let weights = WeightedIndex::new(outcomes.iter().map(|&(_, p)| p))
+                    .expect("The probabilities of the chance outcomes must be positive");
+                let (outcome, probability) = &outcomes[rng.sample(weights)];
+                let total: f64 = outcomes.iter().map(|&(_, p)| p).sum();
+                let child = node
+                    .children()
+                    .find(|child| child.value().state.last() == Some(outcome));
+                if let Some(child) = child {
+                    state.do_move(outcome);
+                    node_id = child.id();
+                    continue;
+                }
+                let index = node
+                    .value()
+                    .unvisited_moves
+                    .iter()
+                    .position(|mv| mv == outcome)
+                    .expect("The chance outcomes must be legal moves");
+                (index, probability / total)
+            } else if node.value().can_add_child() {
+                (rng.gen_range(0..node.value().unvisited_moves.len()), 1.)
+            } else if node.has_children() {
+                node_id = Self::best_child(tree, &turn, node_id, evaluator_args);
+                state.do_move(tree.get(node_id).unwrap().value().state.last().unwrap());
+                continue;
+            } else {
+                return (node_id, state);
+            };
+            let historic = historic(node);
+            let (new_id, new_state) =
+                DefaultLazyTreePolicy::<State, EV, ChanceInfo, f64>::expand_move(
+                    tree.get_mut(node_id).unwrap(),
+                    state,
+                    historic,
+                    index,
+                );
+            tree.get_mut(new_id)
+                .unwrap()
+                .value()
+                .additional_info
+                .probability = probability;
+            return (new_id, new_state);
+        }
+    }
+
+    fn best_child(
+        tree: &LazyMctsTree<State, f64, ChanceInfo>,
+        turn: &State::Player,
+        parent_id: NodeId,
+        evaluator_args: &EV::Args,
+    ) -> NodeId {
+        DefaultLazyTreePolicy::<State, EV, ChanceInfo, f64>::best_child(
+            tree,
+            turn,
+            parent_id,
+            evaluator_args,
+        )
+    }
+}
+
+/// Backpropagates the reward like `DefaultBackProp`, then values each chance node of the branch
+/// by the expectation of its outcomes: its mean reward becomes the mean reward of its visited
+/// children weighted by their probability, normalized by the probability of the visited
+/// outcomes, instead of the mean of the rewards drawn through it. The sum of squared rewards of
+/// the chance nodes still counts the sampled rewards. The rewards are floats.
+pub struct ChanceBackProp;
+
+impl<Move: Clone> BackPropPolicy<Vec<Move>, Move, f64, ChanceInfo, f64> for ChanceBackProp {
+    fn backprop(
+        tree: &mut Tree<MctsNode<Vec<Move>, Move, f64, ChanceInfo>>,
+        leaf: NodeId,
+        reward: f64,
+    ) {
+        DefaultBackProp::backprop(tree, leaf, reward);
+        let mut current_node_id = tree.get(leaf).unwrap().parent().map(|parent| parent.id());
+        while let Some(node_id) = current_node_id {
+            let node = tree.get(node_id).unwrap();
+            current_node_id = node.parent().map(|parent| parent.id());
+            if !node.value().additional_info.chance {
+                continue;
+            }
+            let (expectation, probability) = node
+                .children()
+                .filter_map(|child| {
+                    let value = child.value();
+                    let mean = value.mean_reward()?.raw();
+                    let probability = value.additional_info.probability;
+                    Some((mean * probability, probability))
+                })
+                .fold((0., 0.), |(e, p), (mean, probability)| {
+                    (e + mean, p + probability)
+                });
+            if probability > 0. {
+                let mut node = tree.get_mut(node_id).unwrap();
+                let value = node.value();
+                value.sum_rewards = f64::from(value.n_visits) * expectation / probability;
+            }
+        }
+    }
+}
+
+/// Plays random moves until the end like the `DefaultPlayout`, but draws the outcome of each
+/// chance node by its probability, see `GameTrait::chance_outcomes`.
+pub struct ChancePlayout;
+
+impl<T: GameTrait> Playout<T> for ChancePlayout {
+    type Args = ();
+
+    fn playout(mut state: T, _args: (), rng: &mut dyn RngCore) -> T {
+        let mut moves = Vec::with_capacity(T::MAX_BRANCHING);
+        while !state.is_final() {
+            let outcomes = state.chance_outcomes();
+            if outcomes.is_empty() {
+                state.legal_moves_into(&mut moves);
+                let m = moves.choose(rng).unwrap();
+                state.do_move(m);
+            } else {
+                let (m, _) = outcomes.choose_weighted(rng, |&(_, p)| p).unwrap();
+                state.do_move(m);
+            }
+        }
+        state
+    }
+}
+
+#[test]
+fn test_chance_nodes_are_valued_by_expectation() {
+    use crate::{GameResult, GameResultEvaluator, LazyMcts, NoPlayout};
+
+    /// The player bets, with 1, on a coin landing heads, with 1, 9 times out of 10, or stays, with
+    /// 0, for a draw. The bet wins on heads, so it's worth 0.9.
+    #[derive(Debug, Clone)]
+    struct Bet(Vec<u8>);
+
+    impl GameTrait for Bet {
+        type Player = u8;
+        type Move = u8;
+
+        fn legals_moves(&self) -> Vec<Self::Move> {
+            match self.0[..] {
+                [] | [1] => vec![0, 1],
+                _ => vec![],
+            }
+        }
+
+        fn player_turn(&self) -> Self::Player {
+            1
+        }
+
+        fn hash(&self) -> u64 {
+            self.0.iter().fold(1, |hash, &mv| hash * 3 + u64::from(mv))
+        }
+
+        fn is_final(&self) -> bool {
+            self.legals_moves().is_empty()
+        }
+
+        fn do_move(&mut self, m: &Self::Move) {
+            self.0.push(*m);
+        }
+
+        fn get_winner(&self) -> Self::Player {
+            if self.0[..] == [1, 1] {
+                1
+            } else {
+                2
+            }
+        }
+
+        fn terminal_result(&self) -> Option<GameResult<Self::Player>> {
+            match self.0[..] {
+                [0] => Some(GameResult::Draw),
+                [1, _] => Some(GameResult::Win(self.get_winner())),
+                _ => None,
+            }
+        }
+
+        fn chance_outcomes(&self) -> Vec<(Self::Move, f64)> {
+            match self.0[..] {
+                [1] => vec![(1, 0.9), (0, 0.1)],
+                _ => vec![],
+            }
+        }
+    }
+
+    type Mcts<'a> = LazyMcts<
+        'a,
+        Bet,
+        ChanceTreePolicy<Bet, GameResultEvaluator>,
+        NoPlayout,
+        ChanceBackProp,
+        GameResultEvaluator,
+        ChanceInfo,
+        f64,
+    >;
+    let state = Bet(vec![]);
+    let mcts = Mcts::deterministic(&state, 0);
+    for _ in 0..300 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let stats = mcts.root_stats();
+    let bet = stats.iter().find(|info| info.mv == 1).unwrap();
+    // Both outcomes were drawn, and the coin is valued by their probabilities.
+    assert!((bet.mean_reward.unwrap() - 0.9).abs() < 1e-9);
+    assert_eq!(mcts.best_move(&0.), 1);
+
+    let mut rng = rand::thread_rng();
+    let heads = (0..1000)
+        .filter(|_| ChancePlayout::playout(Bet(vec![1]), (), &mut rng).0[1] == 1)
+        .count();
+    assert!((850..950).contains(&heads));
+}
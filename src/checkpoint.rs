@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use ego_tree::{NodeId, Tree};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// A node of the tree with the index of its parent, the nodes are stored in pre-order so the
+/// parent of a node is always stored before it.
+#[derive(Serialize, Deserialize)]
+struct StoredNode<N> {
+    parent: Option<usize>,
+    value: N,
+}
+
+/// Writes the tree in the file at `path`. The tree is first written in a temporary file which
+/// is then renamed, so a crash while writing never leaves a corrupted file at `path`.
+pub(crate) fn write_tree<N: Serialize>(tree: &Tree<N>, path: &Path) -> io::Result<()> {
+    let tmp_path = tmp_path(path);
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    write_nodes(tree, &mut writer)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    fs::rename(tmp_path, path)
+}
+
+/// Writes the tree to `writer` as a JSON list of its nodes, in pre-order.
+pub(crate) fn write_nodes<N: Serialize>(tree: &Tree<N>, writer: impl Write) -> io::Result<()> {
+    let mut indexes = HashMap::new();
+    let mut nodes = vec![];
+    for node in tree.root().descendants() {
+        let parent = node.parent().map(|p| indexes[&p.id()]);
+        indexes.insert(node.id(), nodes.len());
+        nodes.push(StoredNode {
+            parent,
+            value: node.value(),
+        });
+    }
+    Ok(serde_json::to_writer(writer, &nodes)?)
+}
+
+/// Reads a tree written by `write_tree`.
+pub(crate) fn read_tree<N: DeserializeOwned>(path: &Path) -> io::Result<Tree<N>> {
+    read_nodes(BufReader::new(File::open(path)?))
+}
+
+/// Reads a tree written by `write_nodes`.
+pub(crate) fn read_nodes<N: DeserializeOwned>(reader: impl Read) -> io::Result<Tree<N>> {
+    let nodes: Vec<StoredNode<N>> = serde_json::from_reader(reader)?;
+    let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let mut nodes = nodes.into_iter();
+    let root = nodes.next().ok_or_else(|| invalid("the saved tree is empty"))?;
+    let mut tree = Tree::with_capacity(root.value, nodes.len() + 1);
+    let mut ids: Vec<NodeId> = vec![tree.root().id()];
+    for node in nodes {
+        let parent = node
+            .parent
+            .and_then(|p| ids.get(p).copied())
+            .ok_or_else(|| invalid("a node of the saved tree has no parent"))?;
+        ids.push(tree.get_mut(parent).unwrap().append(node.value).id());
+    }
+    Ok(tree)
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".tmp");
+    path.with_file_name(file_name)
+}
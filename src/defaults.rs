@@ -1,16 +1,22 @@
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::ops::{Add, AddAssign, Div};
+use std::time::{Duration, Instant};
 
 use ego_tree::{NodeId, NodeMut, Tree};
 use noisy_float::types::n64;
-use num_traits::{ToPrimitive, Zero};
-use rand::{Rng, thread_rng};
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
+use rand::{Rng, RngCore};
 use rand::prelude::SliceRandom;
 
-use crate::{Evaluator, Nat, Num, uct_value};
+use crate::{Evaluator, Nat, Num, adaptive_uct_value, uct_value};
 use crate::aliases::{LazyMctsNode, LazyMctsTree};
-use crate::mcts_node::MctsNode;
-use crate::traits::{BackPropPolicy, GameTrait, LazyTreePolicy, Playout};
+use crate::mcts_node::{historic, MctsNode};
+use crate::ops::reward_from_f64;
+use crate::traits::{
+    BackPropPolicy, GameTrait, LazyTreePolicy, Playout, RewardTransform, SimulationWeight,
+    StaticValue, WeightedReward,
+};
 
 /// A default backprop policy it will take the reward of the simulation and backkpropagate the
 /// result  to the branch nodes.
@@ -25,6 +31,7 @@ impl<
 {
     fn backprop(tree: &mut Tree<MctsNode<T, Move, R, A>>, leaf: NodeId, reward: R) {
         let root_id = tree.root().id();
+        let squared_reward = reward.to_f64().unwrap().powi(2);
         let mut current_node_id = leaf;
         // Update the branch
         while current_node_id != root_id {
@@ -32,33 +39,492 @@ impl<
             node_to_update.value().n_visits += 1;
             node_to_update.value().sum_rewards =
                 node_to_update.value().sum_rewards.clone() + reward.clone();
+            node_to_update.value().sum_squared_rewards += squared_reward;
             current_node_id = node_to_update.parent().unwrap().id();
         }
         // Update root
         let mut node_to_update = tree.get_mut(current_node_id).unwrap();
         node_to_update.value().n_visits += 1;
         node_to_update.value().sum_rewards += reward;
+        node_to_update.value().sum_squared_rewards += squared_reward;
     }
 }
 
-/// Simulating taking random moves a applying until the end.
+/// Backpropagates like `DefaultBackProp`, then at each ancestor of the leaf also updates the
+/// siblings whose move is played later in the branch by the same player, as if they had been
+/// played first (a light all-moves-as-first). Only the moves of the tree are known, not the moves
+/// of the playout, so a nudged sibling needs to be played later in the branch itself.
+///
+/// This biases the search: a sibling receives the results of positions where its move was played
+/// later, which is only a good estimate when the value of a move barely depends on when it's
+/// played, and the visits of the children of a node no longer sum to the visits of the node. It
+/// speeds up the convergence of games like tic-tac-toe, see the `amaf_convergence` example.
+pub struct AmafLiteBackProp;
+
+impl<
+    Move: Clone + PartialEq,
+    R: Add + AddAssign + Div + Clone + Zero + ToPrimitive,
+    A: Clone + Default,
+> BackPropPolicy<Vec<Move>, Move, R, A> for AmafLiteBackProp
+{
+    fn backprop(tree: &mut Tree<MctsNode<Vec<Move>, Move, R, A>>, leaf: NodeId, reward: R) {
+        DefaultBackProp::backprop(tree, leaf, reward.clone());
+        let squared_reward = reward.to_f64().unwrap().powi(2);
+        let historic = historic(tree.get(leaf).unwrap());
+        let mut siblings = vec![];
+        let mut node = tree.get(leaf).unwrap();
+        while let Some(parent) = node.parent() {
+            let depth = parent.ancestors().count();
+            for later_move in historic.iter().skip(depth + 2).step_by(2) {
+                let sibling = parent
+                    .children()
+                    .find(|child| child.value().state.last() == Some(later_move));
+                if let Some(sibling) = sibling {
+                    if sibling.id() != node.id() && !siblings.contains(&sibling.id()) {
+                        siblings.push(sibling.id());
+                    }
+                }
+            }
+            node = parent;
+        }
+        for id in siblings {
+            let mut sibling = tree.get_mut(id).unwrap();
+            sibling.value().n_visits += 1;
+            sibling.value().sum_rewards += reward.clone();
+            sibling.value().sum_squared_rewards += squared_reward;
+        }
+    }
+}
+
+/// How the ancestors of a leaf update their value during the backpropagation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupMode {
+    /// The value of a node is the mean reward of the simulations made through it, the sum of
+    /// rewards is incremented like with `DefaultBackProp`.
+    Average,
+    /// The value of a node is the best mean reward of its visited children.
+    Max,
+    /// The value of a node is the best mean reward of its visited children for the player to
+    /// move, in two player games: the maximum at even depths where the player of the root moves,
+    /// and the minimum at odd depths.
+    Minimax,
+}
+
+impl BackupMode {
+    /// Backpropagates `reward` from `leaf`. The leaf and the visits of its ancestors are updated
+    /// like with `DefaultBackProp`. With `Max` and `Minimax` the sum of rewards of each ancestor is
+    /// then recomputed from its children, to `visits * value` so its mean reward is its value,
+    /// rounded to the nearest for the integer rewards.
+    pub fn backprop<T, Move, R, A>(
+        self,
+        tree: &mut Tree<MctsNode<T, Move, R, A>>,
+        leaf: NodeId,
+        reward: R,
+    ) where
+        T: Clone,
+        Move: Clone,
+        R: Add + AddAssign + Div + Clone + Zero + ToPrimitive + FromPrimitive,
+        A: Clone + Default,
+    {
+        if self == BackupMode::Average {
+            return DefaultBackProp::backprop(tree, leaf, reward);
+        }
+        let squared_reward = reward.to_f64().unwrap().powi(2);
+        let mut node_to_update = tree.get_mut(leaf).unwrap();
+        node_to_update.value().n_visits += 1;
+        node_to_update.value().sum_rewards += reward;
+        node_to_update.value().sum_squared_rewards += squared_reward;
+
+        let mut current_node_id = tree.get(leaf).unwrap().parent().map(|parent| parent.id());
+        while let Some(node_id) = current_node_id {
+            let node = tree.get(node_id).unwrap();
+            let means = node.children().filter_map(|child| child.value().mean_reward());
+            let maximize =
+                self == BackupMode::Max || node.ancestors().count().is_multiple_of(2);
+            let value = if maximize { means.max() } else { means.min() }
+                .expect("An ancestor of the leaf has a visited child, cannot happen");
+            current_node_id = node.parent().map(|parent| parent.id());
+
+            let mut node_to_update = tree.get_mut(node_id).unwrap();
+            let node_value = node_to_update.value();
+            node_value.n_visits += 1;
+            node_value.sum_squared_rewards += squared_reward;
+            node_value.sum_rewards = reward_from_f64(value.raw() * f64::from(node_value.n_visits));
+        }
+    }
+}
+
+/// Backpropagates with `BackupMode::Max`, each node takes the value of its best child.
+pub struct MaxBackProp;
+
+impl<
+    T: Clone,
+    Move: Clone,
+    R: Add + AddAssign + Div + Clone + Zero + ToPrimitive + FromPrimitive,
+    A: Clone + Default,
+> BackPropPolicy<T, Move, R, A> for MaxBackProp
+{
+    fn backprop(tree: &mut Tree<MctsNode<T, Move, R, A>>, leaf: NodeId, reward: R) {
+        BackupMode::Max.backprop(tree, leaf, reward)
+    }
+}
+
+/// Backpropagates with `BackupMode::Minimax`, each node takes the value of the best child for
+/// the player to move.
+pub struct MinimaxBackProp;
+
+impl<
+    T: Clone,
+    Move: Clone,
+    R: Add + AddAssign + Div + Clone + Zero + ToPrimitive + FromPrimitive,
+    A: Clone + Default,
+> BackPropPolicy<T, Move, R, A> for MinimaxBackProp
+{
+    fn backprop(tree: &mut Tree<MctsNode<T, Move, R, A>>, leaf: NodeId, reward: R) {
+        BackupMode::Minimax.backprop(tree, leaf, reward)
+    }
+}
+
+/// Backpropagates with `BP` the reward given by the `RewardTransform` `F` of the evaluation of
+/// the simulation, so the rewards are clipped or rescaled in one place whatever the evaluator.
+/// The backprop policies used without it add the evaluation itself.
+pub struct TransformedBackProp<BP, F> {
+    _marker: PhantomData<(BP, F)>,
+}
+
+impl<T, Move, R, A, EvalResult, BP, F> BackPropPolicy<T, Move, R, A, EvalResult>
+for TransformedBackProp<BP, F>
+    where
+        T: Clone,
+        Move: Clone,
+        R: Clone,
+        A: Clone + Default,
+        BP: BackPropPolicy<T, Move, R, A>,
+        F: RewardTransform<EvalResult, R>,
+{
+    fn backprop(tree: &mut Tree<MctsNode<T, Move, R, A>>, leaf: NodeId, eval: EvalResult) {
+        BP::backprop(tree, leaf, F::transform(eval))
+    }
+}
+
+/// Backpropagates with `BP` but keeps the sum of rewards of the root, and of its squared
+/// rewards, from changing: the visits of the root are still counted, UCT needs them, but its
+/// value is never compared since the root isn't chosen. The move selection is unchanged, only
+/// the children are compared, while `LazyMcts::root_value` and what depends on it are then
+/// meaningless.
+pub struct SkipRootRewardBackProp<BP = DefaultBackProp> {
+    _marker: PhantomData<BP>,
+}
+
+impl<T, Move, R, A, EvalResult, BP> BackPropPolicy<T, Move, R, A, EvalResult>
+for SkipRootRewardBackProp<BP>
+    where
+        T: Clone,
+        Move: Clone,
+        R: Clone,
+        A: Clone + Default,
+        BP: BackPropPolicy<T, Move, R, A, EvalResult>,
+{
+    fn backprop(tree: &mut Tree<MctsNode<T, Move, R, A>>, leaf: NodeId, eval: EvalResult) {
+        let root = tree.root().value();
+        let (sum_rewards, sum_squared_rewards) =
+            (root.sum_rewards.clone(), root.sum_squared_rewards);
+        BP::backprop(tree, leaf, eval);
+        let mut root = tree.root_mut();
+        root.value().sum_rewards = sum_rewards;
+        root.value().sum_squared_rewards = sum_squared_rewards;
+    }
+}
+
+/// Backpropagates the rewards weighted by the weight of their simulation: the mean reward of a
+/// node becomes `sum(weight * reward) / sum(weight)` over its simulations, with the sum of the
+/// weights kept in `weighted_visits`, and likewise for the mean of the squared rewards. The
+/// visits still count the simulations, so the exploration of UCT is unchanged, while
+/// `sum_rewards` holds the weighted mean times the visits, so everything reading the mean
+/// reward of the nodes reads the weighted one. With a weight of 1 it backpropagates like the
+/// `DefaultBackProp`. A node visited without weights, e.g. seeded by a shared table, counts its
+/// visits as weights of 1. The rewards are floats, use it with the `WeightedEvaluator`.
+pub struct WeightedBackProp;
+
+impl<T: Clone, Move: Clone, A: Clone + Default> BackPropPolicy<T, Move, f64, A, WeightedReward>
+for WeightedBackProp
+{
+    fn backprop(tree: &mut Tree<MctsNode<T, Move, f64, A>>, leaf: NodeId, eval: WeightedReward) {
+        let mut current_node_id = Some(leaf);
+        while let Some(node_id) = current_node_id {
+            let mut node = tree.get_mut(node_id).unwrap();
+            let value = node.value();
+            let visits = f64::from(value.n_visits);
+            let weights = value.weighted_visits.unwrap_or(visits);
+            let new_weights = weights + eval.weight;
+            if new_weights > 0. {
+                let mean = |sum: f64| if visits > 0. { sum / visits } else { 0. };
+                let new_mean = (mean(value.sum_rewards) * weights + eval.weight * eval.reward)
+                    / new_weights;
+                let new_squared_mean = (mean(value.sum_squared_rewards) * weights
+                    + eval.weight * eval.reward.powi(2))
+                    / new_weights;
+                value.sum_rewards = new_mean * (visits + 1.);
+                value.sum_squared_rewards = new_squared_mean * (visits + 1.);
+            }
+            value.n_visits += 1;
+            value.weighted_visits = Some(new_weights);
+            current_node_id = node.parent().map(|parent| parent.id());
+        }
+    }
+}
+
+/// Evaluates the simulations with the evaluator `EV` and weighs them with the
+/// `SimulationWeight` `F` of their leaf and final state, e.g. to count the short and decisive
+/// playouts more than the long ones, for the `WeightedBackProp`. The nodes are evaluated by
+/// `EV`, and `evaluate_leaf` only sees the final state so it gives a weight of 1.
+pub struct WeightedEvaluator<EV, F> {
+    _marker: PhantomData<(EV, F)>,
+}
+
+impl<State, AdditionalInfo, Reward, EV, F> Evaluator<State, Reward, AdditionalInfo>
+for WeightedEvaluator<EV, F>
+    where
+        State: GameTrait,
+        AdditionalInfo: Clone + Default,
+        Reward: Clone,
+        EV: Evaluator<State, Reward, AdditionalInfo>,
+        EV::EvalResult: ToPrimitive,
+        F: SimulationWeight<State>,
+{
+    type Args = EV::Args;
+    type EvalResult = WeightedReward;
+
+    fn eval_child(
+        child: &LazyMctsNode<State, Reward, AdditionalInfo>,
+        turn: &State::Player,
+        parent_visits: Nat,
+        args: &Self::Args,
+    ) -> Num {
+        EV::eval_child(child, turn, parent_visits, args)
+    }
+
+    fn prior(child: &LazyMctsNode<State, Reward, AdditionalInfo>) -> Option<f64> {
+        EV::prior(child)
+    }
+
+    fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult {
+        WeightedReward {
+            reward: EV::evaluate_leaf(child, turn).to_f64().unwrap(),
+            weight: 1.,
+        }
+    }
+
+    fn evaluate_simulation(
+        leaf: &State,
+        final_state: State,
+        turn: &State::Player,
+        args: &Self::Args,
+    ) -> Self::EvalResult {
+        let weight = F::weight(leaf, &final_state);
+        WeightedReward {
+            reward: EV::evaluate_simulation(leaf, final_state, turn, args)
+                .to_f64()
+                .unwrap(),
+            weight,
+        }
+    }
+}
+
+/// Simulating taking random moves a applying until the end. The moves are played on the state
+/// given to the playout and the legal moves reuse a single buffer, so a playout never clones the
+/// state; an iteration copies the root state with `GameTrait::clone_light`, and the leaf too
+/// only for the evaluators reading it, see `Evaluator::NEEDS_LEAF`.
 pub struct DefaultPlayout;
 
+impl DefaultPlayout {
+    /// Plays random moves on `state` until the end, and pushes them onto `played` if given.
+    fn play_randomly<T: GameTrait>(
+        mut state: T,
+        rng: &mut dyn RngCore,
+        mut played: Option<&mut Vec<T::Move>>,
+    ) -> T {
+        let mut moves = Vec::with_capacity(T::MAX_BRANCHING);
+        while !state.is_final() {
+            state.legal_moves_into(&mut moves);
+            let m = moves.choose(rng).unwrap();
+            state.do_move(m);
+            if let Some(played) = played.as_mut() {
+                played.push(m.clone());
+            }
+        }
+        state
+    }
+}
+
 impl<T: GameTrait> Playout<T> for DefaultPlayout {
     type Args = ();
 
-    fn playout(mut state: T, _args: ()) -> T {
+    fn playout(state: T, _args: (), rng: &mut dyn RngCore) -> T {
+        Self::play_randomly(state, rng, None)
+    }
+
+    fn playout_with_moves(
+        state: T,
+        _args: (),
+        rng: &mut dyn RngCore,
+        played: &mut Vec<T::Move>,
+    ) -> T {
+        Self::play_randomly(state, rng, Some(played))
+    }
+}
+
+/// Like `DefaultPlayout`, but the players other than the one given as argument, the searching
+/// player, play the `GameTrait::opponent_move` of the state when there is one. The argument is
+/// usually the player of the root: the perspective is the one of the searching player for the
+/// whole playout, not the one of the player to move, so with more than two players all the others
+/// are modelled by `opponent_move`. Only the playouts are biased, the opponent moves in the tree
+/// are still selected by the tree policy. Modelling a weak opponent raises the estimated values of
+/// the searching player to what they are against this opponent, which exploits it but
+/// overestimates them against a stronger one.
+pub struct OpponentModelPlayout;
+
+impl<T: GameTrait> Playout<T> for OpponentModelPlayout {
+    type Args = T::Player;
+
+    fn playout(mut state: T, player: T::Player, rng: &mut dyn RngCore) -> T {
+        let mut moves = Vec::with_capacity(T::MAX_BRANCHING);
         while !state.is_final() {
-            let moves = state
-                .legals_moves();
-            let m = moves.choose(&mut thread_rng()) .unwrap();
+            let modelled = if state.player_turn() != player {
+                state.opponent_move(rng)
+            } else {
+                None
+            };
+            let m = modelled.unwrap_or_else(|| {
+                state.legal_moves_into(&mut moves);
+                moves.choose(rng).unwrap().clone()
+            });
+            state.do_move(&m);
+        }
+        state
+    }
+}
+
+/// Like `DefaultPlayout` but stops when the playout took longer than the `Duration` given as
+/// argument, the evaluator then has to evaluate a state which may not be final. To keep the
+/// overhead low the clock is only read every `TimeCappedPlayout::CHECK_EVERY` moves.
+pub struct TimeCappedPlayout;
+
+impl TimeCappedPlayout {
+    pub const CHECK_EVERY: usize = 16;
+}
+
+impl<T: GameTrait> Playout<T> for TimeCappedPlayout {
+    type Args = Duration;
+
+    fn playout(mut state: T, max_duration: Duration, rng: &mut dyn RngCore) -> T {
+        let start = Instant::now();
+        let mut moves = Vec::with_capacity(T::MAX_BRANCHING);
+        let mut n_moves = 0;
+        while !state.is_final() {
+            if n_moves % Self::CHECK_EVERY == 0 && start.elapsed() > max_duration {
+                break;
+            }
+            state.legal_moves_into(&mut moves);
+            let m = moves.choose(rng).unwrap();
+            state.do_move(m);
+            n_moves += 1;
+        }
+        state
+    }
+}
+
+/// The arguments of the `DepthCutoffPlayout`.
+#[derive(Debug, Clone, Copy)]
+pub struct DepthCutoff {
+    /// The number of moves played before the playout looks for a quiet state to stop in.
+    pub max_moves: usize,
+    /// The maximum number of moves played past `max_moves` to reach a quiet state, so a game
+    /// which is rarely quiet can't lengthen the playouts without bound.
+    pub max_extension: usize,
+}
+
+/// Like `DefaultPlayout` but stops after `max_moves` moves, the evaluator then has to evaluate a
+/// state which may not be final. The cutoff is extended, quiescence style, until the state is
+/// `GameTrait::is_quiet`, so a tactical sequence isn't evaluated halfway through, but by at most
+/// `max_extension` moves: the returned state may not be quiet when the extension ran out. With
+/// the default `is_quiet` the playout stops exactly at the cutoff.
+pub struct DepthCutoffPlayout;
+
+impl<T: GameTrait> Playout<T> for DepthCutoffPlayout {
+    type Args = DepthCutoff;
+
+    fn playout(mut state: T, cutoff: DepthCutoff, rng: &mut dyn RngCore) -> T {
+        let mut moves = Vec::with_capacity(T::MAX_BRANCHING);
+        let mut n_moves = 0;
+        while !state.is_final() {
+            if n_moves >= cutoff.max_moves
+                && (state.is_quiet() || n_moves >= cutoff.max_moves + cutoff.max_extension)
+            {
+                break;
+            }
+            state.legal_moves_into(&mut moves);
+            let m = moves.choose(rng).unwrap();
             state.do_move(m);
+            n_moves += 1;
         }
         state
     }
 }
 
-/// Explores at least once each child node, before going deeper.
+/// Doesn't play at all and returns the state of the expanded node, so the evaluator gives its value
+/// to the non final state directly, like in a search guided only by a static evaluation. This
+/// only makes sense with an evaluator whose `evaluate_leaf` is meaningful for a non final state,
+/// `DefaultUctEvaluator` for example calls `get_winner` on it.
+pub struct NoPlayout;
+
+impl<T: GameTrait> Playout<T> for NoPlayout {
+    type Args = ();
+
+    fn playout(state: T, _args: (), _rng: &mut dyn RngCore) -> T {
+        state
+    }
+}
+
+/// Like `DefaultPlayout` but stops when a state was seen the number of times given as argument
+/// during the playout, the states being compared with `GameTrait::hash`. The playout then returns
+/// a state which is not final and counts as a draw with `GameResultEvaluator`, so games allowing
+/// repetitions can't loop forever.
+pub struct RepetitionDrawPlayout;
+
+impl<T: GameTrait> Playout<T> for RepetitionDrawPlayout {
+    type Args = u8;
+
+    fn playout(mut state: T, max_repetitions: u8, rng: &mut dyn RngCore) -> T {
+        let mut moves = Vec::with_capacity(T::MAX_BRANCHING);
+        let mut seen: HashMap<u64, u8> = HashMap::new();
+        while !state.is_final() {
+            let repetitions = seen.entry(state.hash()).or_insert(0);
+            *repetitions += 1;
+            if *repetitions >= max_repetitions {
+                break;
+            }
+            state.legal_moves_into(&mut moves);
+            let m = moves.choose(rng).unwrap();
+            state.do_move(m);
+        }
+        state
+    }
+}
+
+/// Explores at least once each child node, before going deeper. In particular the first
+/// iterations expand each legal move of the root, in a random order, before UCT selects any of
+/// them, so a search stopped after as many iterations as the root has moves, e.g. a very short
+/// timed search, has visited every root move once. The move to expand is drawn with the random
+/// generator of the search, so the first visits aren't biased toward the moves generated first,
+/// and the children are stored in that random order, so the ties between their scores, which
+/// go to the last child stored, are broken randomly too.
+///
+/// The state of the selected node is rebuilt once per iteration, by replaying its historic on
+/// the light clone of the root state the iteration owns, rather than by playing each move on
+/// the way down: on a forced line thousands of nodes deep both ways run within noise of each
+/// other, the selection being dominated by the evaluation of the children at each level and by
+/// the backpropagation, so an apply/undo pair on `GameTrait` isn't needed either.
 pub struct DefaultLazyTreePolicy<State: GameTrait, EV: Evaluator<State, Reward, A>, A: Clone +
 Default, Reward: Clone> {
     phantom_state: PhantomData<State>,
@@ -67,12 +533,17 @@ Default, Reward: Clone> {
     phamtom_r: PhantomData<Reward>,
 }
 
-impl<State: GameTrait, EV: Evaluator<State, Reward, A, Args=f64>, A: Clone + Default,
+impl<State: GameTrait, EV: Evaluator<State, Reward, A>, A: Clone + Default,
     Reward: Clone>
 DefaultLazyTreePolicy<State, EV, A, Reward>
     where
         Reward: Div + ToPrimitive + Add + Zero,
 {
+    /// Descends from the root to the first node which can be expanded or has no children. Each
+    /// step goes down to a child of a finite tree, the historics never link a node back to an
+    /// ancestor, so the selection ends after at most the depth of the tree steps even for a game
+    /// whose states repeat or never end; such a game can only hang in its playouts, which the
+    /// `DepthCutoffPlayout`, the `TimeCappedPlayout` and the `RepetitionDrawPlayout` bound.
     pub fn select(
         tree: &mut LazyMctsTree<State, Reward, A>,
         turn: &State::Player,
@@ -93,28 +564,43 @@ DefaultLazyTreePolicy<State, EV, A, Reward>
     pub fn expand(
         mut node_to_expand: NodeMut<LazyMctsNode<State, Reward, A>>,
         root_state: State,
+        rng: &mut dyn RngCore,
     ) -> (NodeId, State) {
-        let mut new_state = Self::update_state(root_state, &node_to_expand.value().state);
+        let id = node_to_expand.id();
+        let historic = historic(node_to_expand.tree().get(id).unwrap());
+        let new_state = Self::update_state(root_state, &historic);
         if !node_to_expand.value().can_add_child() {
             return (node_to_expand.id(), new_state);
         }
+        let index = rng.gen_range(0..node_to_expand.value().unvisited_moves.len());
+        Self::expand_move(node_to_expand, new_state, historic, index)
+    }
+
+    /// Expands the unvisited move at `index` of `node_to_expand`, whose state is `new_state` and
+    /// historic `new_historic`, and returns the new node and its state.
+    pub(crate) fn expand_move(
+        mut node_to_expand: NodeMut<LazyMctsNode<State, Reward, A>>,
+        mut new_state: State,
+        mut new_historic: Vec<State::Move>,
+        index: usize,
+    ) -> (NodeId, State) {
         let unvisited_moves = &mut node_to_expand.value().unvisited_moves;
-        let index = thread_rng().gen_range(0..unvisited_moves.len());
         let move_to_expand = unvisited_moves[index].clone();
         unvisited_moves[index] = unvisited_moves.last().unwrap().clone();
         unvisited_moves.pop();
 
-        let mut new_historic = node_to_expand.value().state.clone();
         new_state.do_move(&move_to_expand);
         new_historic.push(move_to_expand);
 
+        // The moves are generated in a buffer sized by the branching hint, then shrunk since the
+        // node keeps them.
+        let mut unvisited_moves = Vec::with_capacity(State::MAX_BRANCHING);
+        new_state.legal_moves_into(&mut unvisited_moves);
+        unvisited_moves.shrink_to_fit();
         let new_node = MctsNode {
-            sum_rewards: num_traits::zero(),
-            n_visits: 0,
-            unvisited_moves: new_state.legals_moves(),
+            unvisited_moves,
             hash: new_state.hash(),
-            state: new_historic,
-            additional_info: Default::default(),
+            ..MctsNode::new(new_historic)
         };
 
         (node_to_expand.append(new_node).id(), new_state)
@@ -126,20 +612,21 @@ LazyTreePolicy<State, EV, A, Reward> for DefaultLazyTreePolicy<State, EV, A, Rew
     where
         State: GameTrait,
         Reward: Clone + Div + Add + ToPrimitive + Zero,
-        EV: Evaluator<State, Reward, A, Args=f64>,
+        EV: Evaluator<State, Reward, A>,
         A: Clone + Default
 {
     fn tree_policy(
         tree: &mut LazyMctsTree<State, Reward, A>,
         root_state: State,
         evaluator_args: &EV::Args,
+        rng: &mut dyn RngCore,
     ) -> (NodeId, State) {
         let master_player = root_state.player_turn();
         let selected_node_id = Self::select(tree, &master_player, evaluator_args);
         let node = tree
             .get_mut(selected_node_id)
             .unwrap();
-        Self::expand(node, root_state)
+        Self::expand(node, root_state, rng)
     }
 
     fn update_state(mut root_state: State, historic: &[State::Move]) -> State {
@@ -165,7 +652,8 @@ LazyTreePolicy<State, EV, A, Reward> for DefaultLazyTreePolicy<State, EV, A, Rew
     }
 }
 
-/// Uses UCT to evaluate nodes, and evaluates an end state with 1 if the player won.
+/// Uses UCT to evaluate nodes, and evaluates an end state with 1 if the player won. The sum of
+/// rewards is converted with `ToPrimitive::to_f64`, see `uct_value` for the numeric path.
 pub struct DefaultUctEvaluator;
 
 impl<State: GameTrait, AdditionalInfo: Clone + Default, Reward: Clone + Div + Zero + ToPrimitive
@@ -201,3 +689,862 @@ for DefaultUctEvaluator
         }
     }
 }
+
+/// Uses UCT like `DefaultUctEvaluator`, but the exploration constant given as argument shrinks as
+/// the node accumulates visits, see `adaptive_uct_value`, to focus the search late in it.
+pub struct AdaptiveC;
+
+impl<State: GameTrait, AdditionalInfo: Clone + Default, Reward: Clone + Div + Zero + ToPrimitive
++ Add>
+Evaluator<State, Reward, AdditionalInfo>
+for AdaptiveC
+{
+    type Args = f64;
+    type EvalResult = Nat;
+
+    fn eval_child(
+        child: &LazyMctsNode<State, Reward, AdditionalInfo>,
+        _turn: &State::Player,
+        parent_visits: Nat,
+        &c: &Self::Args,
+    ) -> Num {
+        if child.n_visits == 0 {
+            return n64(0f64);
+        }
+        adaptive_uct_value(
+            parent_visits,
+            child.sum_rewards.to_f64().unwrap(),
+            child.n_visits,
+            c,
+        )
+    }
+
+    fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult {
+        <DefaultUctEvaluator as Evaluator<State, Reward, AdditionalInfo>>::evaluate_leaf(child, turn)
+    }
+}
+
+/// The arguments of the `BaselineUctEvaluator`.
+#[derive(Debug, Clone, Copy)]
+pub struct BaselineArgs {
+    /// The exploration constant of UCT.
+    pub c: f64,
+    /// The advantage of having the move, in reward, of the player of the root, at index 0, and of
+    /// its opponent, at index 1, e.g. the win rate of the first player minus one half.
+    pub player_baseline: [Num; 2],
+}
+
+impl BaselineArgs {
+    /// Returns the arguments with the exploration constant `c` and no baseline.
+    pub fn new(c: f64) -> Self {
+        BaselineArgs {
+            c,
+            player_baseline: [n64(0.); 2],
+        }
+    }
+}
+
+/// Uses UCT like `DefaultUctEvaluator`, and corrects the reward of a simulation by the advantage
+/// of the player to move at its leaf, to compensate the advantage of a player in asymmetric games
+/// when the values of subtrees whose leaves are at different plies are compared: the baseline of
+/// the player of the root is subtracted from the reward when it's to move, the baseline of its
+/// opponent added otherwise. The rewards are floats. It's a tuning aid for the games with a
+/// strong tempo advantage, not a correctness requirement. Games with more than two players use
+/// the baseline of the opponent for all the other players.
+pub struct BaselineUctEvaluator;
+
+impl<State: GameTrait, AdditionalInfo: Clone + Default, Reward: Clone + Div + Zero + ToPrimitive
++ Add>
+Evaluator<State, Reward, AdditionalInfo>
+for BaselineUctEvaluator
+{
+    type Args = BaselineArgs;
+    type EvalResult = f64;
+
+    const NEEDS_LEAF: bool = true;
+
+    fn eval_child(
+        child: &LazyMctsNode<State, Reward, AdditionalInfo>,
+        turn: &State::Player,
+        parent_visits: Nat,
+        args: &Self::Args,
+    ) -> Num {
+        <DefaultUctEvaluator as Evaluator<State, Reward, AdditionalInfo>>::eval_child(
+            child,
+            turn,
+            parent_visits,
+            &args.c,
+        )
+    }
+
+    fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult {
+        <DefaultUctEvaluator as Evaluator<State, Reward, AdditionalInfo>>::evaluate_leaf(child, turn)
+            as f64
+    }
+
+    fn evaluate_simulation(
+        leaf: &State,
+        final_state: State,
+        turn: &State::Player,
+        args: &Self::Args,
+    ) -> Self::EvalResult {
+        let reward =
+            <Self as Evaluator<State, Reward, AdditionalInfo>>::evaluate_leaf(final_state, turn);
+        if leaf.player_turn() == *turn {
+            reward - args.player_baseline[0].raw()
+        } else {
+            reward + args.player_baseline[1].raw()
+        }
+    }
+}
+
+/// The arguments of the `ProgressiveBiasEvaluator`.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressiveBiasArgs {
+    /// The exploration constant of UCT.
+    pub c: f64,
+    /// The weight of the static evaluation, its bias fades as `weight / (visits + 1)`.
+    pub weight: f64,
+}
+
+/// Uses UCT plus the progressive bias `static_eval * weight / (visits + 1)` to evaluate nodes,
+/// where `static_eval` is the `GameTrait::static_eval` of the state of the node seen by the player
+/// of the root, so the domain knowledge of the game guides the first visits of a node. Evaluates
+/// an end state with 1 if the player won like `DefaultUctEvaluator`. The player of the root is
+/// found from the length of the historic of a node, so it needs full historics and refuses
+/// `LazyMcts::compact_historics`.
+pub struct ProgressiveBiasEvaluator;
+
+impl<State: GameTrait, AdditionalInfo: Clone + Default, Reward: Clone + Div + Zero + ToPrimitive
++ Add>
+Evaluator<State, Reward, AdditionalInfo>
+for ProgressiveBiasEvaluator
+{
+    type Args = ProgressiveBiasArgs;
+    type EvalResult = Nat;
+
+    const NEEDS_FULL_HISTORIC: bool = true;
+    const USES_STATIC_EVAL: bool = true;
+
+    fn eval_child(
+        child: &LazyMctsNode<State, Reward, AdditionalInfo>,
+        turn: &State::Player,
+        parent_visits: Nat,
+        args: &Self::Args,
+    ) -> Num {
+        if child.n_visits == 0 {
+            return n64(0f64);
+        }
+        // The static evaluation is seen by the player of the last move, who is the player of
+        // the root at odd depths.
+        let static_eval = child.static_eval().map_or(0., |eval| {
+            if child.state.len() % 2 == 1 { eval } else { 1. - eval }
+        });
+        <DefaultUctEvaluator as Evaluator<State, Reward, AdditionalInfo>>::eval_child(
+            child,
+            turn,
+            parent_visits,
+            &args.c,
+        ) + static_eval * args.weight / f64::from(child.n_visits + 1)
+    }
+
+    fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult {
+        <DefaultUctEvaluator as Evaluator<State, Reward, AdditionalInfo>>::evaluate_leaf(child, turn)
+    }
+}
+
+/// Uses UCT to evaluate nodes like `DefaultUctEvaluator`, and evaluates an end state with the
+/// reward of its `GameTrait::terminal_result`: 1 for a win, 0.5 for a draw and 0 for a loss. A
+/// state which is not final is evaluated as a draw.
+pub struct GameResultEvaluator;
+
+impl<State: GameTrait, AdditionalInfo: Clone + Default, Reward: Clone + Div + Zero + ToPrimitive
++ Add>
+Evaluator<State, Reward, AdditionalInfo>
+for GameResultEvaluator
+{
+    type Args = f64;
+    type EvalResult = f64;
+
+    fn eval_child(
+        child: &LazyMctsNode<State, Reward, AdditionalInfo>,
+        turn: &State::Player,
+        parent_visits: Nat,
+        c: &Self::Args,
+    ) -> Num {
+        <DefaultUctEvaluator as Evaluator<State, Reward, AdditionalInfo>>::eval_child(
+            child,
+            turn,
+            parent_visits,
+            c,
+        )
+    }
+
+    fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult {
+        child
+            .terminal_result()
+            .map_or(0.5, |result| result.reward(turn))
+    }
+}
+
+/// The arguments of the `BlendedEvaluator`.
+#[derive(Debug, Clone, Copy)]
+pub struct BlendArgs<Args> {
+    /// The weight of the static value: 0 evaluates the simulations with the rollout alone, as the
+    /// blended evaluator would, and 1 with the static value of the leaf alone, the playout being
+    /// wasted, so use the `NoPlayout` then.
+    pub lambda: f64,
+    /// The arguments of the blended evaluator.
+    pub args: Args,
+}
+
+/// Evaluates a simulation with `lambda * static + (1 - lambda) * rollout`, where `static` is the
+/// `StaticValue` `F` of the leaf before its playout and `rollout` the evaluation of the final
+/// state by the evaluator `EV`, which must be a reward between 0 and 1 too. The nodes are
+/// evaluated by `EV`. The rewards are floats, so the search needs a float reward like `f64`.
+/// `evaluate_leaf` only sees the final state, so it returns the rollout value alone.
+pub struct BlendedEvaluator<EV, F> {
+    _marker: PhantomData<(EV, F)>,
+}
+
+impl<State, AdditionalInfo, Reward, EV, F> Evaluator<State, Reward, AdditionalInfo>
+for BlendedEvaluator<EV, F>
+    where
+        State: GameTrait,
+        AdditionalInfo: Clone + Default,
+        Reward: Clone,
+        EV: Evaluator<State, Reward, AdditionalInfo>,
+        EV::EvalResult: ToPrimitive,
+        F: StaticValue<State>,
+{
+    type Args = BlendArgs<EV::Args>;
+    type EvalResult = f64;
+
+    const NEEDS_LEAF: bool = true;
+
+    fn eval_child(
+        child: &LazyMctsNode<State, Reward, AdditionalInfo>,
+        turn: &State::Player,
+        parent_visits: Nat,
+        args: &Self::Args,
+    ) -> Num {
+        EV::eval_child(child, turn, parent_visits, &args.args)
+    }
+
+    fn prior(child: &LazyMctsNode<State, Reward, AdditionalInfo>) -> Option<f64> {
+        EV::prior(child)
+    }
+
+    fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult {
+        EV::evaluate_leaf(child, turn).to_f64().unwrap()
+    }
+
+    fn evaluate_simulation(
+        leaf: &State,
+        final_state: State,
+        turn: &State::Player,
+        args: &Self::Args,
+    ) -> Self::EvalResult {
+        let rollout = EV::evaluate_simulation(leaf, final_state, turn, &args.args)
+            .to_f64()
+            .unwrap();
+        args.lambda * F::static_value(leaf, turn) + (1. - args.lambda) * rollout
+    }
+}
+
+#[test]
+fn test_time_capped_playout_stops() {
+    use crate::test_game::Nim;
+    use rand::SeedableRng;
+
+    /// Never ends: each move counts one more.
+    #[derive(Debug, Clone)]
+    struct Count(usize);
+
+    impl GameTrait for Count {
+        type Player = u8;
+        type Move = ();
+
+        fn legals_moves(&self) -> Vec<Self::Move> {
+            vec![()]
+        }
+
+        fn player_turn(&self) -> Self::Player {
+            0
+        }
+
+        fn hash(&self) -> u64 {
+            self.0 as u64
+        }
+
+        fn is_final(&self) -> bool {
+            false
+        }
+
+        fn do_move(&mut self, _m: &Self::Move) {
+            self.0 += 1;
+        }
+
+        fn get_winner(&self) -> Self::Player {
+            unreachable!()
+        }
+    }
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    let state = TimeCappedPlayout::playout(Count(0), Duration::from_millis(1), &mut rng);
+    // The clock is only read every `CHECK_EVERY` moves.
+    assert!(state.0 > 0);
+    assert!(state.0.is_multiple_of(TimeCappedPlayout::CHECK_EVERY));
+    let state = TimeCappedPlayout::playout(Nim::new(10), Duration::from_secs(60), &mut rng);
+    assert!(state.is_final());
+}
+
+#[test]
+fn test_amaf_lite_updates_later_siblings() {
+    use crate::test_game::Nim;
+
+    let node = |historic: Vec<u8>| LazyMctsNode::<Nim, u32, ()>::new(historic);
+    let mut tree = LazyMctsTree::<Nim, u32, ()>::new(node(vec![]));
+    let mut root = tree.root_mut();
+    let sibling = root.append(node(vec![1])).id();
+    let other_sibling = root.append(node(vec![3])).id();
+    let leaf = root
+        .append(node(vec![2]))
+        .append(node(vec![2, 3]))
+        .append(node(vec![2, 3, 1]))
+        .id();
+
+    AmafLiteBackProp::backprop(&mut tree, leaf, 1);
+    // The first player plays 1 later in the branch, the 3 is played by the other player.
+    assert_eq!(tree.get(sibling).unwrap().value().n_visits, 1);
+    assert_eq!(tree.get(sibling).unwrap().value().sum_rewards, 1);
+    assert_eq!(tree.get(other_sibling).unwrap().value().n_visits, 0);
+    assert_eq!(tree.root().value().n_visits, 1);
+}
+
+#[test]
+fn test_repetition_draw_playout_stops() {
+    use rand::SeedableRng;
+
+    /// Never ends: the state swings between two positions.
+    #[derive(Debug, Clone)]
+    struct Swing(u8);
+
+    impl GameTrait for Swing {
+        type Player = u8;
+        type Move = ();
+
+        fn legals_moves(&self) -> Vec<Self::Move> {
+            vec![()]
+        }
+
+        fn player_turn(&self) -> Self::Player {
+            self.0
+        }
+
+        fn hash(&self) -> u64 {
+            self.0 as u64
+        }
+
+        fn is_final(&self) -> bool {
+            false
+        }
+
+        fn do_move(&mut self, _m: &Self::Move) {
+            self.0 = 1 - self.0;
+        }
+
+        fn get_winner(&self) -> Self::Player {
+            unreachable!()
+        }
+    }
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    let state = RepetitionDrawPlayout::playout(Swing(0), 3, &mut rng);
+    assert_eq!(state.0, 0);
+    let reward = <GameResultEvaluator as Evaluator<Swing, f64, ()>>::evaluate_leaf(state, &0);
+    assert_eq!(reward, 0.5);
+}
+
+#[test]
+fn test_baseline_corrects_the_player_to_move() {
+    use crate::test_game::Nim;
+
+    let evaluate = |leaf: Nim, final_state: Nim, args: &BaselineArgs| {
+        <BaselineUctEvaluator as Evaluator<Nim, f64, ()>>::evaluate_simulation(
+            &leaf,
+            final_state,
+            &1,
+            args,
+        )
+    };
+    let won = Nim { sticks: 0, turn: 2 };
+    let mut args = BaselineArgs::new(2f64.sqrt());
+    assert_eq!(evaluate(Nim::new(3), won.clone(), &args), 1.);
+
+    args.player_baseline = [n64(0.2), n64(0.1)];
+    // The player of the root is to move at the first leaf, its opponent at the second.
+    assert_eq!(evaluate(Nim::new(3), won.clone(), &args), 0.8);
+    assert_eq!(evaluate(Nim { sticks: 2, turn: 2 }, won, &args), 1.1);
+    assert_eq!(evaluate(Nim::new(3), Nim::new(0), &args), -0.2);
+}
+
+#[test]
+fn test_progressive_bias_fades_with_the_visits() {
+    use crate::test_game::Nim;
+
+    let bias = |historic: Vec<u8>, n_visits: Nat, weight: f64| {
+        let child = LazyMctsNode::<Nim, u32, ()> {
+            n_visits,
+            sum_rewards: n_visits / 2,
+            sum_squared_rewards: 0.,
+            static_eval: Some(0.8),
+            ..LazyMctsNode::<Nim, u32, ()>::new(historic)
+        };
+        let eval_child = |weight| {
+            <ProgressiveBiasEvaluator as Evaluator<Nim, u32, ()>>::eval_child(
+                &child,
+                &1,
+                10,
+                &ProgressiveBiasArgs { c: 2f64.sqrt(), weight },
+            )
+        };
+        (eval_child(weight) - eval_child(0.)).raw()
+    };
+    assert!((bias(vec![1], 1, 2.) - 0.8).abs() < 1e-9);
+    assert!((bias(vec![1], 3, 2.) - 0.4).abs() < 1e-9);
+    // The static evaluation is seen by the opponent of the root at even depths.
+    assert!((bias(vec![1, 1], 1, 2.) - 0.2).abs() < 1e-9);
+    assert_eq!(bias(vec![1], 0, 2.), 0.);
+}
+
+#[test]
+fn test_adaptive_c_changes_the_best_move_less_often() {
+    use crate::test_game::Nim;
+    use crate::{DefaultMcts, LazyMcts};
+
+    type AdaptiveMcts<'a> = LazyMcts<
+        'a,
+        Nim,
+        DefaultLazyTreePolicy<Nim, AdaptiveC, (), u32>,
+        DefaultPlayout,
+        DefaultBackProp,
+        AdaptiveC,
+        (),
+        u32,
+    >;
+
+    /// Counts how many times the best move changes between two checks, one every 10 iterations.
+    fn changes(execute: &dyn Fn(), best_move: &dyn Fn() -> u8) -> usize {
+        let mut previous = None;
+        let mut changes = 0;
+        for i in 0..1000 {
+            execute();
+            if i >= 100 && i % 10 == 0 {
+                let best_move = best_move();
+                if previous.is_some() && previous != Some(best_move) {
+                    changes += 1;
+                }
+                previous = Some(best_move);
+            }
+        }
+        changes
+    }
+
+    let state = Nim::new(13);
+    let c = 2f64.sqrt();
+    let (mut fixed, mut adaptive) = (0, 0);
+    for seed in 0..10 {
+        let mcts = DefaultMcts::deterministic(&state, seed);
+        fixed += changes(&|| mcts.execute(&c, ()), &|| mcts.best_move(&c));
+        let mcts = AdaptiveMcts::deterministic(&state, seed);
+        adaptive += changes(&|| mcts.execute(&c, ()), &|| mcts.best_move(&c));
+    }
+    assert!(adaptive < fixed, "adaptive: {}, fixed: {}", adaptive, fixed);
+}
+
+#[test]
+fn test_no_playout_evaluates_the_expanded_node() {
+    use crate::test_game::Nim;
+    use crate::LazyMcts;
+    use rand::SeedableRng;
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    assert_eq!(NoPlayout::playout(Nim::new(10), (), &mut rng).sticks, 10);
+
+    // None of the children of the root is final, `GameResultEvaluator` counts them as draws.
+    type Mcts<'a> = LazyMcts<
+        'a,
+        Nim,
+        DefaultLazyTreePolicy<Nim, GameResultEvaluator, (), f64>,
+        NoPlayout,
+        DefaultBackProp,
+        GameResultEvaluator,
+        (),
+        f64,
+    >;
+    let state = Nim::new(10);
+    let mcts = Mcts::new(&state);
+    for _ in 0..3 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let mut csv = Vec::new();
+    mcts.to_csv(&mut csv).unwrap();
+    // The header, the root, then one row per child: one visit and a reward of 0.5.
+    let rows: Vec<_> = String::from_utf8(csv).unwrap().lines().skip(2).map(String::from).collect();
+    assert_eq!(rows.len(), 3);
+    assert!(rows.iter().all(|row| row.ends_with(",1,0.5,0.5")), "{:?}", rows);
+}
+
+#[test]
+fn test_backup_modes() {
+    // The root has the children 1 (mean 0.8) and 2 (mean 0.2), the node 1 has the children 1
+    // (mean 1) and 2 (mean 0.6) where a reward of 0 is backpropagated.
+    fn node(sum_rewards: f64, n_visits: Nat, historic: Vec<u8>) -> MctsNode<Vec<u8>, u8, f64> {
+        MctsNode {
+            sum_rewards,
+            n_visits,
+            ..MctsNode::new(historic)
+        }
+    }
+    let mut tree = Tree::new(node(2., 4, vec![]));
+    let first = tree.root_mut().append(node(1.6, 2, vec![1])).id();
+    tree.root_mut().append(node(0.4, 2, vec![2]));
+    let mut first_node = tree.get_mut(first).unwrap();
+    first_node.append(node(1., 1, vec![1, 1]));
+    let leaf = first_node.append(node(0.6, 1, vec![1, 2])).id();
+
+    let values = |mode: BackupMode| {
+        let mut tree = tree.clone();
+        mode.backprop(&mut tree, leaf, 0.);
+        [leaf, first, tree.root().id()].map(|id| {
+            let value = tree.get(id).unwrap().value();
+            (value.n_visits, value.sum_rewards)
+        })
+    };
+    assert_eq!(values(BackupMode::Average), [(2, 0.6), (3, 1.6), (5, 2.)]);
+    assert_eq!(values(BackupMode::Max), [(2, 0.6), (3, 3.), (5, 5.)]);
+    let minimax = values(BackupMode::Minimax);
+    assert_eq!(minimax[0], (2, 0.6));
+    assert_eq!(minimax[1].0, 3);
+    assert!((minimax[1].1 - 0.9).abs() < 1e-9);
+    assert_eq!(minimax[2].0, 5);
+    assert!((minimax[2].1 - 1.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_opponent_model_playout() {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::test_game::{Nim, TakeOneOpponent};
+
+    // The opponent is to move with 2 sticks, it would win by taking both.
+    let state = Nim { sticks: 2, turn: 2 };
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..20 {
+        let modelled = OpponentModelPlayout::playout(TakeOneOpponent(state.clone()), 1, &mut rng);
+        assert_eq!(modelled.get_winner(), 1);
+    }
+    let winners: Vec<u8> = (0..20)
+        .map(|_| OpponentModelPlayout::playout(state.clone(), 1, &mut rng).get_winner())
+        .collect();
+    assert!(winners.contains(&2));
+}
+
+#[test]
+fn test_blended_evaluator() {
+    use crate::test_game::Nim;
+    use crate::TreePolicyMcts;
+
+    /// The exact value of a Nim state: the player to move wins unless the sticks are a multiple
+    /// of 4.
+    struct NimValue;
+
+    impl StaticValue<Nim> for NimValue {
+        fn static_value(state: &Nim, turn: &u8) -> f64 {
+            let wins = !state.sticks.is_multiple_of(4);
+            if wins == (state.turn == *turn) { 1. } else { 0. }
+        }
+    }
+
+    type Blended = BlendedEvaluator<DefaultUctEvaluator, NimValue>;
+
+    let evaluate = |lambda: f64| {
+        let args = BlendArgs { lambda, args: 2f64.sqrt() };
+        // The leaf is lost by the player 1, who wins the rollout anyway.
+        <Blended as Evaluator<Nim, f64, ()>>::evaluate_simulation(
+            &Nim::new(4),
+            Nim { sticks: 0, turn: 2 },
+            &1,
+            &args,
+        )
+    };
+    assert_eq!(evaluate(0.), 1.);
+    assert_eq!(evaluate(1.), 0.);
+    assert_eq!(evaluate(0.25), 0.75);
+
+    // With the static value alone, the search finds the winning move without playouts.
+    let state = Nim::new(2);
+    let mcts = TreePolicyMcts::<Nim, Blended, NoPlayout, DefaultBackProp, (), f64>::deterministic(
+        &state, 0,
+    );
+    let args = BlendArgs { lambda: 1., args: 2f64.sqrt() };
+    for _ in 0..100 {
+        mcts.execute(&args, ());
+    }
+    assert_eq!(mcts.best_move(&args), 2);
+}
+
+#[test]
+fn test_depth_cutoff_playout_extends_to_a_quiet_state() {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    /// Counts the moves played, the state is quiet every `quiet_every` moves.
+    #[derive(Clone)]
+    struct Counter {
+        moves: usize,
+        quiet_every: usize,
+    }
+
+    impl GameTrait for Counter {
+        type Player = u8;
+        type Move = ();
+
+        fn legals_moves(&self) -> Vec<Self::Move> {
+            vec![()]
+        }
+
+        fn player_turn(&self) -> Self::Player {
+            0
+        }
+
+        fn hash(&self) -> u64 {
+            self.moves as u64
+        }
+
+        fn is_final(&self) -> bool {
+            self.moves == 100
+        }
+
+        fn do_move(&mut self, _m: &Self::Move) {
+            self.moves += 1;
+        }
+
+        fn get_winner(&self) -> Self::Player {
+            0
+        }
+
+        fn is_quiet(&self) -> bool {
+            self.moves.is_multiple_of(self.quiet_every)
+        }
+    }
+
+    let played = |quiet_every: usize, max_moves: usize, max_extension: usize| {
+        let state = Counter { moves: 0, quiet_every };
+        let cutoff = DepthCutoff { max_moves, max_extension };
+        DepthCutoffPlayout::playout(state, cutoff, &mut StdRng::seed_from_u64(0)).moves
+    };
+    assert_eq!(played(1, 3, 10), 3);
+    assert_eq!(played(5, 3, 10), 5);
+    assert_eq!(played(5, 3, 1), 4);
+    assert_eq!(played(5, 98, 10), 100);
+}
+
+#[test]
+fn test_transformed_backprop_clips_the_rewards() {
+    use crate::test_game::Nim;
+
+    struct Clip;
+
+    impl RewardTransform<f64, f64> for Clip {
+        fn transform(eval: f64) -> f64 {
+            eval.clamp(-1., 1.)
+        }
+    }
+
+    let node = |historic: Vec<u8>| LazyMctsNode::<Nim, f64, ()>::new(historic);
+    let mut tree = LazyMctsTree::<Nim, f64, ()>::new(node(vec![]));
+    let leaf = tree.root_mut().append(node(vec![1])).id();
+    for eval in [5., -3., 0.5] {
+        <TransformedBackProp<DefaultBackProp, Clip> as BackPropPolicy<_, _, _, _, f64>>::backprop(
+            &mut tree, leaf, eval,
+        );
+    }
+    for node in [tree.root(), tree.get(leaf).unwrap()] {
+        assert_eq!(node.value().sum_rewards, 0.5);
+        assert_eq!(node.value().sum_squared_rewards, 2.25);
+        assert_eq!(node.value().n_visits, 3);
+    }
+}
+
+#[test]
+fn test_weighted_backprop_computes_weighted_means() {
+    use crate::test_game::Nim;
+
+    let node = |historic: Vec<u8>| LazyMctsNode::<Nim, f64, ()>::new(historic);
+    let mut tree = LazyMctsTree::<Nim, f64, ()>::new(node(vec![]));
+    let leaf = tree.root_mut().append(node(vec![1])).id();
+    for (reward, weight) in [(1., 3.), (0., 1.), (0.5, 0.)] {
+        WeightedBackProp::backprop(&mut tree, leaf, WeightedReward { reward, weight });
+    }
+    for node in [tree.root(), tree.get(leaf).unwrap()] {
+        assert_eq!(node.value().n_visits, 3);
+        assert_eq!(node.value().weighted_visits, Some(4.));
+        assert_eq!(node.value().mean_reward(), Some(n64(0.75)));
+        assert_eq!(node.value().sum_squared_rewards, 0.75 * 3.);
+    }
+
+    // With weights of 1 the statistics are the ones of the default backpropagation.
+    let mut default_tree = LazyMctsTree::<Nim, f64, ()>::new(node(vec![]));
+    let default_leaf = default_tree.root_mut().append(node(vec![1])).id();
+    let mut weighted_tree = default_tree.clone();
+    for reward in [1., 0., 0.5, 1.] {
+        DefaultBackProp::backprop(&mut default_tree, default_leaf, reward);
+        WeightedBackProp::backprop(
+            &mut weighted_tree,
+            default_leaf,
+            WeightedReward { reward, weight: 1. },
+        );
+    }
+    let (default, weighted) = (default_tree.root().value(), weighted_tree.root().value());
+    assert_eq!(default.n_visits, weighted.n_visits);
+    assert!((default.sum_rewards - weighted.sum_rewards).abs() < 1e-12);
+    assert!((default.sum_squared_rewards - weighted.sum_squared_rewards).abs() < 1e-12);
+
+    // A first simulation of weight 0 counts for nothing, not as a visit without weights.
+    let mut tree = LazyMctsTree::<Nim, f64, ()>::new(node(vec![]));
+    for (reward, weight) in [(0., 0.), (1., 1.)] {
+        let root = tree.root().id();
+        WeightedBackProp::backprop(&mut tree, root, WeightedReward { reward, weight });
+    }
+    assert_eq!(tree.root().value().weighted_visits, Some(1.));
+    assert_eq!(tree.root().value().mean_reward(), Some(n64(1.)));
+}
+
+#[test]
+fn test_weighted_evaluator_weighs_the_simulations() {
+    use crate::test_game::Nim;
+    use crate::LazyMcts;
+
+    /// Counts the playouts from the positions with fewer sticks more.
+    struct FewSticks;
+
+    impl SimulationWeight<Nim> for FewSticks {
+        fn weight(leaf: &Nim, _final_state: &Nim) -> f64 {
+            1. / (1. + f64::from(leaf.sticks))
+        }
+    }
+
+    type EV = WeightedEvaluator<GameResultEvaluator, FewSticks>;
+    type Policy = DefaultLazyTreePolicy<Nim, EV, (), f64>;
+    type Mcts<'a> = LazyMcts<'a, Nim, Policy, DefaultPlayout, WeightedBackProp, EV, (), f64>;
+
+    let state = Nim::new(2);
+    let leaf = Nim::new(1);
+    assert_eq!(
+        <EV as Evaluator<Nim, f64, ()>>::evaluate_simulation(
+            &leaf,
+            Nim { sticks: 0, turn: 2 },
+            &1,
+            &1.
+        ),
+        WeightedReward {
+            reward: 1.,
+            weight: 0.5
+        }
+    );
+    // Taking one stick loses with a weight of 1/2, taking both wins with a weight of 1.
+    let node = |historic: Vec<u8>| LazyMctsNode::<Nim, f64, ()>::new(historic);
+    let mut tree = LazyMctsTree::<Nim, f64, ()>::new(node(vec![]));
+    let one = tree.root_mut().append(node(vec![1])).id();
+    let two = tree.root_mut().append(node(vec![2])).id();
+    for (id, leaf) in [(one, Nim { sticks: 1, turn: 2 }), (two, Nim { sticks: 0, turn: 2 })] {
+        let final_state = DefaultPlayout::playout(leaf.clone(), (), &mut rand::thread_rng());
+        let eval = <EV as Evaluator<Nim, f64, ()>>::evaluate_simulation(
+            &leaf,
+            final_state,
+            &1,
+            &1.,
+        );
+        WeightedBackProp::backprop(&mut tree, id, eval);
+    }
+    let root = tree.root().value();
+    assert_eq!(root.n_visits, 2);
+    assert_eq!(root.weighted_visits, Some(1.5));
+    assert_eq!(root.mean_reward(), Some(n64(1. / 1.5)));
+    assert_eq!(tree.get(one).unwrap().value().weighted_visits, Some(0.5));
+
+    let mcts = Mcts::new(&state);
+    for _ in 0..50 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    assert_eq!(mcts.best_move(&2f64.sqrt()), 2);
+}
+
+#[test]
+fn test_selection_ends_in_an_endless_game() {
+    use crate::LazyMcts;
+
+    /// Two positions played back and forth forever.
+    #[derive(Clone)]
+    struct Endless {
+        position: u8,
+    }
+
+    impl GameTrait for Endless {
+        type Player = u8;
+        type Move = u8;
+
+        fn legals_moves(&self) -> Vec<Self::Move> {
+            vec![1 - self.position]
+        }
+
+        fn player_turn(&self) -> Self::Player {
+            self.position
+        }
+
+        fn hash(&self) -> u64 {
+            u64::from(self.position)
+        }
+
+        fn is_final(&self) -> bool {
+            false
+        }
+
+        fn do_move(&mut self, m: &Self::Move) {
+            self.position = *m;
+        }
+
+        fn get_winner(&self) -> Self::Player {
+            unreachable!("The game never ends")
+        }
+    }
+
+    type Policy = DefaultLazyTreePolicy<Endless, GameResultEvaluator, (), f64>;
+    type Mcts<'a> = LazyMcts<
+        'a,
+        Endless,
+        Policy,
+        DepthCutoffPlayout,
+        DefaultBackProp,
+        GameResultEvaluator,
+        (),
+        f64,
+    >;
+
+    let state = Endless { position: 0 };
+    let mcts = Mcts::new(&state);
+    let cutoff = DepthCutoff {
+        max_moves: 10,
+        max_extension: 0,
+    };
+    for _ in 0..100 {
+        mcts.execute(&2f64.sqrt(), cutoff);
+    }
+    // The repeated states are new nodes deeper in a single branch.
+    let statistics = mcts.statistics();
+    assert_eq!(statistics.nodes, 101);
+    assert_eq!(statistics.max_depth, 100);
+    assert_eq!(mcts.root_value(), Some(n64(0.5)));
+}
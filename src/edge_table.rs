@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use num_traits::{FromPrimitive, ToPrimitive};
+
+use crate::aliases::Nat;
+use crate::mcts_node::MctsNode;
+use crate::ops::reward_from_f64;
+
+/// The visits, the sum of rewards and the sum of squared rewards of the moves of the tree, keyed
+/// by the move and the hash of the state it's played from, so the same move played from states
+/// with the same hash shares its statistics, see `LazyMcts::set_edge_table`. With a hash coarser
+/// than the state, see `LazyMcts::set_hasher`, the move shares them between similar states.
+///
+/// The table keeps an entry per distinct pair of a hash and a move ever expanded, about the size
+/// of the move plus 32 bytes with the overhead of the map, and never forgets them, even when the
+/// nodes are pruned or the root is advanced. Like with the `SharedTable`, the rewards are from
+/// the perspective of the player of the root of the searches which recorded them.
+#[derive(Debug)]
+pub struct EdgeTable<Move> {
+    edges: Mutex<HashMap<(u64, Move), EdgeStatistics>>,
+}
+
+/// The visits, sum of rewards and sum of squared rewards of an edge.
+type EdgeStatistics = (Nat, f64, f64);
+
+impl<Move: Hash + Eq + Clone> EdgeTable<Move> {
+    pub fn new() -> Self {
+        EdgeTable {
+            edges: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the visits and the sum of rewards of `mv` played from a state of hash
+    /// `parent_hash`.
+    pub fn get(&self, parent_hash: u64, mv: &Move) -> Option<(Nat, f64)> {
+        self.edges
+            .lock()
+            .unwrap()
+            .get(&(parent_hash, mv.clone()))
+            .map(|&(visits, sum_rewards, _)| (visits, sum_rewards))
+    }
+
+    /// Returns the number of edges in the table.
+    pub fn len(&self) -> usize {
+        self.edges.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds the visits, the sum of rewards and the sum of squared rewards `gained` to the
+    /// statistics of `mv` played from a state of hash `parent_hash`.
+    pub(crate) fn add(&self, parent_hash: u64, mv: Move, gained: EdgeStatistics) {
+        let mut edges = self.edges.lock().unwrap();
+        let (visits, sum_rewards, sum_squared_rewards) =
+            edges.entry((parent_hash, mv)).or_insert((0, 0., 0.));
+        *visits += gained.0;
+        *sum_rewards += gained.1;
+        *sum_squared_rewards += gained.2;
+    }
+
+    /// Gives the statistics of `mv` played from a state of hash `parent_hash` to `node`, the node
+    /// reached by it, if it was never visited. The integer sum of rewards is rounded to the
+    /// nearest.
+    pub(crate) fn seed<T: Clone, R: Clone + FromPrimitive + ToPrimitive, A: Clone + Default>(
+        &self,
+        parent_hash: u64,
+        mv: &Move,
+        node: &mut MctsNode<T, Move, R, A>,
+    ) {
+        if node.n_visits != 0 {
+            return;
+        }
+        if let Some(&(visits, sum_rewards, sum_squared_rewards)) =
+            self.edges.lock().unwrap().get(&(parent_hash, mv.clone()))
+        {
+            node.n_visits = visits;
+            node.sum_rewards = reward_from_f64(sum_rewards);
+            node.sum_squared_rewards = sum_squared_rewards;
+        }
+    }
+}
+
+impl<Move: Hash + Eq + Clone> Default for EdgeTable<Move> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
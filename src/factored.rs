@@ -0,0 +1,198 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+
+use crate::traits::{GameResult, GameTrait};
+use crate::Num;
+
+/// A game whose moves are made of several stages chosen one after the other, e.g. "move the piece
+/// A, then the piece B", so the search can choose each stage instead of enumerating all their
+/// combinations. The games which don't implement it have atomic moves, each one a single node of
+/// the tree; searching the `StagedGame` of a factored game instead adds a node per stage.
+pub trait FactoredGame: GameTrait {
+    /// A stage of a move.
+    type Stage: Debug + Clone + Hash;
+
+    /// Returns the legal choices for the next stage of a move, given the stages already chosen,
+    /// which are empty at the beginning of the move. Only called when the game isn't final.
+    fn legal_stages(&self, chosen: &[Self::Stage]) -> Vec<Self::Stage>;
+
+    /// Returns the move made of the chosen stages when they complete one, `None` while the move
+    /// needs more stages.
+    fn complete_move(&self, chosen: &[Self::Stage]) -> Option<Self::Move>;
+}
+
+/// A factored game searched stage by stage: its moves are the stages of the game, and a state is
+/// the state of the game with the stages already chosen for the current move, which is played as
+/// soon as its stages are complete. The tree then has intermediate stage nodes between the states
+/// of the game, where the same player chooses the next stage, so the evaluators and backprop
+/// policies telling the players apart by the parity of the depth of a node, like the
+/// `MinimaxBackProp`, don't work with it. A partial move is never quiet and has no static
+/// evaluation. See `mcts_staged_agent` to search a whole move.
+#[derive(Debug, Clone)]
+pub struct StagedGame<G: FactoredGame> {
+    pub state: G,
+    /// The stages already chosen for the current move.
+    pub stages: Vec<G::Stage>,
+}
+
+impl<G: FactoredGame> StagedGame<G> {
+    /// Returns the state at the beginning of a move of `state`.
+    pub fn new(state: G) -> Self {
+        StagedGame {
+            state,
+            stages: vec![],
+        }
+    }
+}
+
+impl<G: FactoredGame> GameTrait for StagedGame<G> {
+    type Player = G::Player;
+    type Move = G::Stage;
+
+    const MAX_BRANCHING: usize = G::MAX_BRANCHING;
+
+    fn legals_moves(&self) -> Vec<Self::Move> {
+        if self.state.is_final() {
+            return vec![];
+        }
+        self.state.legal_stages(&self.stages)
+    }
+
+    fn clone_light(&self) -> Self {
+        StagedGame {
+            state: self.state.clone_light(),
+            stages: self.stages.clone(),
+        }
+    }
+
+    fn player_turn(&self) -> Self::Player {
+        self.state.player_turn()
+    }
+
+    fn hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.state.hash().hash(&mut hasher);
+        self.stages.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn is_final(&self) -> bool {
+        self.state.is_final()
+    }
+
+    fn do_move(&mut self, m: &Self::Move) {
+        self.stages.push(m.clone());
+        if let Some(mv) = self.state.complete_move(&self.stages) {
+            self.state.do_move(&mv);
+            self.stages.clear();
+        }
+    }
+
+    fn get_winner(&self) -> Self::Player {
+        self.state.get_winner()
+    }
+
+    fn terminal_result(&self) -> Option<GameResult<Self::Player>> {
+        self.state.terminal_result()
+    }
+
+    fn static_eval(&self) -> Option<Num> {
+        if self.stages.is_empty() {
+            self.state.static_eval()
+        } else {
+            None
+        }
+    }
+
+    fn is_quiet(&self) -> bool {
+        self.stages.is_empty() && self.state.is_quiet()
+    }
+
+    fn ply(&self) -> usize {
+        self.state.ply()
+    }
+}
+
+/// Places two digits, the player wins if they are 2 then 1.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+struct TwoDigits {
+    played: Option<(u8, u8)>,
+}
+
+#[cfg(test)]
+impl GameTrait for TwoDigits {
+    type Player = u8;
+    type Move = (u8, u8);
+
+    fn legals_moves(&self) -> Vec<Self::Move> {
+        (0..3).flat_map(|a| (0..3).map(move |b| (a, b))).collect()
+    }
+
+    fn player_turn(&self) -> Self::Player {
+        1
+    }
+
+    fn hash(&self) -> u64 {
+        0
+    }
+
+    fn is_final(&self) -> bool {
+        self.played.is_some()
+    }
+
+    fn do_move(&mut self, m: &Self::Move) {
+        self.played = Some(*m);
+    }
+
+    fn get_winner(&self) -> Self::Player {
+        if self.played == Some((2, 1)) {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+impl FactoredGame for TwoDigits {
+    type Stage = u8;
+
+    fn legal_stages(&self, _chosen: &[Self::Stage]) -> Vec<Self::Stage> {
+        (0..3).collect()
+    }
+
+    fn complete_move(&self, chosen: &[Self::Stage]) -> Option<Self::Move> {
+        match chosen {
+            &[a, b] => Some((a, b)),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_staged_game_plays_the_move_once_complete() {
+    use crate::DefaultMcts;
+
+    let mut state = StagedGame::new(TwoDigits { played: None });
+    state.do_move(&2);
+    assert_eq!(state.stages, [2]);
+    assert!(!state.is_final() && !state.is_quiet());
+    state.do_move(&1);
+    assert!(state.stages.is_empty());
+    assert_eq!(state.state.played, Some((2, 1)));
+
+    // The stages of the move are nodes of the tree.
+    let state = StagedGame::new(TwoDigits { played: None });
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..200 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    assert_eq!(mcts.best_move(&2f64.sqrt()), 2);
+    assert_eq!(mcts.statistics().max_depth, 2);
+    assert_eq!(
+        crate::mcts_staged_agent(&TwoDigits { played: None }, 200, 2f64.sqrt()),
+        (2, 1)
+    );
+}
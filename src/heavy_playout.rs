@@ -0,0 +1,161 @@
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::prelude::SliceRandom;
+use rand::RngCore;
+
+use crate::traits::{GameTrait, Playout};
+
+/// Scores a legal move of a state for the `WeightedRandomPlayout`, at least 0.
+pub type MoveScore<T> = Arc<dyn Fn(&T, &<T as GameTrait>::Move) -> f64 + Send + Sync>;
+
+/// The arguments of the `WeightedRandomPlayout`.
+pub struct WeightedRandomArgs<T: GameTrait> {
+    pub score: MoveScore<T>,
+    /// The number of moves after which the playout stops, `None` to play until the end.
+    pub max_moves: Option<usize>,
+}
+
+impl<T: GameTrait> Clone for WeightedRandomArgs<T> {
+    fn clone(&self) -> Self {
+        WeightedRandomArgs {
+            score: Arc::clone(&self.score),
+            max_moves: self.max_moves,
+        }
+    }
+}
+
+impl<T: GameTrait> Debug for WeightedRandomArgs<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeightedRandomArgs")
+            .field("max_moves", &self.max_moves)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Like `DefaultPlayout`, but draws each move with a probability proportional to its `score`,
+/// so the domain knowledge of the game makes the playouts more realistic, and stops after
+/// `max_moves` moves if given, the evaluator then has to evaluate a state which may not be
+/// final, like with the `DepthCutoffPlayout`. A state whose moves all score 0 draws them
+/// uniformly.
+pub struct WeightedRandomPlayout;
+
+impl<T: GameTrait> Playout<T> for WeightedRandomPlayout {
+    type Args = WeightedRandomArgs<T>;
+
+    fn playout(mut state: T, args: WeightedRandomArgs<T>, rng: &mut dyn RngCore) -> T {
+        let mut moves = Vec::with_capacity(T::MAX_BRANCHING);
+        let mut scores = Vec::with_capacity(T::MAX_BRANCHING);
+        let mut n_moves = 0;
+        while !state.is_final() && args.max_moves.is_none_or(|max_moves| n_moves < max_moves) {
+            state.legal_moves_into(&mut moves);
+            scores.clear();
+            scores.extend(moves.iter().map(|mv| (args.score)(&state, mv)));
+            let mv = match WeightedIndex::new(&scores) {
+                Ok(weights) => &moves[weights.sample(rng)],
+                Err(_) => moves.choose(rng).unwrap(),
+            };
+            state.do_move(mv);
+            n_moves += 1;
+        }
+        state
+    }
+}
+
+/// Like `DefaultPlayout`, but plays a decisive move, winning at once for the player to move,
+/// when there is one, and otherwise avoids the anti-decisive moves, after which the opponent has
+/// a decisive move, unless all the moves are. It stops after the number of moves given as
+/// argument if any, like the `WeightedRandomPlayout`. Each move plays all the moves of the state
+/// and of the states they lead to, see `GameTrait::clone_light`, so it's worth it for the games
+/// whose random playouts miss the wins and losses in one move, like connection games.
+pub struct DecisivePlayout;
+
+impl DecisivePlayout {
+    /// Returns whether `mv` wins at once for the player to move in `state`.
+    fn is_decisive<T: GameTrait>(state: &T, mv: &T::Move) -> bool {
+        let player = state.player_turn();
+        let mut next = state.clone_light();
+        next.do_move(mv);
+        next.terminal_result()
+            .is_some_and(|result| result.reward(&player) == 1.)
+    }
+
+    /// Returns whether the opponent has a decisive move after `mv` in `state`.
+    fn is_anti_decisive<T: GameTrait>(state: &T, mv: &T::Move) -> bool {
+        let mut next = state.clone_light();
+        next.do_move(mv);
+        !next.is_final()
+            && next
+                .legals_moves()
+                .iter()
+                .any(|reply| Self::is_decisive(&next, reply))
+    }
+}
+
+impl<T: GameTrait> Playout<T> for DecisivePlayout {
+    type Args = Option<usize>;
+
+    fn playout(mut state: T, max_moves: Option<usize>, rng: &mut dyn RngCore) -> T {
+        let mut moves = Vec::with_capacity(T::MAX_BRANCHING);
+        let mut n_moves = 0;
+        while !state.is_final() && max_moves.is_none_or(|max_moves| n_moves < max_moves) {
+            state.legal_moves_into(&mut moves);
+            let mv = match moves.iter().find(|mv| Self::is_decisive(&state, mv)) {
+                Some(mv) => mv.clone(),
+                None => {
+                    let safe: Vec<&T::Move> = moves
+                        .iter()
+                        .filter(|mv| !Self::is_anti_decisive(&state, mv))
+                        .collect();
+                    match safe.choose(rng) {
+                        Some(&mv) => mv.clone(),
+                        None => moves.choose(rng).unwrap().clone(),
+                    }
+                }
+            };
+            state.do_move(&mv);
+            n_moves += 1;
+        }
+        state
+    }
+}
+
+#[test]
+fn test_heavy_playouts() {
+    use crate::test_game::Nim;
+
+    let mut rng = rand::thread_rng();
+    let take_one = WeightedRandomArgs::<Nim> {
+        score: Arc::new(|_, &mv| if mv == 1 { 1. } else { 0. }),
+        max_moves: None,
+    };
+    let state = WeightedRandomPlayout::playout(Nim::new(10), take_one.clone(), &mut rng);
+    // Ten single sticks, the second player takes the last one.
+    assert_eq!(state.get_winner(), 2);
+    let cut = WeightedRandomArgs {
+        max_moves: Some(3),
+        ..take_one
+    };
+    assert_eq!(
+        WeightedRandomPlayout::playout(Nim::new(10), cut, &mut rng).sticks,
+        7
+    );
+    let flat = WeightedRandomArgs::<Nim> {
+        score: Arc::new(|_, _| 0.),
+        max_moves: None,
+    };
+    assert!(WeightedRandomPlayout::playout(Nim::new(10), flat, &mut rng).is_final());
+
+    for _ in 0..50 {
+        // Leaving 4 sticks is the only move not giving the opponent a win, then the first
+        // player takes what the second leaves.
+        let state = DecisivePlayout::playout(Nim::new(5), None, &mut rng);
+        assert_eq!(state.get_winner(), 1);
+    }
+    assert_eq!(
+        DecisivePlayout::playout(Nim::new(5), Some(1), &mut rng).sticks,
+        4
+    );
+    assert!(DecisivePlayout::playout(Nim::new(3), None, &mut rng).is_final());
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::{Add, Div};
+use std::sync::{Arc, Mutex};
+
+use noisy_float::types::n64;
+use num_traits::{ToPrimitive, Zero};
+
+use crate::aliases::LazyMctsNode;
+use crate::traits::{Evaluator, GameTrait};
+use crate::{uct_value, Nat, Num};
+
+/// The number of simulations each move took part in and the sum of their rewards, wherever the
+/// move was played in the tree. Wrap it in an `Arc` to share it between searches, for example to
+/// keep it after advancing the root.
+#[derive(Debug)]
+pub struct HistoryTable<Move> {
+    moves: Mutex<HashMap<Move, (Nat, f64)>>,
+}
+
+impl<Move: Hash + Eq + Clone> HistoryTable<Move> {
+    pub fn new() -> Self {
+        HistoryTable {
+            moves: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a simulation with the reward `reward` for each move of `historic`.
+    pub fn record(&self, historic: &[Move], reward: f64) {
+        let mut moves = self.moves.lock().unwrap();
+        for mv in historic {
+            let (count, sum_rewards) = moves.entry(mv.clone()).or_insert((0, 0.));
+            *count += 1;
+            *sum_rewards += reward;
+        }
+    }
+
+    /// Returns the number of simulations `mv` took part in.
+    pub fn count(&self, mv: &Move) -> Nat {
+        self.moves
+            .lock()
+            .unwrap()
+            .get(mv)
+            .map_or(0, |&(count, _)| count)
+    }
+
+    /// Returns the mean reward of the simulations `mv` took part in, 0 if it never did.
+    pub fn history_value(&self, mv: &Move) -> f64 {
+        self.moves
+            .lock()
+            .unwrap()
+            .get(mv)
+            .map_or(0., |&(count, sum_rewards)| sum_rewards / f64::from(count))
+    }
+}
+
+impl<Move: Hash + Eq + Clone> Default for HistoryTable<Move> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The arguments of the `HistoryUctEvaluator`.
+#[derive(Debug, Clone)]
+pub struct HistoryArgs<Move> {
+    /// The exploration constant of UCT.
+    pub c: f64,
+    /// The weight of the history value of a move in its score.
+    pub history_bonus: f64,
+    pub table: Arc<HistoryTable<Move>>,
+}
+
+/// Uses UCT plus `history_bonus * history_value(move)` to evaluate nodes, so the moves which led
+/// to good rewards anywhere in the tree are tried first, which mostly helps early in the search.
+/// The history table is filled with the reward of each simulation, and an end state is evaluated
+/// with 1 if the player won like with `DefaultUctEvaluator`.
+pub struct HistoryUctEvaluator;
+
+impl<State, AdditionalInfo, Reward> Evaluator<State, Reward, AdditionalInfo> for HistoryUctEvaluator
+where
+    State: GameTrait,
+    State::Move: Hash + Eq,
+    AdditionalInfo: Clone + Default,
+    Reward: Clone + Div + Zero + ToPrimitive + Add,
+{
+    type Args = HistoryArgs<State::Move>;
+    type EvalResult = Nat;
+
+    fn eval_child(
+        child: &LazyMctsNode<State, Reward, AdditionalInfo>,
+        _turn: &State::Player,
+        parent_visits: Nat,
+        args: &Self::Args,
+    ) -> Num {
+        if child.n_visits == 0 {
+            return n64(0f64);
+        }
+        let history_value = child
+            .state
+            .last()
+            .map_or(0., |mv| args.table.history_value(mv));
+        uct_value(
+            parent_visits,
+            child.sum_rewards.to_f64().unwrap(),
+            child.n_visits,
+            args.c,
+        ) + args.history_bonus * history_value
+    }
+
+    fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult {
+        if child.get_winner() == *turn {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn record_leaf(historic: &[State::Move], eval: &Self::EvalResult, args: &Self::Args) {
+        args.table.record(historic, f64::from(*eval));
+    }
+}
+
+#[test]
+fn test_history_table_accumulates_and_influences_selection() {
+    use crate::test_game::Nim;
+    use crate::{DefaultBackProp, DefaultLazyTreePolicy, DefaultPlayout, LazyMcts, LazyTreePolicy};
+
+    type Policy = DefaultLazyTreePolicy<Nim, HistoryUctEvaluator, (), u32>;
+    type Mcts<'a> =
+        LazyMcts<'a, Nim, Policy, DefaultPlayout, DefaultBackProp, HistoryUctEvaluator, (), u32>;
+
+    let state = Nim::new(10);
+    let args = HistoryArgs {
+        c: 2f64.sqrt(),
+        history_bonus: 1.,
+        table: Arc::new(HistoryTable::new()),
+    };
+    let mcts = Mcts::new(&state);
+    for _ in 0..100 {
+        mcts.execute(&args, ());
+    }
+    // Each simulation is recorded for every move leading to its node.
+    let counts: Nat = (1..=3).map(|mv| args.table.count(&mv)).sum();
+    assert!(counts >= 100);
+
+    // Two children with the same statistics, only the history tells them apart.
+    let mut tree = crate::LazyMctsTree::<Nim, u32, ()>::new(LazyMctsNode::<Nim, u32, ()> {
+        sum_rewards: 10,
+        sum_squared_rewards: 10.,
+        n_visits: 20,
+        ..LazyMctsNode::<Nim, u32, ()>::new(vec![])
+    });
+    for mv in [1, 2] {
+        tree.root_mut().append(LazyMctsNode::<Nim, u32, ()> {
+            sum_rewards: 5,
+            sum_squared_rewards: 5.,
+            n_visits: 10,
+            ..LazyMctsNode::<Nim, u32, ()>::new(vec![mv])
+        });
+    }
+    let args = HistoryArgs {
+        c: 2f64.sqrt(),
+        history_bonus: 1.,
+        table: Arc::new(HistoryTable::new()),
+    };
+    args.table.record(&[2], 1.);
+    args.table.record(&[1], 0.);
+    let root = tree.root().id();
+    let best = Policy::best_child(&tree, &1, root, &args);
+    assert_eq!(tree.get(best).unwrap().value().state, [2]);
+
+    args.table.record(&[1], 1.);
+    args.table.record(&[1], 1.);
+    args.table.record(&[2], 0.);
+    args.table.record(&[2], 0.);
+    let best = Policy::best_child(&tree, &1, root, &args);
+    assert_eq!(tree.get(best).unwrap().value().state, [1]);
+}
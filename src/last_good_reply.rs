@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use rand::prelude::SliceRandom;
+use rand::RngCore;
+
+use crate::traits::{GameResult, GameTrait, Playout};
+
+/// For each move, the last reply to it which was played by the winner of a playout.
+#[derive(Debug)]
+pub struct ReplyTable<Move> {
+    replies: Mutex<HashMap<Move, Move>>,
+}
+
+impl<Move: Hash + Eq + Clone> ReplyTable<Move> {
+    pub fn new() -> Self {
+        ReplyTable {
+            replies: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the last good reply to `mv`, if any.
+    pub fn reply(&self, mv: &Move) -> Option<Move> {
+        self.replies.lock().unwrap().get(mv).cloned()
+    }
+
+    /// Updates the table with the moves of a finished playout and the players who played them:
+    /// the replies of the winners are stored, and the replies of the losers are forgotten if they
+    /// were stored. A draw doesn't change the table.
+    pub fn record<Player: Eq>(&self, played: &[(Player, Move)], result: &GameResult<Player>) {
+        let mut replies = self.replies.lock().unwrap();
+        for pair in played.windows(2) {
+            let (previous, (player, reply)) = (&pair[0].1, &pair[1]);
+            let reward = result.reward(player);
+            if reward == 1. {
+                replies.insert(previous.clone(), reply.clone());
+            } else if reward == 0. && replies.get(previous) == Some(reply) {
+                replies.remove(previous);
+            }
+        }
+    }
+}
+
+impl<Move: Hash + Eq + Clone> Default for ReplyTable<Move> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Like `DefaultPlayout`, but answers the previous move with its last good reply when it's legal,
+/// the "last good reply with forgetting" policy. The table given as argument is updated at the end
+/// of each playout, share it between the iterations of a search so the playouts learn from each
+/// other. The `last_good_reply` example measures it against the default playout.
+pub struct LastGoodReplyPlayout;
+
+impl<T> Playout<T> for LastGoodReplyPlayout
+where
+    T: GameTrait,
+    T::Move: Hash + Eq,
+{
+    type Args = Arc<ReplyTable<T::Move>>;
+
+    fn playout(mut state: T, table: Self::Args, rng: &mut dyn RngCore) -> T {
+        let mut moves = Vec::with_capacity(T::MAX_BRANCHING);
+        let mut played: Vec<(T::Player, T::Move)> = vec![];
+        while !state.is_final() {
+            state.legal_moves_into(&mut moves);
+            let m = played
+                .last()
+                .and_then(|(_, previous)| table.reply(previous))
+                .filter(|reply| moves.contains(reply))
+                .unwrap_or_else(|| moves.choose(rng).unwrap().clone());
+            played.push((state.player_turn(), m.clone()));
+            state.do_move(&m);
+        }
+        if let Some(result) = state.terminal_result() {
+            table.record(&played, &result);
+        }
+        state
+    }
+}
+
+#[test]
+fn test_reply_table_keeps_the_replies_of_the_winner() {
+    let table = ReplyTable::new();
+    table.record(&[(1, 'a'), (2, 'b'), (1, 'c')], &GameResult::Win(2));
+    assert_eq!(table.reply(&'a'), Some('b'));
+    assert_eq!(table.reply(&'b'), None);
+
+    table.record(&[(1, 'a'), (2, 'b')], &GameResult::Draw);
+    assert_eq!(table.reply(&'a'), Some('b'));
+    table.record(&[(1, 'a'), (2, 'b')], &GameResult::Win(1));
+    assert_eq!(table.reply(&'a'), None);
+}
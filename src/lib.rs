@@ -1,31 +1,84 @@
 pub use crate::agents::*;
 pub use crate::aliases::*;
-use crate::defaults::DefaultUctEvaluator;
-pub use crate::defaults::{DefaultBackProp, DefaultLazyTreePolicy, DefaultPlayout};
+pub use crate::best_move::BestMoveCriterion;
+pub use crate::builder::{BackPropKind, MctsBuilder, PlayoutKind, Search, TreePolicyKind};
+pub use crate::chance::{ChanceBackProp, ChanceInfo, ChancePlayout, ChanceTreePolicy};
+pub use crate::defaults::{
+    AdaptiveC, AmafLiteBackProp, BackupMode, BaselineArgs, BaselineUctEvaluator, BlendArgs,
+    BlendedEvaluator, DefaultBackProp, DefaultLazyTreePolicy, DefaultPlayout, DefaultUctEvaluator,
+    DepthCutoff, DepthCutoffPlayout, GameResultEvaluator, MaxBackProp, MinimaxBackProp, NoPlayout,
+    OpponentModelPlayout, ProgressiveBiasArgs, ProgressiveBiasEvaluator, RepetitionDrawPlayout,
+    SkipRootRewardBackProp, TimeCappedPlayout, TransformedBackProp, WeightedBackProp,
+    WeightedEvaluator,
+};
+pub use crate::edge_table::EdgeTable;
+pub use crate::factored::{FactoredGame, StagedGame};
+pub use crate::heavy_playout::{
+    DecisivePlayout, MoveScore, WeightedRandomArgs, WeightedRandomPlayout,
+};
+pub use crate::history::{HistoryArgs, HistoryTable, HistoryUctEvaluator};
+pub use crate::last_good_reply::{LastGoodReplyPlayout, ReplyTable};
+pub use crate::maxn::{
+    MaxnBackProp, MaxnEvaluator, MaxnInfo, MaxnReward, MaxnTreePolicy, MultiplayerGame,
+};
 pub use crate::mcts_node::*;
+pub use crate::opening_book::OpeningBook;
 pub use crate::ops::*;
+pub use crate::puct::{PuctEvaluator, PuctInfo, PuctTreePolicy};
+pub use crate::rave::{
+    RaveArgs, RaveBackProp, RaveEvaluator, RaveInfo, RaveReward, RaveTreePolicy,
+};
+pub use crate::shared_table::SharedTable;
+pub use crate::solver::{
+    Proof, SolverBackProp, SolverEvaluator, SolverInfo, SolverReward, SolverTreePolicy,
+};
+#[cfg(feature = "test-utils")]
+pub use crate::test_utils::{check_evaluator_symmetry, compare_searches, AgreementReport};
 pub use crate::traits::*;
-pub use crate::tree_search::LazyMcts;
+pub use crate::tree_search::{
+    BatchRequest, CaughtPanic, IterationOutcome, LazyMcts, MoveExplanation, MoveStats,
+    RootMoveInfo, SearchLimits, SearchObserver, SearchRun, SearchStatistics, SearchStop,
+    SearchTelemetry,
+};
+pub use crate::widening::{ProgressiveWideningTreePolicy, WideningArgs, WideningEvaluator};
 pub use ego_tree::*;
 
 mod agents;
 mod aliases;
+mod best_move;
+mod builder;
+mod chance;
+#[cfg(feature = "serde")]
+mod checkpoint;
 mod defaults;
+mod edge_table;
+mod factored;
+mod heavy_playout;
+mod history;
+mod last_good_reply;
+mod maxn;
 mod mcts_node;
+mod opening_book;
 mod ops;
+mod puct;
+mod rave;
+mod shared_table;
+mod solver;
+#[cfg(test)]
+mod test_game;
+#[cfg(feature = "test-utils")]
+mod test_utils;
 mod traits;
 mod tree_search;
+mod widening;
+
+/// A `LazyMcts` using the `DefaultLazyTreePolicy`, whose evaluator, additional info and reward
+/// are the ones of the search, so they are only given once. The reward stays a parameter rather
+/// than an associated type of the evaluator because the evaluators and the backprop policies of
+/// this crate work with any reward, e.g. `DefaultUctEvaluator` is used with `u32` and `f64`.
+pub type TreePolicyMcts<'a, State, EV, PP = DefaultPlayout, BP = DefaultBackProp, A = (), R = u32> =
+    LazyMcts<'a, State, DefaultLazyTreePolicy<State, EV, A, R>, PP, BP, EV, A, R>;
 
 /// This mcts uses UCT, naive simulation applying random moves until a final state, and scoring 1
 /// if the player won.
-pub type DefaultMcts<'a, State> = LazyMcts<
-    'a,
-    State,
-    DefaultLazyTreePolicy<State, DefaultUctEvaluator, (), u32>,
-    DefaultPlayout,
-    DefaultBackProp,
-    DefaultUctEvaluator,
-    (),
-    u32,
->;
-
+pub type DefaultMcts<'a, State> = TreePolicyMcts<'a, State, DefaultUctEvaluator>;
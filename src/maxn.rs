@@ -0,0 +1,265 @@
+use std::marker::PhantomData;
+
+use ego_tree::{NodeId, Tree};
+use noisy_float::prelude::n64;
+use rand::{Rng, RngCore};
+
+use crate::aliases::{LazyMctsNode, LazyMctsTree};
+use crate::defaults::{DefaultBackProp, DefaultLazyTreePolicy};
+use crate::mcts_node::{historic, MctsNode};
+use crate::traits::{BackPropPolicy, Evaluator, GameResult, GameTrait, LazyTreePolicy};
+use crate::{uct_value, Nat, Num};
+
+/// A game of any number of players, searched with max^n: each player chooses the moves
+/// maximizing its own reward, instead of the reward of the player of the root, so the search
+/// doesn't assume that all the other players play against it. See the `MaxnTreePolicy`.
+pub trait MultiplayerGame: GameTrait {
+    /// The number of players.
+    const PLAYERS: usize;
+
+    /// Returns the index of `player`, below `PLAYERS`, where its reward is stored.
+    fn player_index(player: &Self::Player) -> usize;
+
+    /// Returns the reward of each player of this final state, by index. Defaults to the
+    /// `GameResult::reward` of the `terminal_result` for each player: 1 for the winner and 0 for
+    /// the others, 0 for the loser and 1 for the others, or 0.5 for all in a draw. A state which
+    /// is not final is evaluated as a draw.
+    fn rewards(&self) -> Vec<f64> {
+        match self.terminal_result().unwrap_or(GameResult::Draw) {
+            GameResult::Win(winner) => {
+                let mut rewards = vec![0.; Self::PLAYERS];
+                rewards[Self::player_index(&winner)] = 1.;
+                rewards
+            }
+            GameResult::Loss(loser) => {
+                let mut rewards = vec![1.; Self::PLAYERS];
+                rewards[Self::player_index(&loser)] = 0.;
+                rewards
+            }
+            GameResult::Draw => vec![0.5; Self::PLAYERS],
+        }
+    }
+}
+
+/// The additional info of the nodes of a search with max^n.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaxnInfo {
+    /// The sum of the rewards of each player over the visits of the node, by index, empty until
+    /// its first visit.
+    pub sum_rewards: Vec<f64>,
+    /// The index of the player who played the move leading to the node, set by the
+    /// `MaxnTreePolicy` when it expands the node. 0 for the root.
+    pub mover: usize,
+}
+
+/// The evaluation of a simulation by the `MaxnEvaluator`: the reward of each player, by index,
+/// and the reward of the player of the root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaxnReward {
+    pub rewards: Vec<f64>,
+    pub reward: f64,
+}
+
+/// Evaluates each child with UCT on the rewards of the player who plays the move leading to it,
+/// and evaluates an end state with the `MultiplayerGame::rewards` of all its players. The
+/// argument is the exploration constant of UCT.
+pub struct MaxnEvaluator;
+
+impl<State, Reward> Evaluator<State, Reward, MaxnInfo> for MaxnEvaluator
+where
+    State: MultiplayerGame,
+    Reward: Clone,
+{
+    type Args = f64;
+    type EvalResult = MaxnReward;
+
+    fn eval_child(
+        child: &LazyMctsNode<State, Reward, MaxnInfo>,
+        _turn: &State::Player,
+        parent_visits: Nat,
+        &c: &Self::Args,
+    ) -> Num {
+        let info = &child.additional_info;
+        if child.n_visits == 0 || info.sum_rewards.is_empty() {
+            return n64(0.);
+        }
+        uct_value(
+            parent_visits,
+            info.sum_rewards[info.mover],
+            child.n_visits,
+            c,
+        )
+    }
+
+    fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult {
+        let rewards = child.rewards();
+        MaxnReward {
+            reward: rewards[State::player_index(turn)],
+            rewards,
+        }
+    }
+}
+
+/// Adds the reward of each player to the `MaxnInfo` of each node of the branch, then
+/// backpropagates the reward of the player of the root like `DefaultBackProp`, so the mean
+/// reward of the nodes, `LazyMcts::root_value` and the analyses stay the ones of the player of
+/// the root. The rewards are floats, use it with the `MaxnEvaluator`.
+pub struct MaxnBackProp;
+
+impl<Move: Clone> BackPropPolicy<Vec<Move>, Move, f64, MaxnInfo, MaxnReward> for MaxnBackProp {
+    fn backprop(
+        tree: &mut Tree<MctsNode<Vec<Move>, Move, f64, MaxnInfo>>,
+        leaf: NodeId,
+        eval: MaxnReward,
+    ) {
+        let mut current_node_id = Some(leaf);
+        while let Some(node_id) = current_node_id {
+            let mut node = tree.get_mut(node_id).unwrap();
+            let sum_rewards = &mut node.value().additional_info.sum_rewards;
+            if sum_rewards.is_empty() {
+                *sum_rewards = vec![0.; eval.rewards.len()];
+            }
+            for (sum, reward) in sum_rewards.iter_mut().zip(&eval.rewards) {
+                *sum += reward;
+            }
+            current_node_id = node.parent().map(|parent| parent.id());
+        }
+        DefaultBackProp::backprop(tree, leaf, eval.reward);
+    }
+}
+
+/// Selects and expands like the `DefaultLazyTreePolicy`, and records in each new node the
+/// player who played its move, whose rewards the `MaxnEvaluator` maximizes. Use it with the
+/// `MaxnEvaluator` and the `MaxnBackProp`.
+pub struct MaxnTreePolicy<State> {
+    _marker: PhantomData<State>,
+}
+
+impl<State: MultiplayerGame> LazyTreePolicy<State, MaxnEvaluator, MaxnInfo, f64>
+    for MaxnTreePolicy<State>
+{
+    fn tree_policy(
+        tree: &mut LazyMctsTree<State, f64, MaxnInfo>,
+        root_state: State,
+        c: &f64,
+        rng: &mut dyn RngCore,
+    ) -> (NodeId, State) {
+        let turn = root_state.player_turn();
+        let node_id =
+            DefaultLazyTreePolicy::<State, MaxnEvaluator, MaxnInfo, f64>::select(tree, &turn, c);
+        let node = tree.get(node_id).unwrap();
+        let historic = historic(node);
+        let state = Self::update_state(root_state, &historic);
+        if !node.value().can_add_child() {
+            return (node_id, state);
+        }
+        let mover = State::player_index(&state.player_turn());
+        let index = rng.gen_range(0..node.value().unvisited_moves.len());
+        let (new_id, new_state) =
+            DefaultLazyTreePolicy::<State, MaxnEvaluator, MaxnInfo, f64>::expand_move(
+                tree.get_mut(node_id).unwrap(),
+                state,
+                historic,
+                index,
+            );
+        tree.get_mut(new_id).unwrap().value().additional_info.mover = mover;
+        (new_id, new_state)
+    }
+
+    fn best_child(
+        tree: &LazyMctsTree<State, f64, MaxnInfo>,
+        turn: &State::Player,
+        parent_id: NodeId,
+        c: &f64,
+    ) -> NodeId {
+        DefaultLazyTreePolicy::<State, MaxnEvaluator, MaxnInfo, f64>::best_child(
+            tree, turn, parent_id, c,
+        )
+    }
+}
+
+#[test]
+fn test_maxn_lets_each_player_maximize_its_reward() {
+    use crate::{BestMoveCriterion, DefaultPlayout, LazyMcts};
+
+    /// The first of three players draws with the move 1, or plays 0 and lets the second player
+    /// choose the winner between itself, with 0, and the first player, with 1.
+    #[derive(Debug, Clone)]
+    struct Kingmaker(Vec<u8>);
+
+    impl GameTrait for Kingmaker {
+        type Player = u8;
+        type Move = u8;
+
+        fn legals_moves(&self) -> Vec<Self::Move> {
+            if self.is_final() {
+                vec![]
+            } else {
+                vec![0, 1]
+            }
+        }
+
+        fn player_turn(&self) -> Self::Player {
+            self.0.len() as u8 % 3
+        }
+
+        fn hash(&self) -> u64 {
+            self.0.iter().fold(1, |hash, &mv| hash * 3 + u64::from(mv))
+        }
+
+        fn is_final(&self) -> bool {
+            self.0[..] == [1] || self.0.len() == 2
+        }
+
+        fn do_move(&mut self, m: &Self::Move) {
+            self.0.push(*m);
+        }
+
+        fn get_winner(&self) -> Self::Player {
+            1 - self.0[1]
+        }
+
+        fn terminal_result(&self) -> Option<GameResult<Self::Player>> {
+            match self.0[..] {
+                [1] => Some(GameResult::Draw),
+                [0, _] => Some(GameResult::Win(self.get_winner())),
+                _ => None,
+            }
+        }
+    }
+
+    impl MultiplayerGame for Kingmaker {
+        const PLAYERS: usize = 3;
+
+        fn player_index(player: &Self::Player) -> usize {
+            usize::from(*player)
+        }
+    }
+
+    assert_eq!(Kingmaker(vec![0, 1]).rewards(), [1., 0., 0.]);
+    assert_eq!(Kingmaker(vec![1]).rewards(), [0.5; 3]);
+
+    type Mcts<'a> = LazyMcts<
+        'a,
+        Kingmaker,
+        MaxnTreePolicy<Kingmaker>,
+        DefaultPlayout,
+        MaxnBackProp,
+        MaxnEvaluator,
+        MaxnInfo,
+        f64,
+    >;
+    let state = Kingmaker(vec![]);
+    let mcts = Mcts::deterministic(&state, 0);
+    for _ in 0..500 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    // The second player crowns itself, so the first player is better off with the draw.
+    assert_eq!(mcts.best_move_by(BestMoveCriterion::MostVisited), 1);
+    let kingmaker = mcts
+        .root_stats()
+        .into_iter()
+        .find(|info| info.mv == 0)
+        .unwrap();
+    assert!(kingmaker.mean_reward.unwrap() < 0.3);
+}
@@ -1,12 +1,17 @@
 use std::fmt::{Debug, Error, Formatter};
 use std::ops::{Add, Deref, DerefMut, Div};
 
+use ego_tree::NodeRef;
+use noisy_float::prelude::n64;
 use num_traits::{ToPrimitive, Zero};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-use crate::aliases::Nat;
+use crate::aliases::{Nat, Num};
 use crate::traits::GameTrait;
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MctsNode<T, Move, Reward, AdditionalInfo = ()>
 where
     Reward: Clone,
@@ -15,11 +20,20 @@ where
     AdditionalInfo: Clone + Default,
 {
     pub sum_rewards: Reward,
+    /// The sum of the squares of the rewards, to compute their variance.
+    pub sum_squared_rewards: f64,
+    /// The sum of the weights of the simulations, only kept by the `WeightedBackProp`, `None`
+    /// while the node was never visited by it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub weighted_visits: Option<f64>,
     pub n_visits: Nat,
     /// All the moves who don't have a node. at the creation this list contains all the legals
     /// moves from the state.
     pub unvisited_moves: Vec<Move>,
     pub hash: u64,
+    /// The `GameTrait::static_eval` of the state of the node, see `static_eval`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) static_eval: Option<f64>,
     pub state: T,
     pub additional_info: AdditionalInfo,
 }
@@ -31,6 +45,26 @@ where
     Move: Clone,
     AdditionalInfo: Clone + Default,
 {
+    /// Creates a node of `state` never visited, without unvisited moves, static evaluation nor
+    /// hash. The public fields can then be set, e.g. `node.n_visits = 10`; the static evaluation
+    /// is set by the search when it creates the node.
+    pub fn new(state: T) -> Self
+    where
+        Reward: Zero,
+    {
+        MctsNode {
+            sum_rewards: Reward::zero(),
+            sum_squared_rewards: 0.,
+            weighted_visits: None,
+            n_visits: 0,
+            unvisited_moves: vec![],
+            hash: 0,
+            static_eval: None,
+            state,
+            additional_info: Default::default(),
+        }
+    }
+
     #[inline]
     pub fn can_add_child(&self) -> bool {
         !self.unvisited_moves.is_empty()
@@ -40,6 +74,82 @@ where
     pub fn hash(&self) -> u64 {
         self.hash
     }
+
+    /// Returns the `GameTrait::static_eval` of the state of the node, computed when the node
+    /// was created for the evaluators setting `Evaluator::USES_STATIC_EVAL`, `None` otherwise.
+    #[inline]
+    pub fn static_eval(&self) -> Option<f64> {
+        self.static_eval
+    }
+}
+
+impl<T, Move, Reward, AdditionalInfo> MctsNode<T, Move, Reward, AdditionalInfo>
+where
+    Reward: Clone + ToPrimitive,
+    T: Clone,
+    Move: Clone,
+    AdditionalInfo: Clone + Default,
+{
+    /// Returns the mean reward `sum_rewards / n_visits`, or `None` if the node was never visited.
+    pub fn mean_reward(&self) -> Option<Num> {
+        if self.n_visits == 0 {
+            return None;
+        }
+        Some(n64(
+            self.sum_rewards.to_f64().unwrap() / f64::from(self.n_visits)
+        ))
+    }
+
+    /// Returns the sample variance of the rewards, or `None` with less than 2 visits.
+    pub fn reward_variance(&self) -> Option<f64> {
+        if self.n_visits < 2 {
+            return None;
+        }
+        let n = f64::from(self.n_visits);
+        let sum = self.sum_rewards.to_f64().unwrap();
+        Some(((self.sum_squared_rewards - sum * sum / n) / (n - 1.)).max(0.))
+    }
+}
+
+impl<Move, Reward, AdditionalInfo> MctsNode<Vec<Move>, Move, Reward, AdditionalInfo>
+where
+    Reward: Clone,
+    Move: Clone,
+    AdditionalInfo: Clone + Default,
+{
+    /// Returns the number of moves between the root of the search and this node. It needs the
+    /// full historic of the node, with compact historics use `node.ancestors().count()`.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.state.len()
+    }
+}
+
+/// Returns the moves from the root of the tree to `node`, rebuilt from the last move of the node
+/// and of its ancestors, so it works whether the nodes store their full historic or only their
+/// last move, see `LazyMcts::compact_historics`.
+pub fn historic<Move, Reward, AdditionalInfo>(
+    node: NodeRef<MctsNode<Vec<Move>, Move, Reward, AdditionalInfo>>,
+) -> Vec<Move>
+where
+    Reward: Clone,
+    Move: Clone,
+    AdditionalInfo: Clone + Default,
+{
+    // One more move fits without reallocating, for the tree policy to push the move it expands.
+    let depth = node.ancestors().count();
+    let mut historic = Vec::with_capacity(depth + 1);
+    if node.value().state.len() == depth {
+        historic.extend_from_slice(&node.value().state);
+        return historic;
+    }
+    historic.extend(
+        std::iter::once(node)
+            .chain(node.ancestors())
+            .filter_map(|node| node.value().state.last().cloned()),
+    );
+    historic.reverse();
+    historic
 }
 
 impl<T, M, R, A> Deref for MctsNode<T, M, R, A>
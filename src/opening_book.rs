@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use std::fs::File;
+use std::hash::Hash;
+#[cfg(feature = "serde")]
+use std::io::{self, BufReader, BufWriter, Write};
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The move to play in the positions of an opening, built from a search by
+/// `LazyMcts::opening_book`. A position is identified by the moves played since the root of the
+/// search, so the game loop keeps the moves played since this position and consults the book
+/// before searching, until the game leaves the book:
+///
+/// ```ignore
+/// let mv = match book.get(&played) {
+///     Some(mv) => mv.clone(),
+///     None => mcts_uct_agent(&state, playouts, c),
+/// };
+/// played.push(mv.clone());
+/// ```
+///
+/// With the `serde` feature the book is saved as a JSON list of `[moves, move]` pairs, which
+/// doesn't depend on the tree it was built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OpeningBook<Move: Hash + Eq> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "entries",
+            bound(serialize = "Move: Serialize", deserialize = "Move: DeserializeOwned")
+        )
+    )]
+    moves: HashMap<Vec<Move>, Move>,
+}
+
+impl<Move: Hash + Eq> OpeningBook<Move> {
+    pub fn new() -> Self {
+        OpeningBook {
+            moves: HashMap::new(),
+        }
+    }
+
+    /// Stores `mv` as the move to play after the moves of `historic`.
+    pub fn insert(&mut self, historic: Vec<Move>, mv: Move) {
+        self.moves.insert(historic, mv);
+    }
+
+    /// Returns the move to play after the moves of `historic`, if the position is in the book.
+    pub fn get(&self, historic: &[Move]) -> Option<&Move> {
+        self.moves.get(historic)
+    }
+
+    /// Returns the number of positions of the book.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<Move: Hash + Eq + Serialize + DeserializeOwned> OpeningBook<Move> {
+    /// Writes the book in the file at `path` as JSON.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()
+    }
+
+    /// Reads a book written by `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+}
+
+impl<Move: Hash + Eq> Default for OpeningBook<Move> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Serializes the moves of the book as a list of pairs, JSON maps only have string keys.
+#[cfg(feature = "serde")]
+mod entries {
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S, Move>(
+        moves: &HashMap<Vec<Move>, Move>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        Move: Serialize,
+    {
+        serializer.collect_seq(moves)
+    }
+
+    pub fn deserialize<'de, D, Move>(deserializer: D) -> Result<HashMap<Vec<Move>, Move>, D::Error>
+    where
+        D: Deserializer<'de>,
+        Move: Deserialize<'de> + Hash + Eq,
+    {
+        Ok(Vec::<(Vec<Move>, Move)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_opening_book_round_trip() {
+    let mut book = OpeningBook::new();
+    book.insert(vec![], 2u8);
+    book.insert(vec![2, 1], 3);
+    let path = std::env::temp_dir().join(format!("oxymcts-book-{}.json", std::process::id()));
+    book.save(&path).unwrap();
+    let loaded = OpeningBook::<u8>::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, book);
+    assert_eq!(loaded.get(&[2, 1]), Some(&3));
+    assert_eq!(loaded.get(&[1]), None);
+}
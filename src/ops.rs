@@ -1,17 +1,181 @@
-use noisy_float::prelude::{n64, Float};
+use noisy_float::prelude::n64;
+use num_traits::{FromPrimitive, ToPrimitive};
 
 use crate::aliases::{Nat, Num};
 
 /// Calculates the uct_value.
+///
+/// The visits are converted to `f64` and the whole score is computed in `f64` before being
+/// wrapped in a `Num`, so integer rewards follow the same numeric path as float rewards. `sqrt`
+/// is exactly rounded on every platform but `ln` comes from the platform's math library, so the
+/// last bits of a score can differ between machines. Use an `Evaluator` computing an integer
+/// score when the search must be reproducible across platforms.
 #[inline]
 pub fn uct_value(parent_visits: Nat, sum_rewards: f64, node_visit: Nat, c: f64) -> Num {
-    let sum_rewards = n64(sum_rewards);
-    let exploitation_param = sum_rewards / node_visit as f64;
-    let exploration_param = (n64(parent_visits as f64).ln() / (node_visit as f64)).sqrt();
-    exploitation_param + n64(c) * exploration_param
+    let (exploitation, exploration) = uct_terms(parent_visits, sum_rewards, node_visit, c);
+    n64(exploitation + exploration)
+}
+
+/// Returns the two terms of the `uct_value`, whose sum is the score: the exploitation term, the
+/// mean reward, and the exploration term, `c * sqrt(ln(parent_visits) / node_visit)`.
+#[inline]
+pub fn uct_terms(parent_visits: Nat, sum_rewards: f64, node_visit: Nat, c: f64) -> (f64, f64) {
+    let node_visit = f64::from(node_visit);
+    let exploitation_param = sum_rewards / node_visit;
+    let exploration_param = (f64::from(parent_visits).ln() / node_visit).sqrt();
+    (exploitation_param, c * exploration_param)
+}
+
+/// Calculates the uct_value with the exploration constant `c / (1 + ln(node_visit))`, which
+/// shrinks as the node accumulates visits.
+#[inline]
+pub fn adaptive_uct_value(parent_visits: Nat, sum_rewards: f64, node_visit: Nat, c: f64) -> Num {
+    let c = c / (1. + f64::from(node_visit).ln());
+    uct_value(parent_visits, sum_rewards, node_visit, c)
+}
+
+/// Calculates the PUCT score of AlphaZero, `Q + c * prior * sqrt(parent_visits) / (1 +
+/// node_visit)`, where `Q` is the mean reward of the node, 0 before its first visit.
+#[inline]
+pub fn puct_value(
+    parent_visits: Nat,
+    sum_rewards: f64,
+    node_visit: Nat,
+    prior: f64,
+    c: f64,
+) -> Num {
+    let exploitation = if node_visit == 0 {
+        0.
+    } else {
+        sum_rewards / f64::from(node_visit)
+    };
+    let exploration = c * prior * f64::from(parent_visits).sqrt() / (1. + f64::from(node_visit));
+    n64(exploitation + exploration)
+}
+
+/// Calculates the RAVE score, the UCT score whose mean reward is blended with the mean reward
+/// of the all-moves-as-first statistics of the node, `(1 - beta) * Q + beta * Q_amaf +
+/// exploration`, where `beta = sqrt(k / (3 * node_visit + k))` shrinks from 1 to 0 as the node
+/// accumulates visits. `k` is the number of visits at which both means weigh about the same,
+/// there is no blending without AMAF visits.
+#[inline]
+pub fn rave_value(
+    parent_visits: Nat,
+    sum_rewards: f64,
+    node_visit: Nat,
+    amaf_visits: Nat,
+    amaf_rewards: f64,
+    c: f64,
+    k: f64,
+) -> Num {
+    let (exploitation, exploration) = uct_terms(parent_visits, sum_rewards, node_visit, c);
+    if amaf_visits == 0 {
+        return n64(exploitation + exploration);
+    }
+    let beta = (k / (3. * f64::from(node_visit) + k)).sqrt();
+    let amaf_exploitation = amaf_rewards / f64::from(amaf_visits);
+    n64((1. - beta) * exploitation + beta * amaf_exploitation + exploration)
+}
+
+/// Converts the reward `x` computed in `f64` to a reward `R`, rounded to the nearest integer
+/// when `R` can't hold a fraction, instead of truncated by `FromPrimitive::from_f64`.
+pub(crate) fn reward_from_f64<R: FromPrimitive + ToPrimitive>(x: f64) -> R {
+    let holds_fractions = R::from_f64(0.5).and_then(|half| half.to_f64()) == Some(0.5);
+    let x = if holds_fractions { x } else { x.round() };
+    R::from_f64(x).unwrap()
+}
+
+/// Returns the quantile of the standard normal distribution for the probability `p`, strictly
+/// between 0 and 1, by the rational approximation of Acklam whose relative error is below
+/// 1.15e-9.
+#[inline]
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.)
+    };
+    if p < 0.02425 {
+        tail((-2. * p.ln()).sqrt())
+    } else if p > 1. - 0.02425 {
+        -tail((-2. * (1. - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.)
+    }
 }
 
 #[test]
 fn test_uct_value() {
-    assert!((uct_value(500, 0., 10, 2.0_f64.sqrt()) - 1.339088).abs() < 0.00001)
+    assert!((uct_value(500, 0., 10, 2.0_f64.sqrt()).raw() - 1.114864).abs() < 0.00001)
+}
+
+#[test]
+fn test_adaptive_uct_value() {
+    assert_eq!(
+        adaptive_uct_value(500, 3., 1, 2.),
+        uct_value(500, 3., 1, 2.)
+    );
+    assert!(adaptive_uct_value(500, 3., 10, 2.) < uct_value(500, 3., 10, 2.));
+}
+
+#[test]
+fn test_puct_value() {
+    assert_eq!(puct_value(16, 0., 0, 0.5, 2.), n64(4.));
+    assert_eq!(puct_value(16, 3., 3, 0.5, 2.), n64(2.));
+}
+
+#[test]
+fn test_rave_value() {
+    assert_eq!(rave_value(1, 3., 4, 0, 0., 1., 100.), n64(0.75));
+    // beta = sqrt(12 / 24)
+    let beta = 0.5f64.sqrt();
+    let blended = (1. - beta) * 0.75 + beta * 0.25;
+    assert_eq!(rave_value(1, 3., 4, 8, 2., 1., 12.), n64(blended));
+    assert!(rave_value(1, 3., 4, 8, 2., 1., 1e9) < n64(0.26));
+}
+
+#[test]
+fn test_reward_from_f64() {
+    assert_eq!(reward_from_f64::<u32>(2.9), 3);
+    assert_eq!(reward_from_f64::<u32>(2.2), 2);
+    assert_eq!(reward_from_f64::<f32>(2.25), 2.25);
+    assert_eq!(reward_from_f64::<f64>(0.1), 0.1);
+}
+
+#[test]
+fn test_normal_quantile() {
+    assert!((normal_quantile(0.975) - 1.959964).abs() < 1e-6);
+    assert!((normal_quantile(0.01) + 2.326348).abs() < 1e-6);
+    assert_eq!(normal_quantile(0.5), 0.);
 }
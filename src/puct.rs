@@ -0,0 +1,324 @@
+use std::marker::PhantomData;
+
+use ego_tree::NodeId;
+use num_traits::{ToPrimitive, Zero};
+use rand::{Rng, RngCore};
+
+use crate::aliases::{LazyMctsNode, LazyMctsTree};
+use crate::mcts_node::historic;
+use crate::traits::{Evaluator, GameTrait, LazyTreePolicy, PriorPolicy};
+use crate::{puct_value, Nat, Num};
+
+/// The additional info of the nodes of a search with the `PuctTreePolicy`.
+#[derive(Debug, Clone)]
+pub struct PuctInfo<Move> {
+    /// The prior probability of the move leading to the node, given by the prior policy of its
+    /// parent. 0 for the root.
+    pub prior: f64,
+    /// The prior probabilities of the moves of the node, computed the first time one of its
+    /// moves is expanded, from the most to the least likely.
+    pub priors: Vec<(Move, f64)>,
+}
+
+impl<Move> Default for PuctInfo<Move> {
+    fn default() -> Self {
+        PuctInfo {
+            prior: 0.,
+            priors: vec![],
+        }
+    }
+}
+
+/// Evaluates the children with the PUCT score of AlphaZero, `Q + c_puct * P * sqrt(N) / (1 +
+/// n)`, see `puct_value`, where the prior `P` of a child was given by the `PriorPolicy` `P` when
+/// it was expanded. The argument is `c_puct`. An end state is evaluated with the reward of its
+/// `GameTrait::terminal_result` like with the `GameResultEvaluator`, so the rewards are floats.
+pub struct PuctEvaluator<P> {
+    _marker: PhantomData<P>,
+}
+
+impl<State, Reward, P> Evaluator<State, Reward, PuctInfo<State::Move>> for PuctEvaluator<P>
+where
+    State: GameTrait,
+    Reward: Clone + ToPrimitive,
+    P: PriorPolicy<State>,
+{
+    type Args = f64;
+    type EvalResult = f64;
+
+    fn eval_child(
+        child: &LazyMctsNode<State, Reward, PuctInfo<State::Move>>,
+        _turn: &State::Player,
+        parent_visits: Nat,
+        &c_puct: &Self::Args,
+    ) -> Num {
+        puct_value(
+            parent_visits,
+            child.sum_rewards.to_f64().unwrap(),
+            child.n_visits,
+            child.additional_info.prior,
+            c_puct,
+        )
+    }
+
+    fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult {
+        child
+            .terminal_result()
+            .map_or(0.5, |result| result.reward(turn))
+    }
+
+    fn prior(child: &LazyMctsNode<State, Reward, PuctInfo<State::Move>>) -> Option<f64> {
+        Some(child.additional_info.prior)
+    }
+}
+
+/// The tree policy of AlphaZero: from the root, each node chooses by its PUCT score between its
+/// children and its unvisited move with the highest prior, which scores like a child never
+/// visited, `c_puct * P * sqrt(N)`. Descends into the chosen child, or expands the chosen move
+/// and returns its new node. The priors of the moves of a node are computed by the
+/// `PriorPolicy` `P` when the first of them is expanded, and looked up among its unvisited
+/// moves at each visit of the node, which costs the square of the branching factor; a move
+/// which wasn't legal then, e.g. added back by `LazyMcts::prune` to a node whose priors were
+/// computed without it, has a prior of 0.
+pub struct PuctTreePolicy<State, P, Reward> {
+    _marker: PhantomData<(State, P, Reward)>,
+}
+
+impl<State, P, Reward> PuctTreePolicy<State, P, Reward>
+where
+    State: GameTrait,
+    State::Move: PartialEq,
+    P: PriorPolicy<State>,
+    Reward: Clone + ToPrimitive + Zero,
+{
+    /// Computes the priors of the unvisited moves of `node_id`, the state `state`, if they
+    /// weren't already.
+    pub(crate) fn compute_priors(
+        tree: &mut LazyMctsTree<State, Reward, PuctInfo<State::Move>>,
+        node_id: NodeId,
+        state: &State,
+    ) {
+        let mut node = tree.get_mut(node_id).unwrap();
+        let value = node.value();
+        if !value.additional_info.priors.is_empty() || value.unvisited_moves.is_empty() {
+            return;
+        }
+        let priors = P::priors(state, &value.unvisited_moves);
+        let mut priors: Vec<_> = value.unvisited_moves.iter().cloned().zip(priors).collect();
+        priors.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        value.additional_info.priors = priors;
+    }
+
+    /// Returns the unvisited move of `node` with the highest prior, with its prior.
+    fn best_unvisited_move(
+        node: &LazyMctsNode<State, Reward, PuctInfo<State::Move>>,
+    ) -> Option<(usize, f64)> {
+        let prior = |mv: &State::Move| {
+            node.additional_info
+                .priors
+                .iter()
+                .find(|(other, _)| other == mv)
+                .map_or(0., |&(_, prior)| prior)
+        };
+        node.unvisited_moves
+            .iter()
+            .enumerate()
+            .map(|(index, mv)| (index, prior(mv)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// Expands the unvisited move of `node_id` at `index`, whose prior is `prior`, from its state
+    /// `state`, and returns the new node and its state.
+    fn expand(
+        tree: &mut LazyMctsTree<State, Reward, PuctInfo<State::Move>>,
+        node_id: NodeId,
+        mut state: State,
+        index: usize,
+        prior: f64,
+    ) -> (NodeId, State) {
+        let mut new_historic = historic(tree.get(node_id).unwrap());
+        let mut node = tree.get_mut(node_id).unwrap();
+        let mv = node.value().unvisited_moves.swap_remove(index);
+        state.do_move(&mv);
+        new_historic.push(mv);
+        let new_node = LazyMctsNode::<State, Reward, PuctInfo<State::Move>> {
+            unvisited_moves: state.legals_moves(),
+            hash: state.hash(),
+            additional_info: PuctInfo {
+                prior,
+                priors: vec![],
+            },
+            ..LazyMctsNode::<State, Reward, PuctInfo<State::Move>>::new(new_historic)
+        };
+        (node.append(new_node).id(), state)
+    }
+}
+
+impl<State, P, Reward> LazyTreePolicy<State, PuctEvaluator<P>, PuctInfo<State::Move>, Reward>
+    for PuctTreePolicy<State, P, Reward>
+where
+    State: GameTrait,
+    State::Move: PartialEq,
+    P: PriorPolicy<State>,
+    Reward: Clone + ToPrimitive + Zero,
+{
+    fn tree_policy(
+        tree: &mut LazyMctsTree<State, Reward, PuctInfo<State::Move>>,
+        mut state: State,
+        c_puct: &f64,
+        _rng: &mut dyn RngCore,
+    ) -> (NodeId, State) {
+        let turn = state.player_turn();
+        let mut node_id = tree.root().id();
+        loop {
+            Self::compute_priors(tree, node_id, &state);
+            let node = tree.get(node_id).unwrap();
+            let parent_visits = node.value().n_visits;
+            let best_child = node.has_children().then(|| {
+                let child = Self::best_child(tree, &turn, node_id, c_puct);
+                let score = PuctEvaluator::<P>::eval_child(
+                    tree.get(child).unwrap().value(),
+                    &turn,
+                    parent_visits,
+                    c_puct,
+                );
+                (child, score)
+            });
+            let unvisited = Self::best_unvisited_move(node.value());
+            match (unvisited, best_child) {
+                (Some((index, prior)), best_child)
+                    if best_child.is_none_or(|(_, score)| {
+                        puct_value(parent_visits, 0., 0, prior, *c_puct) >= score
+                    }) =>
+                {
+                    return Self::expand(tree, node_id, state, index, prior);
+                }
+                (_, Some((child, _))) => {
+                    state.do_move(tree.get(child).unwrap().value().state.last().unwrap());
+                    node_id = child;
+                }
+                (_, None) => return (node_id, state),
+            }
+        }
+    }
+
+    fn best_child(
+        tree: &LazyMctsTree<State, Reward, PuctInfo<State::Move>>,
+        turn: &State::Player,
+        parent_id: NodeId,
+        c_puct: &f64,
+    ) -> NodeId {
+        let parent = tree.get(parent_id).unwrap();
+        let n_visits = parent.value().n_visits;
+        parent
+            .children()
+            .max_by_key(|child| {
+                PuctEvaluator::<P>::eval_child(child.value(), turn, n_visits, c_puct)
+            })
+            .unwrap()
+            .id()
+    }
+}
+
+/// Mixes Dirichlet noise of concentration `alpha` into the priors of the moves of the root of
+/// `tree`, each becoming `(1 - epsilon) * prior + epsilon * noise`, also in the children of the
+/// root. See `LazyMcts::add_dirichlet_noise`.
+pub(crate) fn mix_root_noise<State, Reward>(
+    tree: &mut LazyMctsTree<State, Reward, PuctInfo<State::Move>>,
+    alpha: f64,
+    epsilon: f64,
+    rng: &mut dyn RngCore,
+) where
+    State: GameTrait,
+    State::Move: PartialEq,
+    Reward: Clone,
+{
+    let mut root = tree.root_mut();
+    let priors = &mut root.value().additional_info.priors;
+    let noise = dirichlet(alpha, priors.len(), rng);
+    for ((_, prior), noise) in priors.iter_mut().zip(noise) {
+        *prior = (1. - epsilon) * *prior + epsilon * noise;
+    }
+    priors.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    let priors = priors.clone();
+    let children: Vec<NodeId> = tree.root().children().map(|child| child.id()).collect();
+    for child in children {
+        let mut child = tree.get_mut(child).unwrap();
+        let value = child.value();
+        let mv = value.state.last().unwrap();
+        if let Some(&(_, prior)) = priors.iter().find(|(other, _)| other == mv) {
+            value.additional_info.prior = prior;
+        }
+    }
+}
+
+/// Draws `n` probabilities from a symmetric Dirichlet distribution of concentration `alpha`, by
+/// normalizing as many draws of the gamma distribution.
+fn dirichlet(alpha: f64, n: usize, rng: &mut dyn RngCore) -> Vec<f64> {
+    let draws: Vec<f64> = (0..n).map(|_| gamma(alpha, rng)).collect();
+    let sum: f64 = draws.iter().sum();
+    draws.into_iter().map(|draw| draw / sum).collect()
+}
+
+/// Draws from the gamma distribution of scale 1 and shape `shape`, by the method of Marsaglia
+/// and Tsang, boosted by a uniform draw below a shape of 1.
+fn gamma(shape: f64, rng: &mut dyn RngCore) -> f64 {
+    if shape < 1. {
+        let u: f64 = 1. - rng.gen::<f64>();
+        return gamma(shape + 1., rng) * u.powf(1. / shape);
+    }
+    let d = shape - 1. / 3.;
+    let c = 1. / (9. * d).sqrt();
+    loop {
+        // A standard normal draw, by the Box-Muller transform.
+        let x = (-2. * (1. - rng.gen::<f64>()).ln()).sqrt()
+            * (2. * std::f64::consts::PI * rng.gen::<f64>()).cos();
+        let v = (1. + c * x).powi(3);
+        if v <= 0. {
+            continue;
+        }
+        let u: f64 = rng.gen();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+#[test]
+fn test_puct_follows_the_priors() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::test_game::{Nim, TakeOnePrior};
+    use crate::{DefaultBackProp, DefaultPlayout, LazyMcts};
+
+    type EV = PuctEvaluator<TakeOnePrior>;
+    type Policy = PuctTreePolicy<Nim, TakeOnePrior, f64>;
+    type Mcts<'a> =
+        LazyMcts<'a, Nim, Policy, DefaultPlayout, DefaultBackProp, EV, PuctInfo<u8>, f64>;
+
+    let state = Nim::new(2);
+    let mcts = Mcts::new(&state);
+    let outcome = mcts.execute_with_outcome(&1.5, ());
+    // The most likely move is expanded first, with its prior.
+    assert_eq!(outcome.depth, 1);
+    assert_eq!(mcts.historic(outcome.leaf), [1]);
+    let analysis = mcts.root_analysis(&1.5);
+    assert_eq!(analysis[0].mv, 1);
+
+    for _ in 0..200 {
+        mcts.execute(&1.5, ());
+    }
+    let explanation = mcts.explain_best_move(&1.5);
+    // Taking both sticks wins at once, the search overrules the prior.
+    assert_eq!(mcts.best_move(&1.5), 2);
+    assert!(explanation.best.mean_reward.unwrap() > 0.5);
+    assert_eq!(mcts.move_visits().iter().map(|&(_, n)| n).sum::<Nat>(), 201);
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let draws = dirichlet(0.03, 1000, &mut rng);
+    assert!((draws.iter().sum::<f64>() - 1.).abs() < 1e-9);
+    assert!(draws.iter().all(|&draw| (0. ..=1.).contains(&draw)));
+    let mean = (0..2000).map(|_| gamma(2.5, &mut rng)).sum::<f64>() / 2000.;
+    assert!((mean - 2.5).abs() < 0.2);
+}
@@ -0,0 +1,226 @@
+use ego_tree::{NodeId, Tree};
+use noisy_float::prelude::n64;
+use num_traits::ToPrimitive;
+
+use crate::aliases::LazyMctsNode;
+use crate::defaults::{DefaultBackProp, DefaultLazyTreePolicy};
+use crate::mcts_node::{historic, MctsNode};
+use crate::traits::{BackPropPolicy, Evaluator, GameTrait};
+use crate::{rave_value, Nat, Num};
+
+/// The all-moves-as-first statistics of the move leading to a node, the additional info of the
+/// nodes of a search with the `RaveBackProp`: the simulations through its parent in which the
+/// player of the parent played the move, at any point after the parent, and the sum of their
+/// rewards.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RaveInfo {
+    pub amaf_visits: Nat,
+    pub amaf_rewards: f64,
+}
+
+/// The evaluation of a simulation by the `RaveEvaluator`: its reward and the moves played by its
+/// playout, which the `RaveBackProp` counts as if they had been played in the tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaveReward<Move> {
+    pub reward: f64,
+    pub playout_moves: Vec<Move>,
+}
+
+/// The arguments of the `RaveEvaluator`.
+#[derive(Debug, Clone, Copy)]
+pub struct RaveArgs {
+    /// The exploration constant of UCT.
+    pub c: f64,
+    /// The equivalence parameter of `rave_value`: the number of visits at which the mean reward
+    /// of a node and its AMAF mean weigh about the same.
+    pub k: f64,
+}
+
+/// Evaluates the nodes with the `rave_value`, which blends their mean reward with their AMAF
+/// mean while they have few visits, and evaluates an end state with the reward of its
+/// `GameTrait::terminal_result` like the `GameResultEvaluator`. The playouts must report their
+/// moves, see `Playout::playout_with_moves`, and only `LazyMcts::execute` and the methods
+/// executing its iterations give them, the leaf parallel iterations backpropagate the rewards
+/// without AMAF statistics.
+pub struct RaveEvaluator;
+
+impl<State, Reward> Evaluator<State, Reward, RaveInfo> for RaveEvaluator
+where
+    State: GameTrait,
+    Reward: Clone + ToPrimitive,
+{
+    type Args = RaveArgs;
+    type EvalResult = RaveReward<State::Move>;
+
+    const USES_PLAYOUT_MOVES: bool = true;
+
+    fn eval_child(
+        child: &LazyMctsNode<State, Reward, RaveInfo>,
+        _turn: &State::Player,
+        parent_visits: Nat,
+        args: &Self::Args,
+    ) -> Num {
+        if child.n_visits == 0 {
+            return n64(0.);
+        }
+        rave_value(
+            parent_visits,
+            child.sum_rewards.to_f64().unwrap(),
+            child.n_visits,
+            child.additional_info.amaf_visits,
+            child.additional_info.amaf_rewards,
+            args.c,
+            args.k,
+        )
+    }
+
+    fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult {
+        RaveReward {
+            reward: child
+                .terminal_result()
+                .map_or(0.5, |result| result.reward(turn)),
+            playout_moves: vec![],
+        }
+    }
+
+    fn evaluate_simulation_with_moves(
+        _leaf: &State,
+        final_state: State,
+        playout_moves: Vec<State::Move>,
+        turn: &State::Player,
+        _args: &Self::Args,
+    ) -> Self::EvalResult {
+        RaveReward {
+            playout_moves,
+            ..<Self as Evaluator<State, Reward, RaveInfo>>::evaluate_leaf(final_state, turn)
+        }
+    }
+}
+
+/// Backpropagates the reward like `DefaultBackProp`, then updates the AMAF statistics: at each
+/// node of the branch, every child whose move was played later in the simulation by the player
+/// of the node, in the tree or in the playout, gets an AMAF visit with the reward. The players
+/// are told apart by the parity of the moves, so it needs a game of two players moving in turn.
+/// The rewards are floats, use it with the `RaveEvaluator`.
+pub struct RaveBackProp;
+
+impl<Move> BackPropPolicy<Vec<Move>, Move, f64, RaveInfo, RaveReward<Move>> for RaveBackProp
+where
+    Move: Clone + PartialEq,
+{
+    fn backprop(
+        tree: &mut Tree<MctsNode<Vec<Move>, Move, f64, RaveInfo>>,
+        leaf: NodeId,
+        eval: RaveReward<Move>,
+    ) {
+        let leaf_node = tree.get(leaf).unwrap();
+        let mut branch: Vec<NodeId> = std::iter::once(leaf_node)
+            .chain(leaf_node.ancestors())
+            .map(|node| node.id())
+            .collect();
+        branch.reverse();
+        let mut moves = historic(leaf_node);
+        moves.extend(eval.playout_moves);
+        for (depth, &node_id) in branch.iter().enumerate() {
+            let children: Vec<NodeId> = tree
+                .get(node_id)
+                .unwrap()
+                .children()
+                .filter(|child| {
+                    let mv = child.value().state.last().unwrap();
+                    moves[depth..].iter().step_by(2).any(|played| played == mv)
+                })
+                .map(|child| child.id())
+                .collect();
+            for child in children {
+                let mut child = tree.get_mut(child).unwrap();
+                let info = &mut child.value().additional_info;
+                info.amaf_visits += 1;
+                info.amaf_rewards += eval.reward;
+            }
+        }
+        DefaultBackProp::backprop(tree, leaf, eval.reward);
+    }
+}
+
+/// The tree policy of a search with the `RaveEvaluator`, which blends the AMAF statistics in the
+/// selection through the evaluator.
+pub type RaveTreePolicy<State, Reward = f64> =
+    DefaultLazyTreePolicy<State, RaveEvaluator, RaveInfo, Reward>;
+
+#[test]
+fn test_rave_backprop_counts_the_moves_of_the_same_player() {
+    use crate::test_game::Nim;
+    use crate::{DefaultPlayout, LazyMcts, LazyMctsTree, Playout};
+
+    let node = |state: Vec<u8>| LazyMctsNode::<Nim, f64, RaveInfo>::new(state);
+    let mut tree = LazyMctsTree::<Nim, f64, RaveInfo>::new(node(vec![]));
+    let one = tree.root_mut().append(node(vec![1])).id();
+    let two = tree.root_mut().append(node(vec![2])).id();
+    let amaf =
+        |tree: &LazyMctsTree<Nim, f64, RaveInfo>, id| tree.get(id).unwrap().value().additional_info;
+    // The player of the root plays 1 then 3, its opponent 2.
+    let eval = RaveReward {
+        reward: 1.,
+        playout_moves: vec![2, 3],
+    };
+    RaveBackProp::backprop(&mut tree, one, eval);
+    assert_eq!(
+        amaf(&tree, one),
+        RaveInfo {
+            amaf_visits: 1,
+            amaf_rewards: 1.
+        }
+    );
+    assert_eq!(amaf(&tree, two), RaveInfo::default());
+    // Now the player of the root plays 1 then 2.
+    let eval = RaveReward {
+        reward: 0.,
+        playout_moves: vec![3, 2],
+    };
+    RaveBackProp::backprop(&mut tree, one, eval);
+    assert_eq!(
+        amaf(&tree, one),
+        RaveInfo {
+            amaf_visits: 2,
+            amaf_rewards: 1.
+        }
+    );
+    assert_eq!(
+        amaf(&tree, two),
+        RaveInfo {
+            amaf_visits: 1,
+            amaf_rewards: 0.
+        }
+    );
+    assert_eq!(tree.get(one).unwrap().value().n_visits, 2);
+    assert_eq!(tree.get(two).unwrap().value().n_visits, 0);
+
+    let mut played = vec![];
+    let mut rng = rand::thread_rng();
+    DefaultPlayout::playout_with_moves(Nim::new(10), (), &mut rng, &mut played);
+    assert_eq!(played.iter().sum::<u8>(), 10);
+
+    type Mcts<'a> = LazyMcts<
+        'a,
+        Nim,
+        RaveTreePolicy<Nim>,
+        DefaultPlayout,
+        RaveBackProp,
+        RaveEvaluator,
+        RaveInfo,
+        f64,
+    >;
+    let state = Nim::new(2);
+    let mcts = Mcts::new(&state);
+    let args = RaveArgs {
+        c: 2f64.sqrt(),
+        k: 100.,
+    };
+    for _ in 0..200 {
+        mcts.execute(&args, ());
+    }
+    assert_eq!(mcts.move_visits().iter().map(|&(_, n)| n).sum::<Nat>(), 200);
+    // Taking both sticks wins at once.
+    assert_eq!(mcts.best_move(&args), 2);
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::aliases::Nat;
+use crate::mcts_node::MctsNode;
+
+/// The visits, the sum of rewards and the sum of squared rewards of the nodes of previous
+/// searches, keyed by the hash of their state. Share it behind an `Arc` between the searches of
+/// several games, see `LazyMcts::with_shared_table`.
+///
+/// The statistics are only as good as the searches which stored them: the rewards are from the
+/// perspective of the player of the root of the search which stored them, so the searches sharing
+/// a table must search for the same player; two states with the same hash share their statistics;
+/// and the statistics of a position are never forgotten even when the policies or the arguments of
+/// the search change, nor when the table grows.
+///
+/// Two states whose hashes collide can be told apart by storing a verification key with their
+/// statistics, see `LazyMcts::verify_shared_table`.
+#[derive(Debug)]
+pub struct SharedTable<R> {
+    stats: Mutex<HashMap<u64, Entry<R>>>,
+    collisions: AtomicUsize,
+}
+
+/// The visits, the sum of rewards, the sum of squared rewards and the verification key of a
+/// state, if the search which stored them verified the table.
+type Entry<R> = (Nat, R, f64, Option<u64>);
+
+impl<R: Clone> SharedTable<R> {
+    pub fn new() -> Self {
+        SharedTable {
+            stats: Mutex::new(HashMap::new()),
+            collisions: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the visits and the sum of rewards stored for the state of hash `hash`.
+    pub fn get(&self, hash: u64) -> Option<(Nat, R)> {
+        self.stats
+            .lock()
+            .unwrap()
+            .get(&hash)
+            .map(|(visits, sum_rewards, ..)| (*visits, sum_rewards.clone()))
+    }
+
+    /// Returns the number of states in the table.
+    pub fn len(&self) -> usize {
+        self.stats.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of nodes which weren't seeded because the statistics stored under
+    /// their hash have another verification key, those of another state whose hash collides.
+    pub fn collisions(&self) -> usize {
+        self.collisions.load(Ordering::Relaxed)
+    }
+
+    /// Stores the statistics of `node` under its hash with the verification key `key` of its
+    /// state, replacing the stored ones.
+    pub(crate) fn store<T: Clone, Move: Clone, A: Clone + Default>(
+        &self,
+        node: &MctsNode<T, Move, R, A>,
+        key: Option<u64>,
+    ) {
+        self.stats.lock().unwrap().insert(
+            node.hash,
+            (
+                node.n_visits,
+                node.sum_rewards.clone(),
+                node.sum_squared_rewards,
+                key,
+            ),
+        );
+    }
+
+    /// Gives the statistics stored under the hash of `node` to `node`, if it was never visited
+    /// and the verification key `key` of its state matches the stored one. The statistics stored
+    /// or looked up without a key always match.
+    pub(crate) fn seed<T: Clone, Move: Clone, A: Clone + Default>(
+        &self,
+        node: &mut MctsNode<T, Move, R, A>,
+        key: Option<u64>,
+    ) {
+        if node.n_visits != 0 {
+            return;
+        }
+        if let Some((visits, sum_rewards, sum_squared_rewards, stored_key)) =
+            self.stats.lock().unwrap().get(&node.hash)
+        {
+            if key.is_some() && stored_key.is_some() && key != *stored_key {
+                self.collisions.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            node.n_visits = *visits;
+            node.sum_rewards = sum_rewards.clone();
+            node.sum_squared_rewards = *sum_squared_rewards;
+        }
+    }
+}
+
+impl<R: Clone> Default for SharedTable<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
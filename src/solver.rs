@@ -0,0 +1,259 @@
+use std::marker::PhantomData;
+
+use ego_tree::{NodeId, NodeRef, Tree};
+use rand::RngCore;
+
+use crate::aliases::{LazyMctsNode, LazyMctsTree};
+use crate::defaults::{DefaultBackProp, DefaultLazyTreePolicy, DefaultUctEvaluator};
+use crate::mcts_node::{historic, MctsNode};
+use crate::traits::{BackPropPolicy, Evaluator, GameTrait, LazyTreePolicy};
+use crate::{Nat, Num};
+
+/// The game theoretic value of a node for the player of the root, when the search proved it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Proof {
+    /// The node wasn't proven yet, or it's a draw.
+    #[default]
+    Unknown,
+    /// The player of the root wins from the node whatever the opponent plays.
+    Win,
+    /// The player of the root loses from the node whatever it plays.
+    Loss,
+}
+
+/// The additional info of the nodes of a search with the MCTS-Solver, see the `SolverBackProp`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SolverInfo {
+    pub proof: Proof,
+    /// Whether an opponent of the player of the root is to move in the node, set by the
+    /// `SolverTreePolicy` when it expands the node. `false` for the root.
+    pub opponent_to_move: bool,
+}
+
+/// The evaluation of a simulation by the `SolverEvaluator`: its reward, and the proof of its leaf
+/// if the leaf is a final state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolverReward {
+    pub reward: f64,
+    pub proof: Proof,
+}
+
+/// Evaluates the nodes with UCT like the `GameResultEvaluator` and the simulations with the
+/// reward of their `GameTrait::terminal_result`. A simulation from a final state proves its leaf,
+/// won or lost for the player of the root, a draw isn't proven.
+pub struct SolverEvaluator;
+
+impl<State, Reward> Evaluator<State, Reward, SolverInfo> for SolverEvaluator
+where
+    State: GameTrait,
+    DefaultUctEvaluator: Evaluator<State, Reward, SolverInfo, Args = f64>,
+    Reward: Clone,
+{
+    type Args = f64;
+    type EvalResult = SolverReward;
+
+    const NEEDS_LEAF: bool = true;
+
+    fn eval_child(
+        child: &LazyMctsNode<State, Reward, SolverInfo>,
+        turn: &State::Player,
+        parent_visits: Nat,
+        c: &Self::Args,
+    ) -> Num {
+        DefaultUctEvaluator::eval_child(child, turn, parent_visits, c)
+    }
+
+    fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult {
+        SolverReward {
+            reward: child
+                .terminal_result()
+                .map_or(0.5, |result| result.reward(turn)),
+            proof: Proof::Unknown,
+        }
+    }
+
+    fn evaluate_simulation(
+        leaf: &State,
+        final_state: State,
+        turn: &State::Player,
+        _args: &Self::Args,
+    ) -> Self::EvalResult {
+        let proof = match leaf.terminal_result().map(|result| result.reward(turn)) {
+            Some(1.) => Proof::Win,
+            Some(0.) => Proof::Loss,
+            _ => Proof::Unknown,
+        };
+        SolverReward {
+            proof,
+            ..<Self as Evaluator<State, Reward, SolverInfo>>::evaluate_leaf(final_state, turn)
+        }
+    }
+}
+
+/// Backpropagates the reward like `DefaultBackProp`, after propagating the proof of the leaf up
+/// the branch: a node is won for the player of the root when it plays there and one of its
+/// children is won, or when the opponent plays there and all its moves are expanded and won, and
+/// lost the other way around. The propagation stops at the first ancestor whose proof doesn't
+/// change. The rewards are floats, use it with the `SolverEvaluator` and the
+/// `SolverTreePolicy`, which tells who plays in each node.
+pub struct SolverBackProp;
+
+impl<Move: Clone> BackPropPolicy<Vec<Move>, Move, f64, SolverInfo, SolverReward>
+    for SolverBackProp
+{
+    fn backprop(
+        tree: &mut Tree<MctsNode<Vec<Move>, Move, f64, SolverInfo>>,
+        leaf: NodeId,
+        eval: SolverReward,
+    ) {
+        if eval.proof != Proof::Unknown {
+            tree.get_mut(leaf).unwrap().value().additional_info.proof = eval.proof;
+            let mut current_node_id = tree.get(leaf).unwrap().parent().map(|parent| parent.id());
+            while let Some(node_id) = current_node_id {
+                let node = tree.get(node_id).unwrap();
+                let proof = prove(node);
+                if proof == node.value().additional_info.proof {
+                    break;
+                }
+                current_node_id = node.parent().map(|parent| parent.id());
+                tree.get_mut(node_id).unwrap().value().additional_info.proof = proof;
+            }
+        }
+        DefaultBackProp::backprop(tree, leaf, eval.reward);
+    }
+}
+
+/// Returns the proof of `node` given the proofs of its children.
+fn prove<T: Clone, Move: Clone, R: Clone>(
+    node: NodeRef<MctsNode<T, Move, R, SolverInfo>>,
+) -> Proof {
+    let (decisive, refuted) = if node.value().additional_info.opponent_to_move {
+        (Proof::Loss, Proof::Win)
+    } else {
+        (Proof::Win, Proof::Loss)
+    };
+    let mut proofs = node
+        .children()
+        .map(|child| child.value().additional_info.proof);
+    if proofs.clone().any(|proof| proof == decisive) {
+        decisive
+    } else if !node.value().can_add_child() && proofs.all(|proof| proof == refuted) {
+        refuted
+    } else {
+        node.value().additional_info.proof
+    }
+}
+
+/// Selects like the `DefaultLazyTreePolicy`, but never descends into a proven node nor into a
+/// child proven bad for the player to move, unless all of them are. The best child of a node is
+/// its child proven good for the player to move when there is one, so once the root is proven
+/// won `LazyMcts::best_move` returns a winning move. Once the root is proven the iterations only
+/// simulate it again, stop the search with `LazyMcts::root_proof`.
+pub struct SolverTreePolicy<State> {
+    _marker: PhantomData<State>,
+}
+
+impl<State: GameTrait> LazyTreePolicy<State, SolverEvaluator, SolverInfo, f64>
+    for SolverTreePolicy<State>
+{
+    fn tree_policy(
+        tree: &mut LazyMctsTree<State, f64, SolverInfo>,
+        root_state: State,
+        c: &f64,
+        rng: &mut dyn RngCore,
+    ) -> (NodeId, State) {
+        let turn = root_state.player_turn();
+        let mut node_id = tree.root().id();
+        loop {
+            let node = tree.get(node_id).unwrap();
+            if node.value().additional_info.proof != Proof::Unknown {
+                let historic = historic(node);
+                return (node_id, Self::update_state(root_state, &historic));
+            }
+            if node.value().can_add_child() || !node.has_children() {
+                break;
+            }
+            node_id = Self::best_child(tree, &turn, node_id, c);
+        }
+        let (new_id, new_state) =
+            DefaultLazyTreePolicy::<State, SolverEvaluator, SolverInfo, f64>::expand(
+                tree.get_mut(node_id).unwrap(),
+                root_state,
+                rng,
+            );
+        if new_id != node_id {
+            let mut new_node = tree.get_mut(new_id).unwrap();
+            new_node.value().additional_info.opponent_to_move = new_state.player_turn() != turn;
+        }
+        (new_id, new_state)
+    }
+
+    fn best_child(
+        tree: &LazyMctsTree<State, f64, SolverInfo>,
+        turn: &State::Player,
+        parent_id: NodeId,
+        c: &f64,
+    ) -> NodeId {
+        let parent = tree.get(parent_id).unwrap();
+        let (decisive, refuted) = if parent.value().additional_info.opponent_to_move {
+            (Proof::Loss, Proof::Win)
+        } else {
+            (Proof::Win, Proof::Loss)
+        };
+        let proof = |child: &NodeRef<LazyMctsNode<State, f64, SolverInfo>>| {
+            child.value().additional_info.proof
+        };
+        if let Some(child) = parent.children().find(|child| proof(child) == decisive) {
+            return child.id();
+        }
+        let n_visits = parent.value().n_visits;
+        let score = |child: &NodeRef<LazyMctsNode<State, f64, SolverInfo>>| {
+            <SolverEvaluator as Evaluator<State, f64, SolverInfo>>::eval_child(
+                child.value(),
+                turn,
+                n_visits,
+                c,
+            )
+        };
+        parent
+            .children()
+            .filter(|child| proof(child) != refuted)
+            .max_by_key(score)
+            .or_else(|| parent.children().max_by_key(score))
+            .unwrap()
+            .id()
+    }
+}
+
+#[test]
+fn test_solver_proves_nim() {
+    use crate::test_game::Nim;
+    use crate::{DefaultPlayout, LazyMcts};
+
+    type Mcts<'a> = LazyMcts<
+        'a,
+        Nim,
+        SolverTreePolicy<Nim>,
+        DefaultPlayout,
+        SolverBackProp,
+        SolverEvaluator,
+        SolverInfo,
+        f64,
+    >;
+
+    let solve = |sticks| {
+        let state = Nim::new(sticks);
+        let mcts = Mcts::deterministic(&state, 0);
+        let mut iterations = 0;
+        while mcts.root_proof() == Proof::Unknown {
+            mcts.execute(&2f64.sqrt(), ());
+            iterations += 1;
+            assert!(iterations < 10_000, "Nim {} wasn't solved", sticks);
+        }
+        (mcts.root_proof(), mcts.best_move(&2f64.sqrt()))
+    };
+    // Leaving a multiple of 4 sticks wins.
+    assert_eq!(solve(5), (Proof::Win, 1));
+    assert_eq!(solve(7), (Proof::Win, 3));
+    assert_eq!(solve(8).0, Proof::Loss);
+}
@@ -0,0 +1,332 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use noisy_float::prelude::n64;
+use rand::seq::SliceRandom;
+use rand::RngCore;
+
+use crate::{GameTrait, Num, PriorPolicy};
+
+/// Players take turns removing one to three sticks, the player who takes the last stick wins.
+#[derive(Debug, Clone)]
+pub struct Nim {
+    pub sticks: u8,
+    pub turn: u8,
+}
+
+impl Nim {
+    pub fn new(sticks: u8) -> Self {
+        Nim { sticks, turn: 1 }
+    }
+}
+
+impl GameTrait for Nim {
+    type Player = u8;
+    type Move = u8;
+
+    fn initial_state() -> Self {
+        Nim::new(10)
+    }
+
+    fn legals_moves(&self) -> Vec<Self::Move> {
+        (1..=self.sticks.min(3)).collect()
+    }
+
+    fn player_turn(&self) -> Self::Player {
+        self.turn
+    }
+
+    fn hash(&self) -> u64 {
+        self.sticks as u64 * 2 + self.turn as u64
+    }
+
+    fn is_final(&self) -> bool {
+        self.sticks == 0
+    }
+
+    fn do_move(&mut self, m: &Self::Move) {
+        self.sticks -= m;
+        self.turn = 3 - self.turn;
+    }
+
+    fn get_winner(&self) -> Self::Player {
+        3 - self.turn
+    }
+}
+
+/// Prefers taking a single stick, with a prior of 0.8.
+pub struct TakeOnePrior;
+
+impl PriorPolicy<Nim> for TakeOnePrior {
+    fn priors(_state: &Nim, moves: &[u8]) -> Vec<f64> {
+        let others = (moves.len() - 1).max(1) as f64;
+        moves
+            .iter()
+            .map(|&mv| if mv == 1 { 0.8 } else { 0.2 / others })
+            .collect()
+    }
+}
+
+/// A Nim whose opponent always takes a single stick, see `GameTrait::opponent_move`.
+#[derive(Clone)]
+pub struct TakeOneOpponent(pub Nim);
+
+impl GameTrait for TakeOneOpponent {
+    type Player = u8;
+    type Move = u8;
+
+    fn legals_moves(&self) -> Vec<u8> {
+        self.0.legals_moves()
+    }
+
+    fn player_turn(&self) -> u8 {
+        self.0.player_turn()
+    }
+
+    fn hash(&self) -> u64 {
+        self.0.hash()
+    }
+
+    fn is_final(&self) -> bool {
+        self.0.is_final()
+    }
+
+    fn do_move(&mut self, m: &u8) {
+        self.0.do_move(m)
+    }
+
+    fn get_winner(&self) -> u8 {
+        self.0.get_winner()
+    }
+
+    fn opponent_move(&self, _rng: &mut dyn RngCore) -> Option<u8> {
+        Some(1)
+    }
+}
+
+static ROTATION: AtomicU8 = AtomicU8::new(0);
+
+/// A Nim whose `legals_moves` are rotated at each call, the playouts use the moves of
+/// `legal_moves_into` which are always sorted.
+#[derive(Debug, Clone)]
+pub struct RotatingNim(pub Nim);
+
+impl GameTrait for RotatingNim {
+    type Player = u8;
+    type Move = u8;
+
+    fn legals_moves(&self) -> Vec<u8> {
+        let mut moves = self.0.legals_moves();
+        let len = moves.len().max(1);
+        moves.rotate_left(ROTATION.fetch_add(1, Ordering::Relaxed) as usize % len);
+        moves
+    }
+
+    fn legal_moves_into(&self, buf: &mut Vec<u8>) {
+        buf.clear();
+        buf.extend(self.0.legals_moves());
+    }
+
+    fn player_turn(&self) -> u8 {
+        self.0.player_turn()
+    }
+
+    fn hash(&self) -> u64 {
+        self.0.hash()
+    }
+
+    fn is_final(&self) -> bool {
+        self.0.is_final()
+    }
+
+    fn do_move(&mut self, m: &u8) {
+        self.0.do_move(m)
+    }
+
+    fn get_winner(&self) -> u8 {
+        self.0.get_winner()
+    }
+}
+
+/// A Nim enumerating its moves in a random order.
+#[derive(Clone)]
+pub struct ShuffledNim(pub Nim);
+
+impl GameTrait for ShuffledNim {
+    type Player = u8;
+    type Move = u8;
+
+    fn legals_moves(&self) -> Vec<u8> {
+        let mut moves = self.0.legals_moves();
+        moves.shuffle(&mut rand::thread_rng());
+        moves
+    }
+
+    fn player_turn(&self) -> u8 {
+        self.0.player_turn()
+    }
+
+    fn hash(&self) -> u64 {
+        self.0.hash()
+    }
+
+    fn is_final(&self) -> bool {
+        self.0.is_final()
+    }
+
+    fn do_move(&mut self, m: &u8) {
+        self.0.do_move(m)
+    }
+
+    fn get_winner(&self) -> u8 {
+        self.0.get_winner()
+    }
+}
+
+/// A Nim whose `legals_moves` returns each move twice.
+#[derive(Debug, Clone)]
+pub struct DoubledNim(pub Nim);
+
+impl GameTrait for DoubledNim {
+    type Player = u8;
+    type Move = u8;
+
+    fn legals_moves(&self) -> Vec<u8> {
+        let moves = self.0.legals_moves();
+        moves.iter().chain(&moves).copied().collect()
+    }
+
+    fn player_turn(&self) -> u8 {
+        self.0.player_turn()
+    }
+
+    fn hash(&self) -> u64 {
+        self.0.hash()
+    }
+
+    fn is_final(&self) -> bool {
+        self.0.is_final()
+    }
+
+    fn do_move(&mut self, m: &u8) {
+        self.0.do_move(m)
+    }
+
+    fn get_winner(&self) -> u8 {
+        self.0.get_winner()
+    }
+}
+
+/// A Nim where taking `-n` sticks is the same as taking `n` sticks, see `GameTrait::symmetries`.
+#[derive(Clone)]
+pub struct MirroredNim(pub Nim);
+
+impl GameTrait for MirroredNim {
+    type Player = u8;
+    type Move = i8;
+
+    fn legals_moves(&self) -> Vec<i8> {
+        self.0
+            .legals_moves()
+            .into_iter()
+            .flat_map(|mv| [mv as i8, -(mv as i8)])
+            .collect()
+    }
+
+    fn player_turn(&self) -> u8 {
+        self.0.player_turn()
+    }
+
+    fn hash(&self) -> u64 {
+        self.0.hash()
+    }
+
+    fn is_final(&self) -> bool {
+        self.0.is_final()
+    }
+
+    fn do_move(&mut self, m: &i8) {
+        self.0.do_move(&m.unsigned_abs())
+    }
+
+    fn get_winner(&self) -> u8 {
+        self.0.get_winner()
+    }
+
+    fn symmetries(&self, mv: &i8) -> Vec<i8> {
+        vec![-mv]
+    }
+}
+
+/// A Nim writing its moves as the sticks left after them, see `GameTrait::move_notation`.
+#[derive(Clone)]
+pub struct NotatedNim(pub Nim);
+
+impl GameTrait for NotatedNim {
+    type Player = u8;
+    type Move = u8;
+
+    fn legals_moves(&self) -> Vec<u8> {
+        self.0.legals_moves()
+    }
+
+    fn player_turn(&self) -> u8 {
+        self.0.player_turn()
+    }
+
+    fn hash(&self) -> u64 {
+        self.0.hash()
+    }
+
+    fn is_final(&self) -> bool {
+        self.0.is_final()
+    }
+
+    fn do_move(&mut self, m: &u8) {
+        self.0.do_move(m)
+    }
+
+    fn get_winner(&self) -> u8 {
+        self.0.get_winner()
+    }
+
+    fn move_notation(&self, mv: &u8) -> String {
+        format!("-> {}", self.0.sticks - mv)
+    }
+}
+
+/// A Nim estimating each of its positions at 0.5, see `GameTrait::static_eval`.
+#[derive(Clone)]
+pub struct EvaluatedNim(pub Nim);
+
+impl GameTrait for EvaluatedNim {
+    type Player = u8;
+    type Move = u8;
+
+    fn legals_moves(&self) -> Vec<u8> {
+        self.0.legals_moves()
+    }
+
+    fn player_turn(&self) -> u8 {
+        self.0.player_turn()
+    }
+
+    fn hash(&self) -> u64 {
+        self.0.hash()
+    }
+
+    fn is_final(&self) -> bool {
+        self.0.is_final()
+    }
+
+    fn do_move(&mut self, m: &u8) {
+        self.0.do_move(m)
+    }
+
+    fn get_winner(&self) -> u8 {
+        self.0.get_winner()
+    }
+
+    fn static_eval(&self) -> Option<Num> {
+        Some(n64(0.5))
+    }
+}
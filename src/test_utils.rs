@@ -0,0 +1,193 @@
+use num_traits::ToPrimitive;
+
+use crate::traits::{Evaluator, GameTrait};
+use crate::Num;
+
+/// Checks that `EV` evaluates each of the final `states` consistently for the two `players`: the
+/// rewards it gives them are the ones of the `GameTrait::terminal_result` of the state, 1 for a
+/// win, 0 for a loss and 0.5 for a draw, so a win for one of them is a loss for the other. The
+/// states are evaluated by `Evaluator::evaluate_simulation`, as if they were simulated leaves,
+/// with the arguments `args`. Panics with the first inconsistency found, which is usually an
+/// evaluator rewarding the wrong player. Needs the `test-utils` feature.
+pub fn check_evaluator_symmetry<EV, State, R, A>(
+    states: &[State],
+    players: [State::Player; 2],
+    args: &EV::Args,
+) where
+    EV: Evaluator<State, R, A>,
+    EV::EvalResult: ToPrimitive,
+    State: GameTrait,
+    R: Clone,
+    A: Clone + Default,
+{
+    for (index, state) in states.iter().enumerate() {
+        let result = state
+            .terminal_result()
+            .unwrap_or_else(|| panic!("The state {} isn't final", index));
+        for player in &players {
+            let reward = EV::evaluate_simulation(state, state.clone(), player, args)
+                .to_f64()
+                .unwrap();
+            let expected = result.reward(player);
+            assert!(
+                (reward - expected).abs() < 1e-9,
+                "The state {} ended with {:?}, the evaluator gives {} to {:?} instead of {}",
+                index,
+                result,
+                reward,
+                player,
+                expected
+            );
+        }
+    }
+}
+
+/// How two search configurations compared on a set of positions, see `compare_searches`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgreementReport {
+    /// The number of compared positions.
+    pub positions: usize,
+    /// The share of the positions where both configurations chose the same best move.
+    pub same_best_move: f64,
+    /// The mean of the absolute differences between the root values of the configurations.
+    pub mean_value_difference: f64,
+    /// The largest absolute difference between the root values of the configurations.
+    pub max_value_difference: f64,
+    /// The indices of the positions where the configurations chose different best moves.
+    pub disagreements: Vec<usize>,
+}
+
+/// Runs two search configurations on each of the `positions` and reports how much they agree,
+/// to check that a change of the search, e.g. an optimization of the hot path, doesn't change
+/// its choices. A configuration searches a position with the given seed and returns its best
+/// move and its root value, e.g. with `LazyMcts::deterministic`, `LazyMcts::best_move` and
+/// `LazyMcts::root_value`. Both configurations get the seed `seed + i` for the i-th position,
+/// so two configurations drawing the same random numbers are expected to agree exactly. Needs
+/// the `test-utils` feature.
+pub fn compare_searches<Game>(
+    positions: &[Game],
+    config_a: impl Fn(&Game, u64) -> (Game::Move, Num),
+    config_b: impl Fn(&Game, u64) -> (Game::Move, Num),
+    seed: u64,
+) -> AgreementReport
+where
+    Game: GameTrait,
+    Game::Move: PartialEq,
+{
+    let mut disagreements = vec![];
+    let mut sum_differences = 0.;
+    let mut max_value_difference: f64 = 0.;
+    for (index, position) in positions.iter().enumerate() {
+        let position_seed = seed.wrapping_add(index as u64);
+        let (move_a, value_a) = config_a(position, position_seed);
+        let (move_b, value_b) = config_b(position, position_seed);
+        if move_a != move_b {
+            disagreements.push(index);
+        }
+        let difference = (value_a - value_b).raw().abs();
+        sum_differences += difference;
+        max_value_difference = max_value_difference.max(difference);
+    }
+    let positions = positions.len();
+    let share = |count: f64| {
+        if positions == 0 {
+            0.
+        } else {
+            count / positions as f64
+        }
+    };
+    AgreementReport {
+        positions,
+        same_best_move: share((positions - disagreements.len()) as f64),
+        mean_value_difference: share(sum_differences),
+        max_value_difference,
+        disagreements,
+    }
+}
+
+#[test]
+fn test_check_evaluator_symmetry() {
+    use crate::test_game::Nim;
+    use crate::{DefaultUctEvaluator, GameResultEvaluator, LazyMctsNode, Nat, Num};
+
+    let states = [Nim { sticks: 0, turn: 1 }, Nim { sticks: 0, turn: 2 }];
+    check_evaluator_symmetry::<DefaultUctEvaluator, Nim, u32, ()>(&states, [1, 2], &1.);
+    check_evaluator_symmetry::<GameResultEvaluator, Nim, u32, ()>(&states, [1, 2], &1.);
+
+    /// Rewards the player who lost.
+    struct Inverted;
+
+    impl Evaluator<Nim, u32, ()> for Inverted {
+        type Args = ();
+        type EvalResult = Nat;
+
+        fn eval_child(_: &LazyMctsNode<Nim, u32, ()>, _: &u8, _: Nat, _: &()) -> Num {
+            noisy_float::prelude::n64(0.)
+        }
+
+        fn evaluate_leaf(child: Nim, turn: &u8) -> Self::EvalResult {
+            Nat::from(child.get_winner() != *turn)
+        }
+    }
+
+    let result = std::panic::catch_unwind(|| {
+        check_evaluator_symmetry::<Inverted, Nim, u32, ()>(&states, [1, 2], &())
+    });
+    let message = result.unwrap_err();
+    assert_eq!(
+        message.downcast_ref::<String>().unwrap(),
+        "The state 0 ended with Win(2), the evaluator gives 1 to 1 instead of 0"
+    );
+}
+
+#[test]
+fn test_compare_searches() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+    use noisy_float::prelude::n64;
+
+    let search = |compact: bool, c: f64| {
+        move |state: &Nim, seed: u64| {
+            let mut mcts = DefaultMcts::deterministic(state, seed);
+            if compact {
+                mcts.compact_historics();
+            }
+            for _ in 0..100 {
+                mcts.execute(&c, ());
+            }
+            (mcts.best_move(&c), mcts.root_value().unwrap())
+        }
+    };
+    let positions: Vec<Nim> = (2..=9).map(Nim::new).collect();
+    let report = compare_searches(
+        &positions,
+        search(false, 2f64.sqrt()),
+        search(true, 2f64.sqrt()),
+        0,
+    );
+    assert_eq!(
+        report,
+        AgreementReport {
+            positions: 8,
+            same_best_move: 1.,
+            mean_value_difference: 0.,
+            max_value_difference: 0.,
+            disagreements: vec![],
+        }
+    );
+
+    let report = compare_searches(
+        &positions,
+        search(false, 2f64.sqrt()),
+        |state: &Nim, _| (state.sticks.min(3) % 3 + 1, n64(0.5)),
+        0,
+    );
+    assert!(!report.disagreements.is_empty());
+    assert_eq!(
+        report.same_best_move,
+        1. - report.disagreements.len() as f64 / 8.
+    );
+    assert!(report.max_value_difference > 0.);
+    assert!(report.mean_value_difference <= report.max_value_difference);
+    assert!(format!("{:?}", report).starts_with("AgreementReport { positions: 8"));
+}
@@ -1,22 +1,68 @@
 use std::fmt::Debug;
 
 use ego_tree::{NodeId, Tree};
+use rand::RngCore;
 
 use crate::aliases::{LazyMctsNode, LazyMctsTree, Num};
 use crate::mcts_node::MctsNode;
 use crate::Nat;
 
+/// A game searched by the MCTS. The policies are only given the states, so the moves and their
+/// effects can only depend on the state, and a context shared by all the states of a search, e.g.
+/// the cards of a deck, belongs in the state behind an `Arc`, which the clones of the state share.
 pub trait GameTrait: Clone {
+    /// A player of the game, returned by `player_turn` and compared by the evaluators with the
+    /// winner of a final state.
     type Player: Debug + Clone + Eq;
     type Move: Debug + Clone;
 
+    /// A hint of the maximum number of legal moves of a state, used to pre-size the buffers of
+    /// moves, those of the playouts and the one the unvisited moves of a new node are generated
+    /// in by `legal_moves_into`. The buffers only avoid the allocations when the game implements
+    /// `legal_moves_into`, the default one allocating the `Vec` of `legals_moves` and copying it.
+    const MAX_BRANCHING: usize = 32;
+
     /// Returns a list of legal_move, for the actual player.
     fn legals_moves(&self) -> Vec<Self::Move>;
 
+    /// Clears `buf` and fills it with the legal moves, for the actual player. Override it to
+    /// avoid allocating a new `Vec` each time the moves are generated, the pre-sizing by
+    /// `MAX_BRANCHING` requiring it.
+    fn legal_moves_into(&self, buf: &mut Vec<Self::Move>) {
+        buf.clear();
+        buf.extend(self.legals_moves());
+    }
+
+    /// Returns a copy of the state on which moves can be played, used in the hot path of the
+    /// search to get the state of the root at each iteration. Override it to share the data which
+    /// never changes, e.g. behind an `Arc`, instead of cloning it. Defaults to `Clone::clone`.
+    fn clone_light(&self) -> Self {
+        self.clone()
+    }
+
+    /// Returns the number of legal moves, for the actual player. Override it when the moves can
+    /// be counted without generating them.
+    fn legal_moves_count(&self) -> usize {
+        self.legals_moves().len()
+    }
+
+    /// Returns the state at the beginning of a game, used by `play_full_game`. Panics by default,
+    /// for the games which only search the states they are given.
+    fn initial_state() -> Self {
+        unimplemented!("The game has no initial state, implement GameTrait::initial_state")
+    }
+
     /// Return the player actually playing.
     fn player_turn(&self) -> Self::Player;
 
     /// Return if any the hash of the game state.
+    ///
+    /// The nodes are identified by their `NodeId`, an index in the tree which can't collide, and
+    /// the hash only identifies the states shared between searches, in a `SharedTable`. Two of
+    /// `n` distinct states have the same 64 bits hash with a probability of about `n² / 2^65`:
+    /// negligible below a hundred million states, it reaches 40% around four billion, and the
+    /// states colliding then share their statistics. The searches storing that many states in a
+    /// shared table should hash the parts of the state which matter, see `LazyMcts::set_hasher`.
     fn hash(&self) -> u64;
 
     /// If the game is finished this return true.
@@ -27,6 +73,105 @@ pub trait GameTrait: Clone {
 
     /// If the game is finished this function returns the winner of the game.
     fn get_winner(&self) -> Self::Player;
+
+    /// If the game is finished this function returns its result, by default the winner given by
+    /// `get_winner`. Override it for games which can end in a draw.
+    fn terminal_result(&self) -> Option<GameResult<Self::Player>> {
+        if self.is_final() {
+            Some(GameResult::Win(self.get_winner()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a cheap static estimate, between 0 and 1, of the reward of the player who played
+    /// the last move, used by the `ProgressiveBiasEvaluator` to guide the selection before the
+    /// nodes have enough visits. `None` by default, when the game has no such estimate. Only
+    /// called for the evaluators setting `Evaluator::USES_STATIC_EVAL`.
+    fn static_eval(&self) -> Option<Num> {
+        None
+    }
+
+    /// Returns the outcomes of the random event of this state with their probabilities, summing
+    /// to 1, when it's a chance node, e.g. the rolls of a die, whose outcomes are its legal moves
+    /// and aren't chosen by a player. Empty by default, for a decision node. Only the
+    /// `ChanceTreePolicy`, the `ChanceBackProp` and the `ChancePlayout` handle the chance nodes,
+    /// the other policies choose an outcome like a move.
+    fn chance_outcomes(&self) -> Vec<(Self::Move, f64)> {
+        vec![]
+    }
+
+    /// Returns how early the unvisited move `mv` of this state should be expanded by the
+    /// `ProgressiveWideningTreePolicy`, which expands the move with the highest priority first,
+    /// so the few children a widened node gets are the moves the game expects to be the best.
+    /// The ties are broken randomly. 0 by default, the moves are then expanded in a random order.
+    fn expansion_priority(&self, _mv: &Self::Move) -> f64 {
+        0.
+    }
+
+    /// Returns whether the state is quiet, i.e. not in the middle of a tactical sequence like an
+    /// exchange of pieces whose evaluation would swing with the next moves. The
+    /// `DepthCutoffPlayout` keeps playing past its cutoff until it reaches a quiet state. `true`
+    /// by default.
+    fn is_quiet(&self) -> bool {
+        true
+    }
+
+    /// Returns the move `mv` of this state in the notation of the game, e.g. the standard algebraic
+    /// notation of chess, used by the renderings of the tree like
+    /// `LazyMcts::write_tree_with_notation`, e.g. in the traces of `play_full_game`. Defaults to
+    /// the `Debug` of the move.
+    fn move_notation(&self, mv: &Self::Move) -> String {
+        format!("{:?}", mv)
+    }
+
+    /// Returns the move a known opponent would play in this state, which is one where the
+    /// opponent is to move, so the playouts of the `OpponentModelPlayout` simulate its actual
+    /// policy instead of random moves. `None` by default, the opponent then plays randomly like
+    /// the searching player.
+    fn opponent_move(&self, _rng: &mut dyn RngCore) -> Option<Self::Move> {
+        None
+    }
+
+    /// Returns the moves which are symmetric to `mv` in this state, without `mv` itself: the moves
+    /// leading to states equivalent to the one reached by `mv`, e.g. by a rotation of the board.
+    /// Used with `LazyMcts::share_symmetric_statistics`, which gives the statistics of a move to
+    /// its symmetric moves, so the symmetries must be exact: a move returned here whose value
+    /// differs gets the wrong statistics. Empty by default.
+    fn symmetries(&self, _mv: &Self::Move) -> Vec<Self::Move> {
+        vec![]
+    }
+
+    /// Returns the number of moves played since the beginning of the game, 0 by default for the
+    /// games which don't track it. The historic of a node only counts the moves from the root of
+    /// the search, so the ply of a node is the ply of the root state plus the depth of the node.
+    fn ply(&self) -> usize {
+        0
+    }
+}
+
+/// The result of a finished game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameResult<Player> {
+    /// The player won the game.
+    Win(Player),
+    /// The player lost the game, the other players won it.
+    Loss(Player),
+    Draw,
+}
+
+impl<Player: Eq> GameResult<Player> {
+    /// Returns the reward of `player` for this result: 1 for a win, 0.5 for a draw and 0 for a
+    /// loss.
+    pub fn reward(&self, player: &Player) -> f64 {
+        match self {
+            GameResult::Win(winner) if winner == player => 1.,
+            GameResult::Win(_) => 0.,
+            GameResult::Loss(loser) if loser == player => 0.,
+            GameResult::Loss(_) => 1.,
+            GameResult::Draw => 0.5,
+        }
+    }
 }
 
 pub trait Evaluator<State: GameTrait, Reward: Clone, AdditionalInfo: Clone + Default> {
@@ -45,12 +190,119 @@ pub trait Evaluator<State: GameTrait, Reward: Clone, AdditionalInfo: Clone + Def
     /// Evaluates the a final state, when a simulation is over when call this function to know
     /// the reward.
     fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult;
+
+    /// Returns the prior probability of the move leading to `child`, reported by
+    /// `LazyMcts::root_stats`, for the evaluators keeping one like the `PuctEvaluator`. `None` by
+    /// default.
+    fn prior(_child: &LazyMctsNode<State, Reward, AdditionalInfo>) -> Option<f64> {
+        None
+    }
+
+    /// Evaluates a simulation from `leaf`, the state of the selected node, which ended in
+    /// `final_state`. Defaults to `evaluate_leaf` of the final state, override it to also use the
+    /// state before the playout, like the `BlendedEvaluator`, and set `NEEDS_LEAF`.
+    fn evaluate_simulation(
+        _leaf: &State,
+        final_state: State,
+        turn: &State::Player,
+        _args: &Self::Args,
+    ) -> Self::EvalResult {
+        Self::evaluate_leaf(final_state, turn)
+    }
+
+    /// Whether `evaluate_simulation` reads the leaf. `false` by default, so `LazyMcts::execute`
+    /// plays out the state of the leaf instead of a copy and evaluates the final state by
+    /// `evaluate_leaf`, the leaf is only copied for the evaluators setting it to `true`.
+    const NEEDS_LEAF: bool = false;
+
+    /// Whether `eval_child` reads the full historic of the child, e.g. its length to find the
+    /// player of the node. `false` by default, `LazyMcts::compact_historics` refuses the
+    /// evaluators setting it to `true`, whose nodes would only keep their last move.
+    const NEEDS_FULL_HISTORIC: bool = false;
+
+    /// Whether the simulations are evaluated by `evaluate_simulation_with_moves`, which needs the
+    /// playouts to report the moves they played, see `Playout::playout_with_moves`. `false` by
+    /// default, so the playouts don't record their moves.
+    const USES_PLAYOUT_MOVES: bool = false;
+
+    /// Whether `eval_child` reads the `MctsNode::static_eval` of the children, like the
+    /// `ProgressiveBiasEvaluator`. `false` by default, so `GameTrait::static_eval` is only
+    /// computed when a node is created for the evaluators setting it to `true`.
+    const USES_STATIC_EVAL: bool = false;
+
+    /// Evaluates a simulation like `evaluate_simulation`, with the moves played by its playout,
+    /// for the evaluators whose `USES_PLAYOUT_MOVES` is `true` like the `RaveEvaluator`. Only
+    /// `LazyMcts::execute` and the methods executing its iterations give these moves. Defaults to
+    /// `evaluate_simulation`, ignoring the moves.
+    fn evaluate_simulation_with_moves(
+        leaf: &State,
+        final_state: State,
+        _playout_moves: Vec<State::Move>,
+        turn: &State::Player,
+        args: &Self::Args,
+    ) -> Self::EvalResult {
+        Self::evaluate_simulation(leaf, final_state, turn, args)
+    }
+
+    /// Called with the historic of the simulated node and its evaluation, before the evaluation
+    /// is backpropagated. Does nothing by default.
+    fn record_leaf(_historic: &[State::Move], _eval: &Self::EvalResult, _args: &Self::Args) {}
+}
+
+/// A static evaluation of a state, without playing it until the end.
+pub trait StaticValue<State: GameTrait> {
+    /// Returns the estimated reward of `turn` in `state`, between 0 and 1.
+    fn static_value(state: &State, turn: &State::Player) -> f64;
+}
+
+/// Evaluates many leaves at once instead of a playout per leaf, e.g. a neural network evaluating
+/// a batch on a GPU, see `LazyMcts::execute_batch`.
+pub trait BatchEvaluator<State: GameTrait, EvalResult> {
+    /// Returns the evaluation of each of `leaves` for `turn`, the player of the root, in the same
+    /// order as the leaves.
+    fn evaluate_batch(&mut self, leaves: &[State], turn: &State::Player) -> Vec<EvalResult>;
+}
+
+/// The prior probabilities of the moves of a state, e.g. from a policy network, for the
+/// `PuctTreePolicy`.
+pub trait PriorPolicy<State: GameTrait> {
+    /// Returns the prior probability of each of `moves`, legal moves of `state`, in the same
+    /// order. They should sum to 1.
+    fn priors(state: &State, moves: &[State::Move]) -> Vec<f64>;
+}
+
+/// The weight of a simulation in the statistics of the tree, see the `WeightedEvaluator`.
+pub trait SimulationWeight<State: GameTrait> {
+    /// Returns the weight of the simulation from `leaf` which ended in `final_state`, at least 0.
+    fn weight(leaf: &State, final_state: &State) -> f64;
+}
+
+/// The reward of a simulation with its weight, see the `WeightedBackProp`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeightedReward {
+    pub reward: f64,
+    pub weight: f64,
 }
 
 pub trait Playout<State> {
     type Args;
-    /// Plays the state to have a final state.
-    fn playout(state: State, args: Self::Args) -> State;
+    /// Plays the state to have a final state, the random choices are drawn from `rng`.
+    fn playout(state: State, args: Self::Args, rng: &mut dyn RngCore) -> State;
+
+    /// Plays the state like `playout` and pushes the moves it played on `moves`, in their order,
+    /// for the evaluators using them, see `Evaluator::USES_PLAYOUT_MOVES`. Defaults to `playout`
+    /// without reporting any move, override it to report them like the `DefaultPlayout`.
+    fn playout_with_moves(
+        state: State,
+        args: Self::Args,
+        rng: &mut dyn RngCore,
+        _moves: &mut Vec<State::Move>,
+    ) -> State
+    where
+        State: GameTrait,
+    {
+        Self::playout(state, args, rng)
+    }
 }
 
 pub trait LazyTreePolicy<
@@ -61,11 +313,12 @@ pub trait LazyTreePolicy<
 >
 {
     /// Choose the best node, for example we apply the UCT to choose the best node then we expand
-    /// it and we return the expansion.
+    /// it and we return the expansion. The random choices are drawn from `rng`.
     fn tree_policy(
         tree: &mut LazyMctsTree<State, Reward, A>,
         root_state: State,
         evaluator_args: &EV::Args,
+        rng: &mut dyn RngCore,
     ) -> (NodeId, State);
 
     /// This method is only needed because we don't store the state in each node so we need, to
@@ -100,3 +353,9 @@ pub trait BackPropPolicy<
         playout_result: EvalResult,
     );
 }
+
+/// Transforms the evaluation of a simulation into the reward added to the statistics of the
+/// tree, e.g. to clip it, see the `TransformedBackProp`.
+pub trait RewardTransform<EvalResult, Reward> {
+    fn transform(eval: EvalResult) -> Reward;
+}
@@ -1,21 +1,70 @@
 use core::fmt;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::Hash;
+#[cfg(feature = "serde")]
+use std::io::Read;
+use std::io::{self, Write};
 use std::marker::PhantomData;
 use std::ops::{Add, Div};
-use std::sync::Mutex;
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(feature = "serde")]
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+use std::thread;
+#[cfg(feature = "debug-replay")]
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
 
 use ascii_tree::Tree::{Leaf, Node};
 use ascii_tree::{write_tree, Tree};
-use ego_tree::NodeId;
-use num_traits::{ToPrimitive, Zero};
+use ego_tree::{NodeId, NodeRef};
+use noisy_float::prelude::n64;
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
+use rand::distributions::WeightedIndex;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Serialize};
 
 use crate::aliases::{LazyMctsNode, LazyMctsTree};
-use crate::traits::{BackPropPolicy, GameTrait, LazyTreePolicy, Playout};
-use crate::Evaluator;
+#[cfg(feature = "serde")]
+use crate::checkpoint;
+use crate::edge_table::EdgeTable;
+use crate::mcts_node::historic;
+use crate::opening_book::OpeningBook;
+use crate::ops::reward_from_f64;
+use crate::puct::{mix_root_noise, PuctEvaluator, PuctInfo, PuctTreePolicy};
+use crate::shared_table::SharedTable;
+use crate::solver::{Proof, SolverInfo};
+use crate::traits::{
+    BackPropPolicy, BatchEvaluator, GameTrait, LazyTreePolicy, Playout, PriorPolicy,
+};
+use crate::{normal_quantile, uct_terms, BestMoveCriterion, Evaluator, Nat, Num};
 
 /// This is a special MCTS because it doesn't store the state in the node but instead stores the
 /// historic to the node.
-
+///
+/// The nodes are not keyed by the hash of their state, so two paths leading to the same state
+/// always have their own nodes: the search is a pure tree where each node has a single parent.
+/// A game which can come back to a previous state can't create a cycle either, the repeated state
+/// gets a new node deeper in the branch. The transpositions share their statistics through the
+/// tables keyed by the hash instead of through a node with several parents: `set_edge_table`
+/// shares the statistics of a move between the states with the same hash during the search, and
+/// `with_shared_table` seeds the new nodes with those stored by previous searches.
+///
+/// The children of a node are linked in the order of their expansion, so finding the child of a
+/// move, in `advance_root`, `forget_move`, `evaluate_move`, `merge_weighted` or `replay`, scans
+/// the children of its parent. Those lookups happen once per call, or once per node of the
+/// merged tree, never during the iterations, whose selection evaluates every child anyway.
+///
+/// The iterations can be executed from several threads, the tree being behind a mutex. The
+/// selection and the expansion of an iteration hold its lock, so a single node is created at a
+/// time and the tree grows by at most one node per iteration whatever the number of threads: only
+/// the playouts, and their states, run concurrently.
 pub struct LazyMcts<'a, State, TP, PP, BP, EV, AddInfo, Reward>
 where
     State: GameTrait,
@@ -26,12 +75,456 @@ where
     AddInfo: Clone + Default,
     Reward: Clone,
 {
-    root_state: &'a State,
+    root_state: Cow<'a, State>,
+    /// The root state before the predicted move and the predicted move, while pondering.
+    ponder: Option<(Cow<'a, State>, State::Move)>,
     tree_policy: PhantomData<TP>,
     playout_policy: PhantomData<PP>,
     backprop_policy: PhantomData<BP>,
     evaluator: PhantomData<EV>,
     tree: Mutex<LazyMctsTree<State, Reward, AddInfo>>,
+    rng: Mutex<Box<dyn RngCore + Send>>,
+    observation: Mutex<Option<Observation<'a, State::Move, EV::EvalResult>>>,
+    on_expand: Mutex<Option<ExpandHook<'a, State::Move>>>,
+    /// Whether `observation` holds an observer or `on_expand` a hook, so the unobserved
+    /// iterations don't lock them.
+    observed: bool,
+    node_setup: NodeSetup<State, Reward, AddInfo>,
+    /// The visits below which `best_move` passes over a child, see `set_min_visits_for_selection`.
+    min_visits: Nat,
+    node_bound: NodeBound,
+    #[cfg(feature = "debug-replay")]
+    recording: Recording<State::Move, EV::EvalResult>,
+    #[cfg(feature = "rayon")]
+    playout_pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+/// What happened during an iteration, returned by `LazyMcts::execute_with_outcome`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IterationOutcome<EvalResult> {
+    /// The node which was simulated.
+    pub leaf: NodeId,
+    /// The evaluation of the simulation, which was backpropagated from `leaf`.
+    pub reward: EvalResult,
+    /// Whether `leaf` was created by the expansion of this iteration.
+    pub expanded: bool,
+    /// The depth of `leaf`.
+    pub depth: usize,
+    /// The time spent in the playout of `leaf` and its evaluation.
+    pub playout_time: Duration,
+}
+
+/// Receives the events of the iterations of a search, see `LazyMcts::set_observer`, e.g. to
+/// stream the progress of a search to a user interface. The events of an iteration are sent once
+/// it completed, its leaf selected, simulated and backpropagated, without holding the lock of the
+/// tree, so an observer must not execute iterations of the search itself.
+pub trait SearchObserver<Move, EvalResult>: Send {
+    /// Called when an iteration created the node `node`, the child of `parent` by `mv`.
+    fn on_expand(&mut self, _node: NodeId, _parent: NodeId, _mv: &Move) {}
+
+    /// Called after each iteration, with the leaf it simulated and the reward of the playout.
+    fn on_iteration(&mut self, _outcome: &IterationOutcome<EvalResult>) {}
+
+    /// Called periodically with the statistics of the search since the observer was set.
+    fn on_telemetry(&mut self, _telemetry: &SearchTelemetry) {}
+
+    /// Returns whether `LazyMcts::run` and `LazyMcts::run_until` must stop, checked before each
+    /// of their iterations, as their condition is. Never by default.
+    fn should_stop(&mut self) -> bool {
+        false
+    }
+}
+
+/// The statistics of a search sent to its `SearchObserver`, counted since the observer was set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchTelemetry {
+    pub iterations: usize,
+    /// The nodes of the tree, the root included.
+    pub nodes: usize,
+    pub elapsed: Duration,
+    pub iterations_per_second: f64,
+    /// The nodes created per second.
+    pub nodes_per_second: f64,
+    /// The mean depth of the leaves simulated by the iterations.
+    pub mean_depth: f64,
+    /// See `LazyMcts::root_value`.
+    pub root_value: Option<Num>,
+}
+
+/// An iteration whose playout or evaluation panicked, caught by `LazyMcts::try_execute` once
+/// `LazyMcts::catch_simulation_panics` was called.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaughtPanic<Move> {
+    /// The historic of the node which was simulated.
+    pub leaf: Vec<Move>,
+    /// The message of the panic, if it was a string.
+    pub message: Option<String>,
+    /// The time spent in the simulation until it panicked.
+    pub playout_time: Duration,
+}
+
+/// A batch of leaves to evaluate sent through a channel, see `LazyMcts::execute_batch`. The
+/// thread evaluating the batches answers each request with the evaluations of its leaves, in
+/// their order, on `reply`.
+#[derive(Debug)]
+pub struct BatchRequest<State: GameTrait, EvalResult> {
+    pub leaves: Vec<State>,
+    /// The player of the root of the search, whose rewards are evaluated.
+    pub turn: State::Player,
+    pub reply: Sender<Vec<EvalResult>>,
+}
+
+/// Sends the leaves to the thread receiving the requests and waits for its reply, so several
+/// searches can share the thread owning the model by cloning the sender.
+impl<State: GameTrait, EvalResult> BatchEvaluator<State, EvalResult>
+    for Sender<BatchRequest<State, EvalResult>>
+{
+    fn evaluate_batch(&mut self, leaves: &[State], turn: &State::Player) -> Vec<EvalResult> {
+        let (reply, evaluations) = mpsc::channel();
+        self.send(BatchRequest {
+            leaves: leaves.to_vec(),
+            turn: turn.clone(),
+            reply,
+        })
+        .expect("The thread evaluating the batches hung up");
+        evaluations
+            .recv()
+            .expect("The thread evaluating the batches dropped the request")
+    }
+}
+
+/// A summary of a search, returned by `LazyMcts::statistics`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SearchStatistics<Move> {
+    /// The number of nodes of the tree, the root included.
+    pub nodes: usize,
+    /// The depth of the deepest node.
+    pub max_depth: usize,
+    /// The visits of the root.
+    pub root_visits: Nat,
+    /// See `LazyMcts::effective_branching_factor`.
+    pub effective_branching_factor: f64,
+    /// The most visited move and the mean reward of its child, `None` before the first iteration.
+    pub best_move: Option<(Move, f64)>,
+}
+
+/// The statistics of a child of the root, see `MoveExplanation`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveStats<Move> {
+    pub mv: Move,
+    pub visits: Nat,
+    pub mean_reward: Option<Num>,
+}
+
+/// The statistics of a move of the root, returned by `LazyMcts::root_analysis` and
+/// `LazyMcts::root_stats`. The fields which the search doesn't track for the move are `None`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RootMoveInfo<Move> {
+    pub mv: Move,
+    pub visits: Nat,
+    /// The mean reward of the child, `None` until it's visited.
+    pub mean_reward: Option<f64>,
+    /// The score the evaluator of the search gives the child, e.g. its UCT value, `None` until
+    /// the move is expanded and in the `root_stats`.
+    pub score: Option<f64>,
+    /// The variance of the rewards of the child, `None` below 2 visits.
+    pub variance: Option<f64>,
+    /// The `GameTrait::static_eval` of the position after the move, `None` if the game has none,
+    /// the evaluator doesn't read it or the move isn't expanded.
+    pub static_eval: Option<f64>,
+    /// The prior probability of the move given by the evaluator, see `Evaluator::prior`, e.g.
+    /// the PUCT prior, `None` if the evaluator has none or the move isn't expanded.
+    pub prior: Option<f64>,
+}
+
+/// Why `LazyMcts::explain_best_move` chose its move, its `Display` renders it as a few lines of
+/// text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveExplanation<Move> {
+    /// The chosen move.
+    pub best: MoveStats<Move>,
+    /// The most visited of the other moves, `None` if the root has a single child.
+    pub runner_up: Option<MoveStats<Move>>,
+    /// The chosen move followed by the line of the most visited children.
+    pub principal_variation: Vec<Move>,
+    /// See `LazyMcts::root_value`.
+    pub root_value: Option<Num>,
+}
+
+impl<Move: Debug> Display for MoveStats<Move> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {} visits, mean reward ", self.mv, self.visits)?;
+        match self.mean_reward {
+            Some(mean) => write!(f, "{:.3}", mean),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+impl<Move: Debug> Display for MoveExplanation<Move> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "best move {}", self.best)?;
+        if let Some(runner_up) = &self.runner_up {
+            writeln!(f, "runner-up {}", runner_up)?;
+        }
+        let line: Vec<String> = self
+            .principal_variation
+            .iter()
+            .map(|mv| format!("{:?}", mv))
+            .collect();
+        writeln!(f, "principal variation: {}", line.join(" "))?;
+        match self.root_value {
+            Some(value) => write!(f, "root value: {:.3}", value),
+            None => write!(f, "root value: -"),
+        }
+    }
+}
+
+/// An iteration recorded by `LazyMcts::start_recording`, to replay it with `LazyMcts::replay`.
+#[cfg(feature = "debug-replay")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct IterationRecord<Move, EvalResult> {
+    /// The thread which executed the iteration.
+    pub thread: ThreadId,
+    /// The historic of the node which was simulated.
+    pub leaf: Vec<Move>,
+    /// The move leading to the leaf, if the leaf was created by the expansion of this iteration.
+    pub expanded: Option<Move>,
+    /// The evaluation of the simulation, which was backpropagated from the leaf.
+    pub reward: EvalResult,
+}
+
+/// The iterations recorded since `LazyMcts::start_recording`, `None` when not recording.
+#[cfg(feature = "debug-replay")]
+type Recording<Move, EvalResult> = Mutex<Option<Vec<IterationRecord<Move, EvalResult>>>>;
+
+/// What stopped `LazyMcts::execute_until` or `LazyMcts::run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchStop {
+    /// The tree reached the maximum number of nodes.
+    NodeCap,
+    /// The maximum number of iterations were executed.
+    Iterations,
+    /// The time budget elapsed.
+    Time,
+    /// The stopping condition given to `LazyMcts::run_until` returned true, or the
+    /// `SearchObserver` of the search stopped it.
+    Condition,
+}
+
+/// When `LazyMcts::run` stops, the first limit reached stopping the search. The limits which
+/// are `None` don't stop it, so at least one must be set unless a condition stops the search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// The maximum time of the search, checked after each iteration.
+    pub time: Option<Duration>,
+    pub iterations: Option<usize>,
+    /// The maximum number of nodes of the tree, the root included.
+    pub nodes: Option<usize>,
+}
+
+/// What happened during `LazyMcts::run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchRun {
+    pub iterations: usize,
+    /// The number of nodes created by the iterations.
+    pub expanded: usize,
+    /// The depth of the deepest leaf simulated by the iterations.
+    pub max_depth: usize,
+    pub elapsed: Duration,
+    pub stop: SearchStop,
+}
+
+/// The hook of `LazyMcts::set_on_expand`, called for each new node.
+type ExpandHook<'a, Move> = Box<dyn FnMut(NodeId, NodeId, &Move) + Send + 'a>;
+
+/// The observer of a search with its counters since it was set.
+struct Observation<'a, Move, EvalResult> {
+    observer: Box<dyn SearchObserver<Move, EvalResult> + 'a>,
+    /// The number of iterations between two telemetries, 0 for none.
+    telemetry_every: usize,
+    start: Instant,
+    iterations: usize,
+    expansions: usize,
+    sum_depths: usize,
+}
+
+/// Shares the statistics added by a backpropagation to the branch of a leaf with the symmetric
+/// siblings of its nodes, given the root state and the statistics of the branch before it.
+type ShareSymmetries<State, R, A> = fn(&mut LazyMctsTree<State, R, A>, &State, &[BranchStatistics]);
+
+/// Labels a node which isn't the root given its index among its siblings, in the renderings
+/// of the tree.
+type Label<'l, State, R, A> = dyn Fn(usize, NodeRef<LazyMctsNode<State, R, A>>) -> String + 'l;
+
+/// The id, visits, sum of rewards and sum of squared rewards of a node.
+type BranchStatistics = (NodeId, Nat, f64, f64);
+
+/// The edge table of a search, with the functions seeding a new node from it given the hash of
+/// its parent, and recording the statistics added by a backpropagation to a branch given the
+/// statistics of the branch before it, which need `State::Move: Hash + Eq`.
+struct EdgeSharing<State: GameTrait, R: Clone, A: Clone + Default> {
+    table: Arc<EdgeTable<State::Move>>,
+    seed: SeedFromEdge<State, R, A>,
+    record: RecordInEdges<State, R, A>,
+}
+
+type SeedFromEdge<State, R, A> =
+    fn(&EdgeTable<<State as GameTrait>::Move>, u64, &mut LazyMctsNode<State, R, A>);
+
+type RecordInEdges<State, R, A> =
+    fn(&EdgeTable<<State as GameTrait>::Move>, &LazyMctsTree<State, R, A>, &[BranchStatistics]);
+
+/// The removal of the dominated moves of the root, see `LazyMcts::prune_dominated_root_moves`.
+#[derive(Debug, Clone, Copy)]
+struct DominatedRootMoves {
+    delta: f64,
+    reward_range: f64,
+    every: usize,
+}
+
+impl<State: GameTrait, R: Clone, A: Clone + Default> Clone for EdgeSharing<State, R, A> {
+    fn clone(&self) -> Self {
+        EdgeSharing {
+            table: self.table.clone(),
+            seed: self.seed,
+            record: self.record,
+        }
+    }
+}
+
+/// Completes the nodes created by the tree policy: hashes their state, if asked removes the
+/// duplicates of their unvisited moves and sorts them, if a shared table was given seeds their
+/// statistics, and with compact historics only keeps their last move. Also holds how the
+/// backpropagations are shared with the symmetric siblings and with the edge table, if they
+/// are, whether the dominated moves of the root are removed after them, whether the panics of
+/// the simulations are caught, whether the arena of the tree is kept when the root advances, and
+/// the virtual loss of the selected branches.
+struct NodeSetup<State: GameTrait, R: Clone, A: Clone + Default> {
+    hasher: fn(&State) -> u64,
+    dedup_moves: Option<fn(&mut Vec<State::Move>)>,
+    move_key: Option<fn(&State::Move) -> u64>,
+    shared_table: Option<Arc<SharedTable<R>>>,
+    verification_key: Option<fn(&State) -> u64>,
+    compact_historics: bool,
+    share_symmetries: Option<ShareSymmetries<State, R, A>>,
+    edge_sharing: Option<EdgeSharing<State, R, A>>,
+    dominated_root_moves: Option<DominatedRootMoves>,
+    catch_panics: bool,
+    recycle_nodes: bool,
+    virtual_loss: Nat,
+    max_nodes: Option<usize>,
+}
+
+/// The counters of the nodes of a search bounded by `LazyMcts::set_max_nodes`.
+#[derive(Debug, Default)]
+struct NodeBound {
+    /// The nodes of the tree, counted from the expansions since they were last recounted, so
+    /// it's only exact right after an eviction.
+    nodes: AtomicUsize,
+    evictions: AtomicUsize,
+    /// The nodes removed from the tree by the caught panics of the simulations and with the
+    /// dominated moves of the root, which are still in its arena.
+    detached: AtomicUsize,
+    /// The visits of the root from which its dominated moves are looked for again.
+    next_dominance_pass: AtomicUsize,
+    /// Read by each iteration from its selection to the end of its backpropagation, while it
+    /// holds `NodeId`s the eviction would invalidate, and written by the eviction.
+    gate: RwLock<()>,
+}
+
+impl<State: GameTrait, R: Clone, A: Clone + Default> NodeSetup<State, R, A> {
+    fn setup(&self, node: &mut LazyMctsNode<State, R, A>, state: &State) {
+        node.hash = (self.hasher)(state);
+        self.order_moves(&mut node.unvisited_moves);
+        if let Some(dedup_moves) = self.dedup_moves {
+            dedup_moves(&mut node.unvisited_moves);
+        }
+        if let Some(shared_table) = &self.shared_table {
+            shared_table.seed(node, self.verification_key.map(|key| key(state)));
+        }
+        if self.compact_historics {
+            compact(&mut node.state);
+        }
+    }
+
+    /// Sorts `moves` by the move key if one was given.
+    fn order_moves(&self, moves: &mut [State::Move]) {
+        if let Some(move_key) = self.move_key {
+            moves.sort_by_key(move_key);
+        }
+    }
+}
+
+/// Adds `visits` visits without reward to `node_id` and its ancestors, or removes them.
+fn add_visits<State, R, A>(
+    tree: &mut LazyMctsTree<State, R, A>,
+    node_id: NodeId,
+    visits: Nat,
+    add: bool,
+) where
+    State: GameTrait,
+    R: Clone,
+    A: Clone + Default,
+{
+    let mut current_node_id = Some(node_id);
+    while let Some(id) = current_node_id {
+        let mut node = tree.get_mut(id).unwrap();
+        if add {
+            node.value().n_visits += visits;
+        } else {
+            node.value().n_visits -= visits;
+        }
+        current_node_id = node.parent().map(|parent| parent.id());
+    }
+}
+
+/// Only keeps the last move of `historic`.
+fn compact<Move>(historic: &mut Vec<Move>) {
+    historic.drain(..historic.len().saturating_sub(1));
+    historic.shrink_to_fit();
+}
+
+impl<State: GameTrait, R: Clone, A: Clone + Default> Default for NodeSetup<State, R, A> {
+    fn default() -> Self {
+        NodeSetup {
+            hasher: State::hash,
+            dedup_moves: None,
+            move_key: None,
+            shared_table: None,
+            verification_key: None,
+            compact_historics: false,
+            share_symmetries: None,
+            edge_sharing: None,
+            dominated_root_moves: None,
+            catch_panics: false,
+            recycle_nodes: false,
+            virtual_loss: 0,
+            max_nodes: None,
+        }
+    }
+}
+
+impl<State: GameTrait, R: Clone, A: Clone + Default> Clone for NodeSetup<State, R, A> {
+    fn clone(&self) -> Self {
+        NodeSetup {
+            hasher: self.hasher,
+            dedup_moves: self.dedup_moves,
+            move_key: self.move_key,
+            shared_table: self.shared_table.clone(),
+            verification_key: self.verification_key,
+            compact_historics: self.compact_historics,
+            share_symmetries: self.share_symmetries,
+            edge_sharing: self.edge_sharing.clone(),
+            dominated_root_moves: self.dominated_root_moves,
+            catch_panics: self.catch_panics,
+            recycle_nodes: self.recycle_nodes,
+            virtual_loss: self.virtual_loss,
+            max_nodes: self.max_nodes,
+        }
+    }
 }
 
 impl<'a, State, TP, PP, BP, EV, A, R> LazyMcts<'a, State, TP, PP, BP, EV, A, R>
@@ -49,105 +542,2938 @@ where
     }
 
     pub fn with_capacity(root_state: &'a State, capacity: usize) -> Self {
-        let tree = LazyMctsTree::<State, R, A>::with_capacity(
-            LazyMctsNode::<State, R, A> {
-                sum_rewards: Zero::zero(),
-                n_visits: 0,
-                unvisited_moves: root_state.legals_moves(),
-                hash: root_state.hash(),
-                state: vec![],
-                additional_info: Default::default(),
-            },
-            capacity,
-        );
+        Self::with_rng(root_state, capacity, StdRng::from_entropy())
+    }
+
+    /// Creates a search drawing all its random choices from `rng`.
+    pub fn with_rng(
+        root_state: &'a State,
+        capacity: usize,
+        rng: impl RngCore + Send + 'static,
+    ) -> Self {
+        let tree = Self::new_tree(root_state, capacity, &NodeSetup::default());
+        Self::from_tree(Cow::Borrowed(root_state), tree, Box::new(rng))
+    }
+
+    /// Creates a search whose random choices are seeded by `seed`, so two searches with the same
+    /// seed and the same arguments build the same tree, as long as the game itself is
+    /// deterministic. Useful for tests and for comparable benchmarks.
+    pub fn deterministic(root_state: &'a State, seed: u64) -> Self {
+        Self::with_rng(root_state, 0, StdRng::seed_from_u64(seed))
+    }
+
+    fn new_tree(
+        root_state: &State,
+        capacity: usize,
+        node_setup: &NodeSetup<State, R, A>,
+    ) -> LazyMctsTree<State, R, A> {
+        let mut root = LazyMctsNode::<State, R, A> {
+            unvisited_moves: root_state.legals_moves(),
+            ..LazyMctsNode::<State, R, A>::new(vec![])
+        };
+        Self::setup_node(node_setup, &mut root, root_state);
+        LazyMctsTree::<State, R, A>::with_capacity(root, capacity)
+    }
+
+    fn from_tree(
+        root_state: Cow<'a, State>,
+        tree: LazyMctsTree<State, R, A>,
+        rng: Box<dyn RngCore + Send>,
+    ) -> Self {
         Self {
             root_state,
+            ponder: None,
             tree_policy: PhantomData,
             playout_policy: PhantomData,
             backprop_policy: PhantomData,
             evaluator: PhantomData,
             tree: Mutex::new(tree),
+            rng: Mutex::new(rng),
+            observation: Mutex::new(None),
+            on_expand: Mutex::new(None),
+            observed: false,
+            node_setup: NodeSetup::default(),
+            min_visits: 0,
+            node_bound: NodeBound::default(),
+            #[cfg(feature = "debug-replay")]
+            recording: Mutex::new(None),
+            #[cfg(feature = "rayon")]
+            playout_pool: None,
         }
     }
 
-    /// Executes one selection, expansion?, simulation, backpropagation.
-    pub fn execute(&self, evaluation_args: &EV::Args, playout_args: PP::Args) {
-        let mut tree = self.tree.lock().unwrap();
-        let (node_id, state) = TP::tree_policy(&mut tree, self.root_state.clone(), evaluation_args);
-        drop(tree);
+    /// Creates a search whose new nodes start with the statistics stored in `shared_table` for
+    /// their hash, if any. Call `store_in_shared_table` at the end of the search to keep its
+    /// statistics for the next searches, see `SharedTable` for when they become stale.
+    ///
+    /// The seeded visits are counted as visits of the node, but not of its ancestors: a child
+    /// can have more visits than its parent, e.g. a position stored by a search where it was
+    /// reached by another path, and the visits of the children of a node then add up to more
+    /// than its own. UCT explores such a child less, as if its visits had been made here.
+    pub fn with_shared_table(root_state: &'a State, shared_table: Arc<SharedTable<R>>) -> Self {
+        let mut mcts = Self::new(root_state);
+        mcts.node_setup.shared_table = Some(shared_table);
+        *mcts.tree.get_mut().unwrap() = Self::new_tree(root_state, 0, &mcts.node_setup);
+        mcts
+    }
+
+    /// Creates a search owning a pool of `threads` threads, on which `execute_leaf_pooled` runs
+    /// the playouts. The pool is shared by the clones of the search. The random choices are
+    /// seeded by `seed` like with `deterministic`, or drawn from the entropy of the system with
+    /// `None`. Needs the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn with_playout_pool(
+        root_state: &'a State,
+        threads: usize,
+        seed: Option<u64>,
+    ) -> Result<Self, rayon::ThreadPoolBuildError> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()?;
+        let mut mcts = match seed {
+            Some(seed) => Self::deterministic(root_state, seed),
+            None => Self::new(root_state),
+        };
+        mcts.playout_pool = Some(Arc::new(pool));
+        Ok(mcts)
+    }
+
+    /// Stores the statistics of every node of the tree in the shared table of the search, if it
+    /// was created with one.
+    pub fn store_in_shared_table(&self) {
+        let Some(shared_table) = &self.node_setup.shared_table else {
+            return;
+        };
+        let tree = self.tree.lock().unwrap();
+        let Some(key) = self.node_setup.verification_key else {
+            for node in tree.root().descendants() {
+                shared_table.store(node.value(), None);
+            }
+            return;
+        };
+        let mut stack = vec![(tree.root(), self.root_state.clone_light())];
+        while let Some((node, state)) = stack.pop() {
+            shared_table.store(node.value(), Some(key(&state)));
+            for child in node.children() {
+                let mut child_state = state.clone_light();
+                child_state.do_move(child.value().state.last().unwrap());
+                stack.push((child, child_state));
+            }
+        }
+    }
+
+    /// Stores a verification key with the statistics of each state in the shared table, a
+    /// second hash of the state independent of its hash, and only seeds a new node with the
+    /// statistics stored under its hash if they have the key of its state. Two states whose
+    /// hashes collide then don't share their statistics, the pair of the hash and the key
+    /// identifying a state with 128 bits, and `SharedTable::collisions` counts the nodes which
+    /// weren't seeded. The statistics stored without a key seed any node. Storing the keys needs
+    /// the states of the nodes, which `store_in_shared_table` rebuilds by playing the moves of
+    /// the tree from the root. The nodes already in the tree aren't seeded again.
+    pub fn verify_shared_table(&mut self, key: fn(&State) -> u64) {
+        self.node_setup.verification_key = Some(key);
+    }
+
+    /// Replaces `GameTrait::hash` by `hasher` to compute the hash of the nodes, for example to
+    /// hash only a part of the state. The nodes already in the tree are hashed again.
+    pub fn set_hasher(&mut self, hasher: fn(&State) -> u64) {
+        self.node_setup.hasher = hasher;
+        let tree = self.tree.get_mut().unwrap();
+        let ids: Vec<_> = tree.root().descendants().map(|node| node.id()).collect();
+        for id in ids {
+            let historic = historic(tree.get(id).unwrap());
+            let state = TP::update_state(self.root_state.clone_light(), &historic);
+            tree.get_mut(id).unwrap().value().hash = hasher(&state);
+        }
+    }
+
+    /// Sorts the legal moves of each node by `move_key` before storing them, the nodes already in
+    /// the tree are sorted too. The moves to expand are drawn by their position in the node, so
+    /// without it a game generating its moves in a varying order, e.g. by iterating a `HashSet`,
+    /// makes two runs with the same seed differ. The playouts still play among the moves in the
+    /// order given by the game, which must then also be deterministic to reproduce a search.
+    pub fn ensure_deterministic_order(&mut self, move_key: fn(&State::Move) -> u64) {
+        self.node_setup.move_key = Some(move_key);
+        for node in self.tree.get_mut().unwrap().values_mut() {
+            node.unvisited_moves.sort_by_key(move_key);
+        }
+    }
+
+    /// Removes the duplicates of the legal moves of each node before storing them, for the games
+    /// whose `legals_moves` can return the same move twice, so a move is never expanded twice.
+    /// The moves of the nodes already in the tree are deduplicated too, and those which already
+    /// have a child are removed.
+    pub fn dedup_moves(&mut self)
+    where
+        State::Move: Hash + Eq,
+    {
+        self.node_setup.dedup_moves = Some(dedup::<State::Move>);
+        let tree = self.tree.get_mut().unwrap();
+        let ids: Vec<_> = tree.root().descendants().map(|node| node.id()).collect();
+        for id in ids {
+            let expanded: HashSet<State::Move> = tree
+                .get(id)
+                .unwrap()
+                .children()
+                .filter_map(|child| child.value().state.last().cloned())
+                .collect();
+            let mut node = tree.get_mut(id).unwrap();
+            let unvisited_moves = &mut node.value().unvisited_moves;
+            dedup(unvisited_moves);
+            unvisited_moves.retain(|mv| !expanded.contains(mv));
+        }
+    }
+
+    /// Stores only its last move in each node instead of the full historic from the root, the
+    /// historics already in the tree are shortened too. The historic of a node is then rebuilt
+    /// from its ancestors with `historic` when it's needed, which costs a walk up the tree at each
+    /// iteration but saves the memory of the historics, whose length grows with the depth.
+    /// `MctsNode::depth` then no longer gives the depth of a node, `ancestors().count()` does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the evaluator needs the full historics, see `Evaluator::NEEDS_FULL_HISTORIC`,
+    /// like the `ProgressiveBiasEvaluator` finding the player of a node from the length of its
+    /// historic.
+    pub fn compact_historics(&mut self) {
+        assert!(
+            !EV::NEEDS_FULL_HISTORIC,
+            "The evaluator needs the full historics of the nodes"
+        );
+        self.node_setup.compact_historics = true;
+        for node in self.tree.get_mut().unwrap().values_mut() {
+            compact(&mut node.state);
+        }
+    }
+
+    /// After each backpropagation, gives what it added to the statistics of each node of the
+    /// branch to the siblings of the node reached by the `GameTrait::symmetries` of its move, so
+    /// the symmetric moves learn together while the tree keeps a node per move. Only the siblings
+    /// already expanded get the statistics, the integer rewards rounded to the nearest. The visits
+    /// of the children of a node then no longer sum to the visits of the node.
+    pub fn share_symmetric_statistics(&mut self)
+    where
+        State::Move: PartialEq,
+        R: FromPrimitive,
+    {
+        self.node_setup.share_symmetries = Some(share_with_symmetries::<State, R, A>);
+    }
+
+    /// Shares the statistics of the moves through `edge_table`, or stops sharing them with `None`:
+    /// each backpropagation adds what it gave to each node of the branch to the edge of its move
+    /// from the hash of its parent, and a new node starts with the statistics of its edge, if
+    /// any, so a move reached again from a state with the same hash, in this search or in another
+    /// one sharing the table, doesn't start from scratch. Like with a shared table the seeded
+    /// visits aren't counted by the parent, and the integer sums of rewards are rounded to the
+    /// nearest. See `EdgeTable` for its memory cost.
+    pub fn set_edge_table(&mut self, edge_table: Option<Arc<EdgeTable<State::Move>>>)
+    where
+        State::Move: Hash + Eq,
+        R: FromPrimitive,
+    {
+        self.node_setup.edge_sharing = edge_table.map(|table| EdgeSharing {
+            table,
+            seed: seed_from_edge_table::<State, R, A>,
+            record: record_in_edge_table::<State, R, A>,
+        });
+    }
+
+    /// Removes from the root, after the iterations giving it `every` visits, the children which
+    /// can't be the best move, so the remaining iterations are spent on the contending moves,
+    /// like the successive elimination of the best arm identification. The mean reward of a child
+    /// is bounded by the Hoeffding bound `mean ± reward_range * sqrt(ln(2 / delta) / (2 *
+    /// visits))`, which holds with a probability of at least `1 - delta` for rewards spread over
+    /// `reward_range`, the difference between the highest and the lowest reward, e.g. 1 for the
+    /// rewards between 0 and 1. A child is removed when its upper bound is below the lower bound
+    /// of the child with the best one. A removed move is forgotten like with `forget_move`, so
+    /// `best_move` never returns it; the moves not expanded yet are kept until they are compared.
+    /// The lower `delta`, the more visits a move needs to be removed and the rarer a wrong
+    /// removal. The nodes of the removed moves are freed with the ones of the caught panics.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `reward_range` isn't positive or `every` is 0.
+    pub fn prune_dominated_root_moves(&mut self, delta: f64, reward_range: f64, every: usize) {
+        assert!(reward_range > 0., "The reward range must be positive");
+        assert!(
+            every > 0,
+            "The dominated moves can't be looked for every 0 visits"
+        );
+        self.node_setup.dominated_root_moves = Some(DominatedRootMoves {
+            delta,
+            reward_range,
+            every,
+        });
+    }
+
+    /// Catches the panics of the playouts and of the evaluation of the simulations, so an iteration
+    /// hitting a bug of the game is dropped instead of aborting the whole search: the node it
+    /// created is removed and its move is given back to the unvisited moves of its parent, nothing
+    /// is backpropagated, and the search goes on with the next iterations. `execute` ignores the
+    /// dropped iterations, `try_execute` returns them as a `CaughtPanic`. The panic is
+    /// still reported by the panic hook, which prints it on the standard error by default.
+    ///
+    /// It's opt-in because it hides the bugs it catches, from a search which looks fine and only
+    /// got fewer iterations, and because the unwinding may leave the state shared through the
+    /// arguments inconsistent, e.g. a mutex of a table is poisoned if the panic happened while
+    /// it was locked, which then fails the next iterations. The selection, the expansion and the
+    /// backpropagation aren't guarded, nor the playouts of `execute_leaf_parallel` and
+    /// `execute_leaf_pooled`.
+    pub fn catch_simulation_panics(&mut self) {
+        self.node_setup.catch_panics = true;
+    }
+
+    /// Keeps the capacity of the arena of the tree when `advance_root` moves the root, so the
+    /// searches of a long game fill the slots of the nodes removed with the rest of the old tree
+    /// instead of growing a new arena after each move. The nodes of the tree are stored in a
+    /// single arena, so there is no allocation per node to recycle, only the regrowth of the
+    /// arena; the moves and the historics of the kept nodes are moved to the new tree in any
+    /// case. The tradeoff is memory: the tree holds the slots of the largest tree of the game
+    /// until `shrink_to_fit` is called.
+    pub fn recycle_nodes(&mut self) {
+        self.node_setup.recycle_nodes = true;
+    }
+
+    /// Adds `virtual_loss` visits with a reward of 0 to each node of the branch selected by an
+    /// iteration until its result is backpropagated, so the iterations running at the same time
+    /// on other threads, with `execute_many` or `execute` from several threads, see the branch
+    /// as worse and spread over the tree instead of all simulating the same leaf. The virtual
+    /// visits are removed before the backpropagation, so the statistics end up the same; only
+    /// the selection of the concurrent iterations changes. It's 0 by default, a single thread
+    /// never sees it. The larger it is, the more the threads diverge from the best line.
+    pub fn set_virtual_loss(&mut self, virtual_loss: Nat) {
+        self.node_setup.virtual_loss = virtual_loss;
+    }
+
+    /// Bounds the tree to `max_nodes` nodes, or removes the bound with `None`, so a long search
+    /// degrades instead of running out of memory, where the `nodes` of the `SearchLimits` stop
+    /// it. When an iteration takes the tree over the bound, the least visited leaves are evicted
+    /// until the tree is an eighth below it, and the tree is copied to a new arena with room for
+    /// `max_nodes` nodes to free them. Like with `prune` the move of an evicted leaf is given
+    /// back to its parent, whose statistics still count the visits made through it. The
+    /// eviction changes the `NodeId`s of the nodes, those of the outcomes of the iterations and
+    /// of the expansion hook included, so it waits for the running iterations to end, and the
+    /// iterations starting meanwhile, e.g. on the other threads of `execute_many`, wait for it.
+    /// See `evictions` and `node_count`.
+    pub fn set_max_nodes(&mut self, max_nodes: Option<usize>) {
+        self.node_setup.max_nodes = max_nodes;
+        let nodes = self.node_count();
+        self.node_bound.nodes.store(nodes, Ordering::Relaxed);
+        self.bound_nodes();
+    }
+
+    /// Returns the number of nodes evicted to keep the tree under the bound of `set_max_nodes`.
+    pub fn evictions(&self) -> usize {
+        self.node_bound.evictions.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of nodes of the tree, without the nodes removed from it which are
+    /// still in its arena.
+    pub fn node_count(&self) -> usize {
+        self.tree.lock().unwrap().root().descendants().count()
+    }
+
+    /// Marks the start of an iteration, whose `NodeId`s must not be invalidated by an eviction
+    /// until the returned guard is given to `end_iteration`. Before its selection, the start of
+    /// every iteration is the point where the tree is brought back under its bound.
+    fn begin_iteration(&self) -> RwLockReadGuard<'_, ()> {
+        self.bound_nodes();
+        self.node_bound.gate.read().unwrap()
+    }
 
-        let final_state = PP::playout(state, playout_args);
-        let eval = EV::evaluate_leaf(final_state, &self.root_state.player_turn());
+    /// Marks the end of the iteration started by `iteration`, after which the dominated moves of
+    /// the root are removed and the tree is brought back under its bound.
+    fn end_iteration(&self, iteration: RwLockReadGuard<'_, ()>) {
+        drop(iteration);
+        self.prune_dominated();
+        self.bound_nodes();
+    }
 
+    /// Removes the dominated moves of the root once it got `every` visits since the last time,
+    /// see `prune_dominated_root_moves`.
+    fn prune_dominated(&self) {
+        let Some(dominated) = self.node_setup.dominated_root_moves else {
+            return;
+        };
         let mut tree = self.tree.lock().unwrap();
-        BP::backprop(&mut tree, node_id, eval);
+        let root_visits = tree.root().value().n_visits as usize;
+        if root_visits < self.node_bound.next_dominance_pass.load(Ordering::Relaxed) {
+            return;
+        }
+        self.node_bound
+            .next_dominance_pass
+            .store(root_visits + dominated.every, Ordering::Relaxed);
+        let detached = remove_dominated_root_moves::<State, R, A>(
+            &mut tree,
+            dominated.delta,
+            dominated.reward_range,
+        );
+        self.node_bound
+            .detached
+            .fetch_add(detached, Ordering::Relaxed);
     }
 
-    /// Returns the best move from the root.
-    pub fn best_move(&self, evaluator_args: &EV::Args) -> State::Move {
-        let tree = self.tree.lock().unwrap();
-        let best_child = TP::best_child(
-            &tree,
+    /// Evicts the least visited leaves if the tree is over the bound of `set_max_nodes`, and
+    /// frees the nodes detached by the caught panics or with the dominated moves of the root once
+    /// they are a quarter of the tree, by
+    /// copying the tree to a new arena. Waits for the running iterations to end, the iterations
+    /// starting meanwhile wait for it.
+    fn bound_nodes(&self) {
+        let nodes = self.node_bound.nodes.load(Ordering::Relaxed);
+        let over = self
+            .node_setup
+            .max_nodes
+            .is_some_and(|max_nodes| nodes > max_nodes);
+        let detached = self.node_bound.detached.load(Ordering::Relaxed);
+        if !over && (detached == 0 || detached * 4 < nodes) {
+            return;
+        }
+        let _gate = self.node_bound.gate.write().unwrap();
+        let mut tree = self.tree.lock().unwrap();
+        let mut nodes = tree.root().descendants().count();
+        let mut evicted = 0;
+        if let Some(max_nodes) = self
+            .node_setup
+            .max_nodes
+            .filter(|&max_nodes| nodes > max_nodes)
+        {
+            let target = max_nodes - max_nodes / 8;
+            evicted = evict_least_visited_leaves::<State, R, A>(
+                &mut tree,
+                nodes - target,
+                &self.node_setup,
+            );
+            self.node_bound
+                .evictions
+                .fetch_add(evicted, Ordering::Relaxed);
+            nodes -= evicted;
+        }
+        if evicted > 0 || self.node_bound.detached.swap(0, Ordering::Relaxed) > 0 {
+            let capacity = self
+                .node_setup
+                .max_nodes
+                .map_or(nodes, |max_nodes| max_nodes + 1);
+            let tree = &mut *tree;
+            *tree = subtree::<State, R, A>(tree, tree.root().id(), capacity, SubtreeMode::Take);
+        }
+        self.node_bound.nodes.store(nodes, Ordering::Relaxed);
+    }
+
+    /// Sets a hook called after each iteration which created a node, with the id of the new
+    /// node, the id of its parent and the move leading to it, replacing the previous one if any.
+    /// The hook is called with the `SearchObserver::on_expand` of the observer set by
+    /// `set_observer`, which it doesn't replace.
+    pub fn set_on_expand(
+        &mut self,
+        on_expand: impl FnMut(NodeId, NodeId, &State::Move) + Send + 'a,
+    ) {
+        *self.on_expand.get_mut().unwrap() = Some(Box::new(on_expand));
+        self.observed = true;
+    }
+
+    /// Removes the hook set by `set_on_expand`.
+    pub fn clear_on_expand(&mut self) {
+        *self.on_expand.get_mut().unwrap() = None;
+        self.observed = self.observation.get_mut().unwrap().is_some();
+    }
+
+    /// Sets the observer receiving the events of the iterations, replacing the previous one if
+    /// any, and the statistics of the search every `telemetry_every` iterations, or never with 0.
+    /// The iterations of `execute`, of `execute_many`, of `execute_batch` and of the loops built
+    /// on them are observed, the leaf-parallel iterations, with several playouts per leaf, only
+    /// send their expansions. The observer can stop `run` and `run_until`, see
+    /// `SearchObserver::should_stop`.
+    pub fn set_observer(
+        &mut self,
+        observer: impl SearchObserver<State::Move, EV::EvalResult> + 'a,
+        telemetry_every: usize,
+    ) {
+        *self.observation.get_mut().unwrap() = Some(Observation {
+            observer: Box::new(observer),
+            telemetry_every,
+            start: Instant::now(),
+            iterations: 0,
+            expansions: 0,
+            sum_depths: 0,
+        });
+        self.observed = true;
+    }
+
+    /// Removes the observer set by `set_observer`.
+    pub fn clear_observer(&mut self) {
+        *self.observation.get_mut().unwrap() = None;
+        self.observed = self.on_expand.get_mut().unwrap().is_some();
+    }
+
+    /// Executes one selection, expansion?, simulation, backpropagation.
+    pub fn execute(&self, evaluation_args: &EV::Args, playout_args: PP::Args) {
+        let _ = self.try_execute(evaluation_args, playout_args);
+    }
+
+    /// Executes one iteration like `execute` and returns what happened during it, for the
+    /// callers running their own loop of iterations. See `try_execute` once
+    /// `catch_simulation_panics` was called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the simulation panicked and the panic was caught.
+    pub fn execute_with_outcome(
+        &self,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+    ) -> IterationOutcome<EV::EvalResult> {
+        self.try_execute(evaluation_args, playout_args)
+            .unwrap_or_else(|caught| {
+                panic!(
+                    "The simulation of {:?} panicked, use try_execute",
+                    caught.leaf
+                )
+            })
+    }
+
+    /// Executes one iteration like `execute_with_outcome`, which only fails with the panic of
+    /// the simulation once `catch_simulation_panics` was called. The iteration draws its random
+    /// numbers from its own generator seeded by the search, so the generator of the search is
+    /// only locked for the seed and the playouts of concurrent calls don't wait for each other.
+    pub fn try_execute(
+        &self,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+    ) -> Result<IterationOutcome<EV::EvalResult>, CaughtPanic<State::Move>> {
+        let iteration = self.begin_iteration();
+        let mut rng = StdRng::seed_from_u64(self.rng.lock().unwrap().next_u64());
+        let outcome = Self::execute_from(
+            &self.tree,
+            &self.root_state,
             &self.root_state.player_turn(),
-            tree.root().id(),
-            evaluator_args,
+            evaluation_args,
+            playout_args,
+            &mut rng,
+            &self.node_setup,
         );
-        tree.get(best_child)
-            .unwrap()
-            .value()
-            .state
-            .last()
-            .expect("The historic of the children of the root is empty, cannot happen")
-            .clone()
+        self.complete_attempt(outcome.as_ref());
+        self.end_iteration(iteration);
+        outcome
     }
 
-    pub fn write_tree(&self) -> String {
-        let tree = self.dfs(self.tree.lock().unwrap().root().id());
-        let mut output = String::new();
-        write_tree(&mut output, &tree).unwrap();
-        output
+    /// Executes `iterations` iterations on `threads` threads sharing the tree, a tree
+    /// parallelization: each thread runs whole iterations, the selection, the expansion and the
+    /// backpropagation under the lock of the tree like `execute` from several threads, while
+    /// the playouts run concurrently. Each thread draws its random numbers from its own
+    /// generator seeded by the search, so they don't wait for each other's generator; the tree
+    /// still depends on how the threads interleave, so a seeded search isn't reproducible with
+    /// more than one thread. Several threads may simulate the same leaf at the same time unless
+    /// `set_virtual_loss` spreads them. The search is shared between the threads so it must be
+    /// `Sync`, which needs the states to be `Send + Sync` and the moves, the rewards and the
+    /// additional infos to be `Send`, the statistics being only updated under the lock.
+    pub fn execute_many(
+        &self,
+        iterations: usize,
+        threads: usize,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+    ) where
+        Self: Sync,
+        EV::Args: Sync,
+        PP::Args: Clone + Send,
+    {
+        let next_iteration = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for _ in 0..threads.max(1) {
+                let mut thread_rng = StdRng::from_rng(&mut **self.rng.lock().unwrap()).unwrap();
+                let (next_iteration, playout_args) = (&next_iteration, playout_args.clone());
+                scope.spawn(move || {
+                    while next_iteration.fetch_add(1, Ordering::Relaxed) < iterations {
+                        let iteration = self.begin_iteration();
+                        let outcome = Self::execute_from(
+                            &self.tree,
+                            &self.root_state,
+                            &self.root_state.player_turn(),
+                            evaluation_args,
+                            playout_args.clone(),
+                            &mut thread_rng,
+                            &self.node_setup,
+                        );
+                        self.complete_attempt(outcome.as_ref());
+                        self.end_iteration(iteration);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Completes the iteration of `outcome` if it wasn't dropped by a caught panic, whose node
+    /// may be left in the arena of the tree until `bound_nodes` frees it.
+    fn complete_attempt(
+        &self,
+        outcome: Result<&IterationOutcome<EV::EvalResult>, &CaughtPanic<State::Move>>,
+    ) {
+        match outcome {
+            Ok(outcome) => self.complete_iteration(outcome),
+            Err(_) => {
+                self.node_bound.detached.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Records the iteration of `outcome` if the search is recording, and sends the iteration and
+    /// the node it created if any to the observer.
+    fn complete_iteration(&self, outcome: &IterationOutcome<EV::EvalResult>) {
+        #[cfg(feature = "debug-replay")]
+        self.record(outcome.leaf, outcome.expanded, &outcome.reward);
+        if outcome.expanded {
+            self.node_bound.nodes.fetch_add(1, Ordering::Relaxed);
+            self.notify_expansion(outcome.leaf);
+        }
+        if !self.observed {
+            return;
+        }
+        let mut observation = self.observation.lock().unwrap();
+        if let Some(observation) = observation.as_mut() {
+            observation.iterations += 1;
+            observation.sum_depths += outcome.depth;
+            observation.observer.on_iteration(outcome);
+            let every = observation.telemetry_every;
+            if every > 0 && observation.iterations.is_multiple_of(every) {
+                let telemetry = self.telemetry(observation);
+                observation.observer.on_telemetry(&telemetry);
+            }
+        }
+    }
+
+    fn telemetry(&self, observation: &Observation<State::Move, EV::EvalResult>) -> SearchTelemetry {
+        let elapsed = observation.start.elapsed();
+        let seconds = elapsed.as_secs_f64();
+        SearchTelemetry {
+            iterations: observation.iterations,
+            nodes: self.node_count(),
+            elapsed,
+            iterations_per_second: observation.iterations as f64 / seconds,
+            nodes_per_second: observation.expansions as f64 / seconds,
+            mean_depth: observation.sum_depths as f64 / observation.iterations as f64,
+            root_value: self.root_value(),
+        }
+    }
+
+    /// Returns whether the observer tells `run_until` to stop.
+    fn observer_stops(&self) -> bool {
+        self.observed
+            && self
+                .observation
+                .lock()
+                .unwrap()
+                .as_mut()
+                .is_some_and(|observation| observation.observer.should_stop())
     }
 
-    fn dfs(&self, node_id: NodeId) -> Tree {
+    /// Starts recording the iterations, from any thread, until `take_recording` is called. The
+    /// iterations of `execute_leaf_parallel` are recorded once per playout.
+    #[cfg(feature = "debug-replay")]
+    pub fn start_recording(&self) {
+        *self.recording.lock().unwrap() = Some(vec![]);
+    }
+
+    /// Stops the recording and returns the recorded iterations, in the order they were recorded.
+    #[cfg(feature = "debug-replay")]
+    pub fn take_recording(&self) -> Vec<IterationRecord<State::Move, EV::EvalResult>> {
+        self.recording.lock().unwrap().take().unwrap_or_default()
+    }
+
+    #[cfg(feature = "debug-replay")]
+    fn record(&self, leaf: NodeId, expanded: bool, reward: &EV::EvalResult) {
+        let mut recording = self.recording.lock().unwrap();
+        if let Some(recording) = recording.as_mut() {
+            let leaf = historic(self.tree.lock().unwrap().get(leaf).unwrap());
+            recording.push(IterationRecord {
+                thread: thread::current().id(),
+                expanded: if expanded { leaf.last().cloned() } else { None },
+                leaf,
+                reward: reward.clone(),
+            });
+        }
+    }
+
+    /// Applies the recorded iterations on a single thread, on a search of the same root state: the
+    /// nodes of each leaf are created if they are missing, then its reward is backpropagated. From
+    /// a new search it rebuilds the nodes and the statistics of the recorded search, without
+    /// drawing any random number. The nodes are created in the order of the records, which can
+    /// differ from the order of the expansions when several threads searched, so the order of the
+    /// siblings and of the unvisited moves may differ.
+    #[cfg(feature = "debug-replay")]
+    pub fn replay(
+        &mut self,
+        records: &[IterationRecord<State::Move, EV::EvalResult>],
+        evaluation_args: &EV::Args,
+    ) where
+        State::Move: PartialEq,
+    {
+        let tree = self.tree.get_mut().unwrap();
+        for record in records {
+            let mut node_id = tree.root().id();
+            let mut state = self.root_state.clone_light();
+            for mv in &record.leaf {
+                state.do_move(mv);
+                let child = tree
+                    .get(node_id)
+                    .unwrap()
+                    .children()
+                    .find(|child| child.value().state.last() == Some(mv))
+                    .map(|child| child.id());
+                node_id = match child {
+                    Some(child) => child,
+                    None => {
+                        let mut new_historic = historic(tree.get(node_id).unwrap());
+                        new_historic.push(mv.clone());
+                        let mut parent = tree.get_mut(node_id).unwrap();
+                        parent
+                            .value()
+                            .unvisited_moves
+                            .retain(|unvisited| unvisited != mv);
+                        let mut node = LazyMctsNode::<State, R, A> {
+                            unvisited_moves: state.legals_moves(),
+                            ..LazyMctsNode::<State, R, A>::new(new_historic)
+                        };
+                        Self::setup_node(&self.node_setup, &mut node, &state);
+                        parent.append(node).id()
+                    }
+                };
+            }
+            EV::record_leaf(&record.leaf, &record.reward, evaluation_args);
+            Self::backprop(
+                tree,
+                &self.root_state,
+                node_id,
+                record.reward.clone(),
+                &self.node_setup,
+            );
+        }
+    }
+
+    fn notify_expansion(&self, node_id: NodeId) {
+        if !self.observed {
+            return;
+        }
         let tree = self.tree.lock().unwrap();
         let node = tree.get(node_id).unwrap();
-        if node.has_children() {
-            let mut nodes = vec![];
-            for c in node.children() {
-                nodes.push(self.dfs(c.id()))
-            }
-            Node(
-                format!("{};{}", node.value().n_visits, node.value().sum_rewards),
-                nodes,
-            )
+        let parent = node.parent().unwrap().id();
+        let mv = node.value().state.last().unwrap().clone();
+        drop(tree);
+        if let Some(on_expand) = self.on_expand.lock().unwrap().as_mut() {
+            on_expand(node_id, parent, &mv);
+        }
+        if let Some(observation) = self.observation.lock().unwrap().as_mut() {
+            observation.expansions += 1;
+            observation.observer.on_expand(node_id, parent, &mv);
+        }
+    }
+
+    /// Executes one iteration on `tree` whose root is `root_state`, the leaves are evaluated
+    /// from the perspective of `turn` and the new node is completed by `node_setup`, which also
+    /// tells whether a panic of the simulation is caught, the node it created being then removed
+    /// from the tree but not from its arena.
+    fn execute_from(
+        tree: &Mutex<LazyMctsTree<State, R, A>>,
+        root_state: &State,
+        turn: &State::Player,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+        rng: &mut dyn RngCore,
+        node_setup: &NodeSetup<State, R, A>,
+    ) -> Result<IterationOutcome<EV::EvalResult>, CaughtPanic<State::Move>> {
+        let mut locked_tree = tree.lock().unwrap();
+        let (node_id, state, expanded) = Self::select_leaf(
+            &mut locked_tree,
+            root_state,
+            evaluation_args,
+            rng,
+            node_setup,
+        );
+        if node_setup.virtual_loss > 0 {
+            add_visits::<State, R, A>(&mut locked_tree, node_id, node_setup.virtual_loss, true);
+        }
+        drop(locked_tree);
+
+        let start = Instant::now();
+        let simulate = || {
+            if EV::USES_PLAYOUT_MOVES {
+                let mut moves = vec![];
+                let final_state =
+                    PP::playout_with_moves(state.clone_light(), playout_args, rng, &mut moves);
+                EV::evaluate_simulation_with_moves(
+                    &state,
+                    final_state,
+                    moves,
+                    turn,
+                    evaluation_args,
+                )
+            } else if EV::NEEDS_LEAF {
+                let final_state = PP::playout(state.clone_light(), playout_args, rng);
+                EV::evaluate_simulation(&state, final_state, turn, evaluation_args)
+            } else {
+                EV::evaluate_leaf(PP::playout(state, playout_args, rng), turn)
+            }
+        };
+        let eval = if node_setup.catch_panics {
+            panic::catch_unwind(AssertUnwindSafe(simulate))
         } else {
-            Leaf(vec![format!(
-                "{};{}",
-                node.value().n_visits,
-                node.value().sum_rewards
-            )])
+            Ok(simulate())
+        };
+        let playout_time = start.elapsed();
+
+        let mut locked_tree = tree.lock().unwrap();
+        if node_setup.virtual_loss > 0 {
+            add_visits::<State, R, A>(&mut locked_tree, node_id, node_setup.virtual_loss, false);
         }
+        let eval = match eval {
+            Ok(eval) => eval,
+            Err(payload) => {
+                let leaf = historic(locked_tree.get(node_id).unwrap());
+                if expanded {
+                    let mut node = locked_tree.get_mut(node_id).unwrap();
+                    let parent = node.parent().unwrap().id();
+                    node.detach();
+                    let mut parent = locked_tree.get_mut(parent).unwrap();
+                    let unvisited_moves = &mut parent.value().unvisited_moves;
+                    unvisited_moves.push(leaf.last().unwrap().clone());
+                    node_setup.order_moves(unvisited_moves);
+                }
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned());
+                return Err(CaughtPanic {
+                    leaf,
+                    message,
+                    playout_time,
+                });
+            }
+        };
+        let historic = historic(locked_tree.get(node_id).unwrap());
+        EV::record_leaf(&historic, &eval, evaluation_args);
+        let depth = historic.len();
+        Self::backprop(
+            &mut locked_tree,
+            root_state,
+            node_id,
+            eval.clone(),
+            node_setup,
+        );
+        Ok(IterationOutcome {
+            leaf: node_id,
+            reward: eval,
+            expanded,
+            depth,
+            playout_time,
+        })
     }
-}
 
-impl<State, TP, PP, BP, EV, A, R> Debug for LazyMcts<'_, State, TP, PP, BP, EV, A, R>
-where
-    State: GameTrait,
-    TP: LazyTreePolicy<State, EV, A, R>,
-    PP: Playout<State>,
-    BP: BackPropPolicy<Vec<State::Move>, State::Move, R, A, EV::EvalResult>,
-    EV: Evaluator<State, R, A>,
-    EV::EvalResult: Debug,
-    A: Clone + Default + Debug,
-    R: Clone + Debug + Div + Add + Zero + ToPrimitive,
-{
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str(&format!("{:?}", self.tree))
+    /// Backpropagates `eval` from `node_id` with the backprop policy, then shares it with the
+    /// symmetric siblings of the branch if `node_setup` asks to.
+    fn backprop(
+        tree: &mut LazyMctsTree<State, R, A>,
+        root_state: &State,
+        node_id: NodeId,
+        eval: EV::EvalResult,
+        node_setup: &NodeSetup<State, R, A>,
+    ) {
+        if node_setup.share_symmetries.is_none() && node_setup.edge_sharing.is_none() {
+            BP::backprop(tree, node_id, eval);
+        } else {
+            let before = branch_statistics::<State, R, A>(tree, node_id);
+            BP::backprop(tree, node_id, eval);
+            if let Some(edge_sharing) = &node_setup.edge_sharing {
+                (edge_sharing.record)(&edge_sharing.table, tree, &before);
+            }
+            if let Some(share_symmetries) = node_setup.share_symmetries {
+                share_symmetries(tree, root_state, &before);
+            }
+        }
     }
-}
 
-impl<State, TP, PP, BP, EV, A, R> Clone for LazyMcts<'_, State, TP, PP, BP, EV, A, R>
-where
+    /// Completes the new node of `state` with `node_setup`, and with its static evaluation for
+    /// the evaluators reading it, see `Evaluator::USES_STATIC_EVAL`.
+    fn setup_node(
+        node_setup: &NodeSetup<State, R, A>,
+        node: &mut LazyMctsNode<State, R, A>,
+        state: &State,
+    ) {
+        node_setup.setup(node, state);
+        if EV::USES_STATIC_EVAL {
+            node.static_eval = state.static_eval().map(|eval| eval.raw());
+        }
+    }
+
+    /// Runs the tree policy on `tree`, returns the selected node, its state and whether the node
+    /// was created by the expansion.
+    fn select_leaf(
+        tree: &mut LazyMctsTree<State, R, A>,
+        root_state: &State,
+        evaluation_args: &EV::Args,
+        rng: &mut dyn RngCore,
+        node_setup: &NodeSetup<State, R, A>,
+    ) -> (NodeId, State, bool) {
+        let (node_id, state) =
+            TP::tree_policy(tree, root_state.clone_light(), evaluation_args, rng);
+        let mut node = tree.get_mut(node_id).unwrap();
+        let expanded = node.value().n_visits == 0 && node.parent().is_some();
+        if expanded {
+            Self::setup_node(node_setup, node.value(), &state);
+            if let Some(edge_sharing) = &node_setup.edge_sharing {
+                let parent_hash = node.parent().unwrap().value().hash;
+                (edge_sharing.seed)(&edge_sharing.table, parent_hash, node.value());
+            }
+        }
+        (node_id, state, expanded)
+    }
+
+    /// Executes one iteration with leaf parallelization: the selected leaf is simulated
+    /// `playouts_per_leaf` times, the playouts being spread over `threads` threads. Each result is
+    /// then backpropagated, so the leaf gets `playouts_per_leaf` visits; averaging them in a
+    /// single backpropagation would need fractional rewards. Only the playouts run in parallel,
+    /// the selection and the backpropagation are unchanged.
+    pub fn execute_leaf_parallel(
+        &self,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+        playouts_per_leaf: usize,
+        threads: usize,
+    ) where
+        State: Send,
+        State::Player: Send,
+        PP::Args: Clone + Send,
+        EV::EvalResult: Send,
+    {
+        self.execute_leaf_parallel_by(
+            evaluation_args,
+            playout_args,
+            |_| playouts_per_leaf,
+            threads,
+        );
+    }
+
+    /// Executes one iteration with leaf parallelization like `execute_leaf_parallel`, but the
+    /// selected leaf is simulated `playout_budget(depth)` times, where `depth` is the depth of the
+    /// leaf, so the deep leaves of the lines the search committed to can get more playouts than
+    /// the ones near the root. The leaf gets a visit per playout, so a budget growing with the
+    /// depth also weighs the deep leaves more in the statistics of their ancestors. A budget of 0
+    /// is raised to 1, so the leaf is always visited. The generator of the search is only locked
+    /// for the selection and the seeds of the threads, not during the playouts.
+    pub fn execute_leaf_parallel_by(
+        &self,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+        playout_budget: impl Fn(usize) -> usize,
+        threads: usize,
+    ) where
+        State: Send,
+        State::Player: Send,
+        PP::Args: Clone + Send,
+        EV::EvalResult: Send,
+    {
+        let iteration = self.begin_iteration();
+        let mut rng = self.rng.lock().unwrap();
+        let mut tree = self.tree.lock().unwrap();
+        let (node_id, state, expanded) = Self::select_leaf(
+            &mut tree,
+            &self.root_state,
+            evaluation_args,
+            &mut **rng,
+            &self.node_setup,
+        );
+        let playouts_per_leaf =
+            playout_budget(tree.get(node_id).unwrap().ancestors().count()).max(1);
+        drop(tree);
+        let threads = threads.clamp(1, playouts_per_leaf);
+        let thread_rngs: Vec<StdRng> = (0..threads)
+            .map(|_| StdRng::seed_from_u64(rng.next_u64()))
+            .collect();
+        drop(rng);
+
+        let turn = self.root_state.player_turn();
+        let final_states: Vec<State> = thread::scope(|scope| {
+            let handles: Vec<_> = thread_rngs
+                .into_iter()
+                .enumerate()
+                .map(|(i, mut thread_rng)| {
+                    let playouts =
+                        playouts_per_leaf / threads + usize::from(i < playouts_per_leaf % threads);
+                    let (state, playout_args) = (state.clone(), playout_args.clone());
+                    scope.spawn(move || {
+                        (0..playouts)
+                            .map(|_| {
+                                PP::playout(
+                                    state.clone_light(),
+                                    playout_args.clone(),
+                                    &mut thread_rng,
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        let evals = final_states
+            .into_iter()
+            .map(|final_state| EV::evaluate_simulation(&state, final_state, &turn, evaluation_args))
+            .collect();
+        self.backprop_leaf_evals(node_id, expanded, evals, evaluation_args);
+        self.end_iteration(iteration);
+    }
+
+    /// Executes one iteration with leaf parallelization like `execute_leaf_parallel`, but the
+    /// playouts run on the thread pool of the search, given by `with_playout_pool`, or on the
+    /// global pool of rayon, so no thread is spawned by the iteration. Each playout draws its
+    /// random numbers from its own generator seeded by the search, so a seeded search stays
+    /// reproducible whatever the number of threads. Needs the `rayon` feature, and the states and
+    /// the playout arguments are shared between the threads so they must be `Send + Sync`.
+    #[cfg(feature = "rayon")]
+    pub fn execute_leaf_pooled(
+        &self,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+        playouts_per_leaf: usize,
+    ) where
+        State: Send + Sync,
+        State::Player: Sync,
+        PP::Args: Clone + Send + Sync,
+        EV::EvalResult: Send,
+    {
+        use rayon::prelude::*;
+
+        let iteration = self.begin_iteration();
+        let mut rng = self.rng.lock().unwrap();
+        let (node_id, state, expanded) = Self::select_leaf(
+            &mut self.tree.lock().unwrap(),
+            &self.root_state,
+            evaluation_args,
+            &mut **rng,
+            &self.node_setup,
+        );
+        let seeds: Vec<u64> = (0..playouts_per_leaf).map(|_| rng.next_u64()).collect();
+        drop(rng);
+
+        let turn = self.root_state.player_turn();
+        let playouts = || -> Vec<State> {
+            seeds
+                .par_iter()
+                .map(|&seed| {
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    PP::playout(state.clone_light(), playout_args.clone(), &mut rng)
+                })
+                .collect()
+        };
+        let final_states = match &self.playout_pool {
+            Some(pool) => pool.install(playouts),
+            None => playouts(),
+        };
+        let evals = final_states
+            .into_iter()
+            .map(|final_state| EV::evaluate_simulation(&state, final_state, &turn, evaluation_args))
+            .collect();
+        self.backprop_leaf_evals(node_id, expanded, evals, evaluation_args);
+        self.end_iteration(iteration);
+    }
+
+    /// Backpropagates each evaluation of the playouts of a leaf parallel iteration, and ends it.
+    fn backprop_leaf_evals(
+        &self,
+        node_id: NodeId,
+        expanded: bool,
+        evals: Vec<EV::EvalResult>,
+        evaluation_args: &EV::Args,
+    ) {
+        let mut locked_tree = self.tree.lock().unwrap();
+        let historic = historic(locked_tree.get(node_id).unwrap());
+        for eval in evals {
+            EV::record_leaf(&historic, &eval, evaluation_args);
+            #[cfg(feature = "debug-replay")]
+            let reward = eval.clone();
+            Self::backprop(
+                &mut locked_tree,
+                &self.root_state,
+                node_id,
+                eval,
+                &self.node_setup,
+            );
+            #[cfg(feature = "debug-replay")]
+            {
+                drop(locked_tree);
+                self.record(node_id, expanded, &reward);
+                locked_tree = self.tree.lock().unwrap();
+            }
+        }
+        drop(locked_tree);
+        if expanded {
+            self.node_bound.nodes.fetch_add(1, Ordering::Relaxed);
+            self.notify_expansion(node_id);
+        }
+    }
+
+    /// Executes `batch_size` iterations whose leaves are evaluated together by `evaluator`
+    /// instead of by playouts, for the evaluators which are faster on batches like the neural
+    /// networks. The leaves are selected one after the other, each one marked with the virtual
+    /// loss of `set_virtual_loss`, at least 1, so the next selections move to other leaves;
+    /// the leaves are then evaluated in a single call without holding the lock of the tree, and
+    /// the virtual losses are removed before the evaluations are backpropagated. A leaf can still
+    /// be selected twice, e.g. a final state, and is then evaluated twice.
+    ///
+    /// A `Sender<BatchRequest<..>>` is a batch evaluator, so the batches can be evaluated on
+    /// another thread, for example one gathering the batches of several searches. The panics of
+    /// the evaluator are not caught. Returns the outcome of each iteration, their playout time
+    /// being the time of the whole evaluation.
+    pub fn execute_batch(
+        &self,
+        batch_size: usize,
+        evaluator: &mut impl BatchEvaluator<State, EV::EvalResult>,
+        evaluation_args: &EV::Args,
+    ) -> Vec<IterationOutcome<EV::EvalResult>> {
+        let virtual_loss = self.node_setup.virtual_loss.max(1);
+        let iteration = self.begin_iteration();
+        let mut rng = self.rng.lock().unwrap();
+        let mut tree = self.tree.lock().unwrap();
+        let (mut leaves, mut states) = (Vec::with_capacity(batch_size), vec![]);
+        for _ in 0..batch_size {
+            let (node_id, state, expanded) = Self::select_leaf(
+                &mut tree,
+                &self.root_state,
+                evaluation_args,
+                &mut **rng,
+                &self.node_setup,
+            );
+            add_visits::<State, R, A>(&mut tree, node_id, virtual_loss, true);
+            leaves.push((node_id, expanded));
+            states.push(state);
+        }
+        drop(tree);
+        drop(rng);
+
+        let start = Instant::now();
+        let evals = evaluator.evaluate_batch(&states, &self.root_state.player_turn());
+        let playout_time = start.elapsed();
+        assert_eq!(
+            evals.len(),
+            states.len(),
+            "The batch evaluator must return an evaluation per leaf"
+        );
+
+        let mut tree = self.tree.lock().unwrap();
+        for &(node_id, _) in &leaves {
+            add_visits::<State, R, A>(&mut tree, node_id, virtual_loss, false);
+        }
+        let outcomes: Vec<_> = leaves
+            .into_iter()
+            .zip(evals)
+            .map(|((node_id, expanded), eval)| {
+                let historic = historic(tree.get(node_id).unwrap());
+                EV::record_leaf(&historic, &eval, evaluation_args);
+                Self::backprop(
+                    &mut tree,
+                    &self.root_state,
+                    node_id,
+                    eval.clone(),
+                    &self.node_setup,
+                );
+                IterationOutcome {
+                    leaf: node_id,
+                    reward: eval,
+                    expanded,
+                    depth: historic.len(),
+                    playout_time,
+                }
+            })
+            .collect();
+        drop(tree);
+        for outcome in &outcomes {
+            self.complete_iteration(outcome);
+        }
+        self.end_iteration(iteration);
+        outcomes
+    }
+
+    /// Executes `iterations` iterations, the arguments of the i-th iteration are given by
+    /// `evaluation_args(i)` and `playout_args(i)`. For example to anneal the exploration constant
+    /// of the default evaluator from 2 to 0.5:
+    ///
+    /// ```ignore
+    /// mcts.execute_scheduled(1000, |i| 2. - 1.5 * i as f64 / 1000., |_| ());
+    /// ```
+    pub fn execute_scheduled(
+        &self,
+        iterations: usize,
+        evaluation_args: impl Fn(usize) -> EV::Args,
+        playout_args: impl Fn(usize) -> PP::Args,
+    ) {
+        for i in 0..iterations {
+            self.execute(&evaluation_args(i), playout_args(i));
+        }
+    }
+
+    /// Executes iterations until the tree has `max_nodes` nodes or `max_iterations` iterations
+    /// were executed, to bound the memory of the search without pruning it. Returns the number of
+    /// executed iterations and what stopped the search, like `run` with these two limits.
+    pub fn execute_until(
+        &self,
+        max_nodes: usize,
+        max_iterations: usize,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+    ) -> (usize, SearchStop)
+    where
+        PP::Args: Clone,
+    {
+        let limits = SearchLimits {
+            iterations: Some(max_iterations),
+            nodes: Some(max_nodes),
+            ..SearchLimits::default()
+        };
+        let run = self.run(limits, evaluation_args, playout_args);
+        (run.iterations, run.stop)
+    }
+
+    /// Executes iterations until one of the `limits` is reached, and returns what the search did
+    /// and which limit stopped it, for the agents playing under time controls. See `run_until`
+    /// to stop on a condition too.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no limit is set, the search would never stop.
+    pub fn run(
+        &self,
+        limits: SearchLimits,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+    ) -> SearchRun
+    where
+        PP::Args: Clone,
+    {
+        assert!(
+            limits != SearchLimits::default(),
+            "At least one limit must be set"
+        );
+        self.run_until(limits, |_| false, evaluation_args, playout_args)
+    }
+
+    /// Like `run`, but also stops as soon as `stop` returns true, called before each iteration
+    /// with the number of iterations already executed. The condition can look at the search
+    /// itself, e.g. `|_| mcts.is_converged(0.9)`. With no limit set only the condition stops
+    /// the search.
+    pub fn run_until(
+        &self,
+        limits: SearchLimits,
+        mut stop: impl FnMut(usize) -> bool,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+    ) -> SearchRun
+    where
+        PP::Args: Clone,
+    {
+        let start = Instant::now();
+        // Counted by the iterations and recounted by the evictions.
+        let nodes = &self.node_bound.nodes;
+        nodes.store(self.node_count(), Ordering::Relaxed);
+        let mut run = SearchRun {
+            iterations: 0,
+            expanded: 0,
+            max_depth: 0,
+            elapsed: Duration::ZERO,
+            stop: SearchStop::Condition,
+        };
+        run.stop = loop {
+            run.elapsed = start.elapsed();
+            if limits.time.is_some_and(|time| run.elapsed >= time) {
+                break SearchStop::Time;
+            }
+            if limits
+                .iterations
+                .is_some_and(|iterations| run.iterations >= iterations)
+            {
+                break SearchStop::Iterations;
+            }
+            if limits
+                .nodes
+                .is_some_and(|max_nodes| nodes.load(Ordering::Relaxed) >= max_nodes)
+            {
+                break SearchStop::NodeCap;
+            }
+            if stop(run.iterations) || self.observer_stops() {
+                break SearchStop::Condition;
+            }
+            if let Ok(outcome) = self.try_execute(evaluation_args, playout_args.clone()) {
+                run.max_depth = run.max_depth.max(outcome.depth);
+                if outcome.expanded {
+                    run.expanded += 1;
+                }
+            }
+            run.iterations += 1;
+        };
+        run
+    }
+
+    /// Executes iterations until their playouts took `total` in all, as measured by
+    /// `IterationOutcome::playout_time`, so the budget is spread by the cost of the simulations
+    /// when it varies a lot between the positions, rather than by their number. The selection and
+    /// the backpropagation aren't counted, so the search takes longer than `total`. Measuring
+    /// costs two reads of the clock per iteration, which every iteration pays. Returns the number
+    /// of executed iterations and the playout time they took, at least `total`. See `run` to
+    /// bound the time of the whole iterations instead.
+    pub fn execute_time_budgeted(
+        &self,
+        total: Duration,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+    ) -> (usize, Duration)
+    where
+        PP::Args: Clone,
+    {
+        let mut iterations = 0;
+        let mut spent = Duration::ZERO;
+        while spent < total {
+            spent += match self.try_execute(evaluation_args, playout_args.clone()) {
+                Ok(outcome) => outcome.playout_time,
+                Err(caught) => caught.playout_time,
+            };
+            iterations += 1;
+        }
+        (iterations, spent)
+    }
+
+    /// Returns the mean reward of playing `mv` from the root, after searching during `budget`
+    /// iterations the position it leads to. The search runs on a copy of the subtree of `mv` so
+    /// the tree is left untouched, which costs a copy of the whole subtree. Like in the rest of
+    /// the tree the rewards are from the perspective of the player of the root. Returns 0 if the
+    /// move was never visited and `budget` is 0. See `try_evaluate_move` once
+    /// `catch_simulation_panics` was called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a simulation panicked and the panic was caught.
+    pub fn evaluate_move(
+        &self,
+        mv: &State::Move,
+        budget: usize,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+    ) -> Num
+    where
+        State::Move: PartialEq,
+        PP::Args: Clone,
+    {
+        self.try_evaluate_move(mv, budget, evaluation_args, playout_args)
+            .unwrap_or_else(|caught| {
+                panic!(
+                    "The simulation of {:?} panicked, use try_evaluate_move",
+                    caught.leaf
+                )
+            })
+    }
+
+    /// Returns the mean reward of playing `mv` from the root like `evaluate_move`, which only
+    /// fails with the first panic of the simulations once `catch_simulation_panics` was called.
+    /// The iterations draw their random numbers from a generator seeded by the search, so the
+    /// generator of the search is only locked for the seed.
+    pub fn try_evaluate_move(
+        &self,
+        mv: &State::Move,
+        budget: usize,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+    ) -> Result<Num, CaughtPanic<State::Move>>
+    where
+        State::Move: PartialEq,
+        PP::Args: Clone,
+    {
+        let mut state = self.root_state.clone_light();
+        state.do_move(mv);
+        let mut tree = self.tree.lock().unwrap();
+        let subtree = match child_matching::<State, R, A>(&tree, |other| other == mv) {
+            Some(child) => subtree::<State, R, A>(&mut tree, child, 0, SubtreeMode::Copy),
+            None => Self::new_tree(&state, 0, &self.node_setup),
+        };
+        drop(tree);
+
+        let subtree = Mutex::new(subtree);
+        let turn = self.root_state.player_turn();
+        let mut rng = StdRng::seed_from_u64(self.rng.lock().unwrap().next_u64());
+        for _ in 0..budget {
+            Self::execute_from(
+                &subtree,
+                &state,
+                &turn,
+                evaluation_args,
+                playout_args.clone(),
+                &mut rng,
+                &self.node_setup,
+            )?;
+        }
+        let subtree = subtree.into_inner().unwrap();
+        Ok(subtree.root().value().mean_reward().unwrap_or(n64(0.)))
+    }
+
+    /// Returns the best move from the root, the best child of the tree policy for
+    /// `evaluator_args`, so the exploration term of the evaluator takes part in the choice. Use
+    /// `best_move_by` to choose the move to play by its statistics alone, e.g. the robust or the
+    /// secure child. A best child with fewer visits than `set_min_visits_for_selection` is passed
+    /// over for the child chosen by `BestMoveCriterion::HighestMeanWithMinVisits`.
+    pub fn best_move(&self, evaluator_args: &EV::Args) -> State::Move {
+        let tree = self.tree.lock().unwrap();
+        let best_child = tree
+            .get(TP::best_child(
+                &tree,
+                &self.root_state.player_turn(),
+                tree.root().id(),
+                evaluator_args,
+            ))
+            .unwrap();
+        if best_child.value().n_visits < self.min_visits {
+            drop(tree);
+            return self.best_move_by(BestMoveCriterion::HighestMeanWithMinVisits(self.min_visits));
+        }
+        best_child
+            .value()
+            .state
+            .last()
+            .expect("The historic of the children of the root is empty, cannot happen")
+            .clone()
+    }
+
+    /// Sets the visits below which `best_move` passes over a child, so a child lucky in its few
+    /// visits isn't chosen in a short search. 0, the default, passes over none.
+    pub fn set_min_visits_for_selection(&mut self, min_visits: Nat) {
+        self.min_visits = min_visits;
+    }
+
+    /// Returns the best move like `best_move`, or the move chosen by `fallback` from the root state
+    /// if the root has no children yet, for example when a timed search was interrupted before
+    /// its first iteration.
+    pub fn best_move_or(
+        &self,
+        fallback: impl Fn(&State) -> State::Move,
+        evaluator_args: &EV::Args,
+    ) -> State::Move {
+        if !self.tree.lock().unwrap().root().has_children() {
+            return fallback(&self.root_state);
+        }
+        self.best_move(evaluator_args)
+    }
+
+    /// Moves the root to the state reached by playing `mv`. The subtree of the child of `mv` is
+    /// kept with its statistics and the rest of the tree is dropped, if `mv` was never expanded
+    /// the tree is rebuilt from the new root state. The historics of the kept nodes are rebased
+    /// on the new root, they lose their first move. The kept subtree is moved to a new arena, so
+    /// the memory of the dropped nodes is freed and doesn't grow over a long game, unless
+    /// `recycle_nodes` keeps the slots for the next searches.
+    ///
+    /// If a `ponder` is pending, `mv` is the move the opponent really played from the position
+    /// before the prediction: the pondered search is kept if it is the predicted move, otherwise
+    /// it is discarded and the tree is rebuilt from the actual position.
+    pub fn advance_root(&mut self, mv: &State::Move)
+    where
+        State::Move: PartialEq,
+    {
+        self.advance_root_with(|other| other == mv, |_| mv.clone());
+    }
+
+    /// Moves the root like `advance_root`, to the move for which `matches` returns true instead
+    /// of the move equal to a given one, so the moves don't need to implement `PartialEq`, e.g.
+    /// `mcts.advance_root_matching(|mv| mv.cell == cell)`. The move played is the one stored in
+    /// the matching child of the root, or the first matching legal move of the root state if the
+    /// move was never expanded.
+    ///
+    /// # Panics
+    ///
+    /// If the move was never expanded and no legal move of the root state matches.
+    pub fn advance_root_matching(&mut self, matches: impl Fn(&State::Move) -> bool) {
+        self.advance_root_with(&matches, |state| {
+            state
+                .legals_moves()
+                .into_iter()
+                .find(|mv| matches(mv))
+                .expect("No legal move of the root state matches")
+        });
+    }
+
+    /// Moves the root to the move of the child matching `matches`, or to the move returned by
+    /// `unexpanded` from the root state if no child matches.
+    fn advance_root_with(
+        &mut self,
+        matches: impl Fn(&State::Move) -> bool,
+        unexpanded: impl FnOnce(&State) -> State::Move,
+    ) {
+        if let Some((root_state, predicted_move)) = self.ponder.take() {
+            if matches(&predicted_move) {
+                return;
+            }
+            self.root_state = root_state;
+            let tree = self.tree.get_mut().unwrap();
+            *tree = Self::new_tree(&self.root_state, 0, &self.node_setup);
+        }
+
+        let tree = self.tree.get_mut().unwrap();
+        let child = child_matching::<State, R, A>(tree, &matches);
+        let mv = match child {
+            Some(child) => tree
+                .get(child)
+                .unwrap()
+                .value()
+                .state
+                .last()
+                .unwrap()
+                .clone(),
+            None => unexpanded(&self.root_state),
+        };
+        let mut new_root_state = self.root_state.as_ref().clone();
+        new_root_state.do_move(&mv);
+        let capacity = if self.node_setup.recycle_nodes {
+            tree.nodes().count()
+        } else {
+            0
+        };
+        *tree = match child {
+            Some(child) => subtree::<State, R, A>(tree, child, capacity, SubtreeMode::Take),
+            None => Self::new_tree(&new_root_state, capacity, &self.node_setup),
+        };
+        self.root_state = Cow::Owned(new_root_state);
+    }
+
+    /// Removes the child of the root reached by playing `mv` with its subtree, and prevents the
+    /// search from expanding `mv` again, for example to focus the analysis on the other moves.
+    /// The statistics of the root still count the visits made through `mv`. The move is only
+    /// forgotten at the current root, moving the root with `advance_root` clears it.
+    pub fn forget_move(&mut self, mv: &State::Move)
+    where
+        State::Move: PartialEq,
+    {
+        self.forget_move_matching(|other| other == mv);
+    }
+
+    /// Forgets the moves of the root for which `matches` returns true like `forget_move`, without
+    /// needing the moves to implement `PartialEq`.
+    pub fn forget_move_matching(&mut self, matches: impl Fn(&State::Move) -> bool) {
+        let tree = self.tree.get_mut().unwrap();
+        while let Some(child) = child_matching::<State, R, A>(tree, &matches) {
+            tree.get_mut(child).unwrap().detach();
+        }
+        tree.root_mut()
+            .value()
+            .unvisited_moves
+            .retain(|unvisited_move| !matches(unvisited_move));
+    }
+
+    /// Frees the memory of the nodes removed from the tree by `forget_move`, which stay in the
+    /// arena of the tree until then, by copying the tree without them. Returns the number of
+    /// freed nodes. The copy costs a pass over the whole tree and holds both trees in memory
+    /// meanwhile, so it's worth calling once after forgetting large subtrees, not after each
+    /// move; `advance_root` and `prune` already copy the tree they keep.
+    pub fn shrink_to_fit(&mut self) -> usize {
+        let tree = self.tree.get_mut().unwrap();
+        let allocated = tree.nodes().count();
+        *tree = subtree::<State, R, A>(tree, tree.root().id(), 0, SubtreeMode::Take);
+        allocated - tree.nodes().count()
+    }
+
+    /// Removes the nodes with less than `min_visits` visits with their subtree to free their
+    /// memory, the root being always kept. The move of each removed node is given back to the
+    /// unvisited moves of its parent, so the search expands it again instead of considering the
+    /// parent fully expanded; the new node starts without statistics, while the statistics of its
+    /// ancestors still count the visits made through the removed one. Returns the number of
+    /// removed nodes.
+    pub fn prune(&mut self, min_visits: Nat) -> usize {
+        let tree = self.tree.get_mut().unwrap();
+        let pruned: Vec<(NodeId, NodeId)> = tree
+            .root()
+            .descendants()
+            .filter(|node| {
+                node.value().n_visits < min_visits
+                    && node
+                        .parent()
+                        .is_some_and(|parent| parent.value().n_visits >= min_visits)
+            })
+            .map(|node| (node.id(), node.parent().unwrap().id()))
+            .collect();
+        let nodes = tree.root().descendants().count();
+        for &(id, parent) in &pruned {
+            let mut node = tree.get_mut(id).unwrap();
+            let mv = node.value().state.last().unwrap().clone();
+            node.detach();
+            let mut parent = tree.get_mut(parent).unwrap();
+            parent.value().unvisited_moves.push(mv);
+        }
+        for parent in pruned.iter().map(|&(_, parent)| parent) {
+            self.node_setup
+                .order_moves(&mut tree.get_mut(parent).unwrap().value().unvisited_moves);
+        }
+        // The detached nodes stay in the arena of the tree, copying it frees them.
+        *tree = subtree::<State, R, A>(tree, tree.root().id(), 0, SubtreeMode::Take);
+        nodes - tree.root().descendants().count()
+    }
+
+    /// Merges the tree of `other`, which must search the same root state, in this tree. The
+    /// statistics of this tree are scaled by `self_weight` and those of `other` by `other_weight`
+    /// before being summed, so the mean reward of a node is the average of the two estimates
+    /// weighted by the weight of each tree times its visits. The nodes are matched by their
+    /// historic, the nodes only in `other` are copied with their subtree. The scaled visits and
+    /// the integer scaled rewards are rounded to the nearest.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a weight is negative or if both are 0.
+    pub fn merge_weighted(&mut self, other: &Self, self_weight: f64, other_weight: f64)
+    where
+        State::Move: PartialEq,
+        R: FromPrimitive,
+    {
+        assert!(
+            self_weight >= 0. && other_weight >= 0. && self_weight + other_weight > 0.,
+            "The weights of a merge must be positive and not both 0"
+        );
+        let tree = self.tree.get_mut().unwrap();
+        let other_tree = other.tree.lock().unwrap();
+        let mut merged = HashSet::new();
+        let mut to_merge = vec![(tree.root().id(), other_tree.root().id())];
+        while let Some((id, other_id)) = to_merge.pop() {
+            merged.insert(id);
+            let other_node = other_tree.get(other_id).unwrap();
+            let mut value = tree.get(id).unwrap().value().clone();
+            weigh::<State, R, A>(
+                &mut value,
+                &[
+                    (tree.get(id).unwrap().value(), self_weight),
+                    (other_node.value(), other_weight),
+                ],
+            );
+            *tree.get_mut(id).unwrap().value() = value;
+
+            for other_child in other_node.children() {
+                let mv = other_child.value().state.last();
+                let child = tree
+                    .get(id)
+                    .unwrap()
+                    .children()
+                    .find(|child| child.value().state.last() == mv)
+                    .map(|child| child.id());
+                match child {
+                    Some(child) => to_merge.push((child, other_child.id())),
+                    None => {
+                        let mut to_copy = vec![(other_child.id(), id)];
+                        while let Some((other_id, parent)) = to_copy.pop() {
+                            let other_node = other_tree.get(other_id).unwrap();
+                            let mut value = other_node.value().clone();
+                            weigh::<State, R, A>(&mut value, &[(other_node.value(), other_weight)]);
+                            let mut parent = tree.get_mut(parent).unwrap();
+                            let mv = value.state.last().unwrap().clone();
+                            parent
+                                .value()
+                                .unvisited_moves
+                                .retain(|unvisited| *unvisited != mv);
+                            let new_id = parent.append(value).id();
+                            merged.insert(new_id);
+                            to_copy.extend(other_node.children().map(|child| (child.id(), new_id)));
+                        }
+                    }
+                }
+            }
+        }
+
+        let ids: Vec<_> = tree
+            .root()
+            .descendants()
+            .map(|node| node.id())
+            .filter(|id| !merged.contains(id))
+            .collect();
+        for id in ids {
+            let mut value = tree.get(id).unwrap().value().clone();
+            weigh::<State, R, A>(&mut value, &[(tree.get(id).unwrap().value(), self_weight)]);
+            *tree.get_mut(id).unwrap().value() = value;
+        }
+    }
+
+    /// Copies the statistics of `other` in the nodes of this search which are also in `other`,
+    /// to keep the knowledge of a previous search of the same root state in a rebuilt tree. The
+    /// ids of the nodes of two trees are unrelated, so the nodes are matched by their historic.
+    /// The nodes which are only in `other` are not added.
+    pub fn seed_from(&mut self, other: &Self)
+    where
+        State::Move: PartialEq,
+    {
+        let tree = self.tree.get_mut().unwrap();
+        let other_tree = other.tree.lock().unwrap();
+        let mut to_seed = vec![(tree.root().id(), other_tree.root())];
+        while let Some((id, other_node)) = to_seed.pop() {
+            let mut node = tree.get_mut(id).unwrap();
+            let value = node.value();
+            value.sum_rewards = other_node.value().sum_rewards.clone();
+            value.sum_squared_rewards = other_node.value().sum_squared_rewards;
+            value.n_visits = other_node.value().n_visits;
+
+            let node = tree.get(id).unwrap();
+            for child in node.children() {
+                let mv = child.value().state.last();
+                if let Some(other_child) = other_node
+                    .children()
+                    .find(|other_child| other_child.value().state.last() == mv)
+                {
+                    to_seed.push((child.id(), other_child));
+                }
+            }
+        }
+    }
+
+    /// Searches during `budget` iterations the position after `predicted_move`, to use the
+    /// opponent's thinking time. The root is advanced to this position, then the next call to
+    /// `advance_root` with the move the opponent really played resolves the prediction: if the
+    /// prediction was right the pondered search is reused, otherwise it is discarded.
+    ///
+    /// Pondering again on the same move continues the same search, pondering on another move
+    /// discards it.
+    pub fn ponder(
+        &mut self,
+        predicted_move: &State::Move,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+        budget: usize,
+    ) where
+        State::Move: PartialEq,
+        PP::Args: Clone,
+    {
+        match self.ponder.take() {
+            Some((root_state, pending_move)) if pending_move == *predicted_move => {
+                self.ponder = Some((root_state, pending_move));
+            }
+            pending => {
+                if let Some((root_state, _)) = pending {
+                    *self.tree.get_mut().unwrap() =
+                        Self::new_tree(&root_state, 0, &self.node_setup);
+                    self.root_state = root_state;
+                }
+                let root_state = self.root_state.clone();
+                self.advance_root(predicted_move);
+                self.ponder = Some((root_state, predicted_move.clone()));
+            }
+        }
+        for _ in 0..budget {
+            self.execute(evaluation_args, playout_args.clone());
+        }
+    }
+
+    /// Returns the `k` most visited moves of the root, from the most visited, each with its
+    /// principal variation: the line of at most `max_len` moves following it, built by playing
+    /// the most visited child at each node. Returns less than `k` lines if the root has less
+    /// than `k` children.
+    pub fn multi_pv(&self, k: usize, max_len: usize) -> Vec<(State::Move, Vec<State::Move>)> {
+        let tree = self.tree.lock().unwrap();
+        let mut children: Vec<_> = tree.root().children().collect();
+        children.sort_by_key(|child| std::cmp::Reverse(child.value().n_visits));
+        children
+            .into_iter()
+            .take(k)
+            .map(|child| {
+                let mv = child.value().state.last().unwrap().clone();
+                (mv, Self::most_visited_line(&tree, child.id(), max_len))
+            })
+            .collect()
+    }
+
+    /// Explains the move returned by `best_move`: its statistics, the ones of the most visited of
+    /// the other moves, the principal variation starting with it and the value of the root.
+    ///
+    /// # Panics
+    ///
+    /// If the root has no children, before the first iteration.
+    pub fn explain_best_move(&self, evaluator_args: &EV::Args) -> MoveExplanation<State::Move> {
+        let tree = self.tree.lock().unwrap();
+        let best_id = TP::best_child(
+            &tree,
+            &self.root_state.player_turn(),
+            tree.root().id(),
+            evaluator_args,
+        );
+        let stats = |child: NodeRef<LazyMctsNode<State, R, A>>| MoveStats {
+            mv: child.value().state.last().unwrap().clone(),
+            visits: child.value().n_visits,
+            mean_reward: child.value().mean_reward(),
+        };
+        let best = stats(tree.get(best_id).unwrap());
+        let runner_up = tree
+            .root()
+            .children()
+            .filter(|child| child.id() != best_id)
+            .max_by_key(|child| child.value().n_visits)
+            .map(stats);
+        let mut principal_variation = vec![best.mv.clone()];
+        principal_variation.extend(Self::most_visited_line(&tree, best_id, usize::MAX));
+        MoveExplanation {
+            best,
+            runner_up,
+            principal_variation,
+            root_value: tree.root().value().mean_reward(),
+        }
+    }
+
+    /// Returns the line of at most `max_len` moves following the node `from`, built by playing
+    /// the most visited child at each node.
+    fn most_visited_line(
+        tree: &LazyMctsTree<State, R, A>,
+        from: NodeId,
+        max_len: usize,
+    ) -> Vec<State::Move> {
+        let mut line = vec![];
+        let mut node = tree.get(from).unwrap();
+        while line.len() < max_len {
+            match node.children().max_by_key(|child| child.value().n_visits) {
+                Some(child) => {
+                    line.push(child.value().state.last().unwrap().clone());
+                    node = child;
+                }
+                None => break,
+            }
+        }
+        line
+    }
+
+    /// Returns the statistics of every legal move of the root in one call, for the analysis
+    /// tools: the expanded moves from the most to the least visited, their score computed by the
+    /// evaluator of the search with `evaluator_args`, then the moves not expanded yet, which only
+    /// have their move.
+    pub fn root_analysis(&self, evaluator_args: &EV::Args) -> Vec<RootMoveInfo<State::Move>> {
+        self.root_moves_info(Some(evaluator_args))
+    }
+
+    /// Like `root_analysis`, without the scores: returns the move, the visits, the mean reward,
+    /// the `static_eval` and the prior of each legal move of the root, for the displays which
+    /// don't know the arguments of the evaluator.
+    pub fn root_stats(&self) -> Vec<RootMoveInfo<State::Move>> {
+        self.root_moves_info(None)
+    }
+
+    /// Returns the principal variation of the search: the line of at most `depth` moves from the
+    /// root, built by playing the most visited child at each node. Empty before the first
+    /// iteration. See `multi_pv` for the lines of the other moves.
+    pub fn pv(&self, depth: usize) -> Vec<State::Move> {
+        let tree = self.tree.lock().unwrap();
+        Self::most_visited_line(&tree, tree.root().id(), depth)
+    }
+
+    /// The statistics of `root_analysis`, with the scores if `evaluator_args` are given.
+    fn root_moves_info(&self, evaluator_args: Option<&EV::Args>) -> Vec<RootMoveInfo<State::Move>> {
+        let tree = self.tree.lock().unwrap();
+        let root = tree.root();
+        let turn = self.root_state.player_turn();
+        let mut analysis: Vec<RootMoveInfo<State::Move>> = root
+            .children()
+            .map(|child| {
+                let value = child.value();
+                RootMoveInfo {
+                    mv: value.state.last().unwrap().clone(),
+                    visits: value.n_visits,
+                    mean_reward: value.mean_reward().map(|mean| mean.raw()),
+                    score: evaluator_args.map(|args| {
+                        EV::eval_child(value, &turn, root.value().n_visits, args).raw()
+                    }),
+                    variance: value.reward_variance(),
+                    static_eval: value.static_eval(),
+                    prior: EV::prior(value),
+                }
+            })
+            .collect();
+        analysis.sort_by_key(|info| std::cmp::Reverse(info.visits));
+        analysis.extend(root.value().unvisited_moves.iter().map(|mv| RootMoveInfo {
+            mv: mv.clone(),
+            visits: 0,
+            mean_reward: None,
+            score: None,
+            variance: None,
+            static_eval: None,
+            prior: None,
+        }));
+        analysis
+    }
+
+    /// Returns the move of the child of the root chosen by `criterion`. Among the children with
+    /// the best score, the one with the highest mean reward is chosen, then the first expanded,
+    /// which has the lowest `NodeId`.
+    pub fn best_move_by(&self, criterion: BestMoveCriterion) -> State::Move {
+        let tree = self.tree.lock().unwrap();
+        best_child_by::<State, R, A>(tree.root(), criterion)
+            .expect("The root has no children, execute the search before choosing a move")
+            .value()
+            .state
+            .last()
+            .expect("The historic of the children of the root is empty, cannot happen")
+            .clone()
+    }
+
+    /// Returns a summary of the search: the size and the shape of the tree, the visits of the
+    /// root, and the most visited move with its mean reward, see `SearchStatistics`.
+    pub fn statistics(&self) -> SearchStatistics<State::Move> {
+        let effective_branching_factor = self.effective_branching_factor();
+        let tree = self.tree.lock().unwrap();
+        let (nodes, max_depth) = tree
+            .root()
+            .descendants()
+            .fold((0, 0), |(nodes, max_depth), node| {
+                (nodes + 1, max_depth.max(node.ancestors().count()))
+            });
+        let best_move = best_child_by::<State, R, A>(tree.root(), BestMoveCriterion::MostVisited)
+            .map(|child| {
+                let value = child.value();
+                (
+                    value.state.last().unwrap().clone(),
+                    value.mean_reward().map_or(0., |mean| mean.raw()),
+                )
+            });
+        SearchStatistics {
+            nodes,
+            max_depth,
+            root_visits: tree.root().value().n_visits,
+            effective_branching_factor,
+            best_move,
+        }
+    }
+
+    /// Returns the number of visits of each child of the root.
+    pub fn move_visits(&self) -> Vec<(State::Move, Nat)> {
+        let tree = self.tree.lock().unwrap();
+        tree.root()
+            .children()
+            .map(|child| {
+                (
+                    child.value().state.last().unwrap().clone(),
+                    child.value().n_visits,
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the share of the visits of the children of the root made through each move, placed
+    /// at the index `index_fn(move)` of a vector of length `size`, the other indices being 0. It's
+    /// the policy target of the format usually expected to train a neural network. The shares of
+    /// the moves mapped to the same index are summed, and all the shares are 0 before the first
+    /// iteration.
+    ///
+    /// # Panics
+    ///
+    /// If `index_fn` returns an index not below `size`.
+    pub fn policy_target_array(
+        &self,
+        size: usize,
+        index_fn: impl Fn(&State::Move) -> usize,
+    ) -> Vec<f32> {
+        let move_visits = self.move_visits();
+        let total: Nat = move_visits.iter().map(|&(_, n_visits)| n_visits).sum();
+        let mut target = vec![0f32; size];
+        if total == 0 {
+            return target;
+        }
+        for (mv, n_visits) in move_visits {
+            target[index_fn(&mv)] += n_visits as f32 / total as f32;
+        }
+        target
+    }
+
+    /// Samples a move of the root with the RNG of the search, each with a probability
+    /// proportional to `visits^(1 / temperature)`, and returns it with the probability of each
+    /// move of the children of the root, the policy target of self-play training. A temperature
+    /// of 1 samples in proportion to the visits, and the moves get more likely to be the most
+    /// visited as it decreases to 0, which always chooses the most visited move, the first
+    /// expanded among the most visited. See `policy_target_array` for the target as an array.
+    ///
+    /// # Panics
+    ///
+    /// If the temperature is negative, or if the root has no children, before the first
+    /// iteration.
+    pub fn sample_move(&self, temperature: f64) -> (State::Move, Vec<(State::Move, f64)>) {
+        assert!(temperature >= 0., "The temperature must not be negative");
+        let move_visits = self.move_visits();
+        let max_visits = move_visits
+            .iter()
+            .map(|&(_, n_visits)| n_visits)
+            .max()
+            .expect("The root has no children, execute the search before choosing a move");
+        let weights: Vec<f64> = if temperature == 0. {
+            let best = move_visits
+                .iter()
+                .position(|&(_, n_visits)| n_visits == max_visits)
+                .unwrap();
+            (0..move_visits.len())
+                .map(|index| if index == best { 1. } else { 0. })
+                .collect()
+        } else {
+            // Relative to the most visited move, so the powers don't overflow at low temperatures.
+            move_visits
+                .iter()
+                .map(|&(_, n_visits)| {
+                    (f64::from(n_visits) / f64::from(max_visits)).powf(1. / temperature)
+                })
+                .collect()
+        };
+        let total: f64 = weights.iter().sum();
+        let index = self
+            .rng
+            .lock()
+            .unwrap()
+            .sample(WeightedIndex::new(&weights).unwrap());
+        let distribution: Vec<(State::Move, f64)> = move_visits
+            .into_iter()
+            .zip(weights)
+            .map(|((mv, _), weight)| (mv, weight / total))
+            .collect();
+        (distribution[index].0.clone(), distribution)
+    }
+
+    /// Returns whether the search has converged: the gap between the visits of the two most
+    /// visited children of the root, as a share of the visits of all its children, is at least
+    /// `threshold`. More iterations are then unlikely to change the most visited move, so a timed
+    /// agent can stop early. A root with a single child has converged, and one without children
+    /// hasn't.
+    pub fn is_converged(&self, threshold: f64) -> bool {
+        let mut visits: Vec<Nat> = self
+            .move_visits()
+            .into_iter()
+            .map(|(_, n_visits)| n_visits)
+            .collect();
+        visits.sort_unstable_by(|a, b| b.cmp(a));
+        match visits[..] {
+            [] => false,
+            [_] => true,
+            [first, second, ..] => {
+                let total: Nat = visits.iter().sum();
+                f64::from(first - second) >= threshold * f64::from(total)
+            }
+        }
+    }
+
+    /// Builds an opening book from the tree: each node with at least `min_visits` visits and a
+    /// child is stored with its most visited move, each position being identified by the moves
+    /// played from the root of the search.
+    pub fn opening_book(&self, min_visits: Nat) -> OpeningBook<State::Move>
+    where
+        State::Move: Hash + Eq,
+    {
+        let tree = self.tree.lock().unwrap();
+        let mut book = OpeningBook::new();
+        for node in tree.root().descendants() {
+            if node.value().n_visits < min_visits {
+                continue;
+            }
+            if let Some(child) = best_child_by::<State, R, A>(node, BestMoveCriterion::MostVisited)
+            {
+                let mv = child.value().state.last().unwrap().clone();
+                book.insert(historic(node), mv);
+            }
+        }
+        book
+    }
+
+    /// Returns the variance of the rewards of each child of the root with at least 2 visits.
+    pub fn move_variance(&self) -> Vec<(State::Move, f64)> {
+        let tree = self.tree.lock().unwrap();
+        tree.root()
+            .children()
+            .filter_map(|child| {
+                let variance = child.value().reward_variance()?;
+                Some((child.value().state.last().unwrap().clone(), variance))
+            })
+            .collect()
+    }
+
+    /// Returns for each internal node its id, its number of children and its number of visits,
+    /// sorted from the most visited to the least visited node, so truncating the result keeps
+    /// the top N nodes.
+    pub fn branching_report(&self) -> Vec<(NodeId, usize, Nat)> {
+        let tree = self.tree.lock().unwrap();
+        let mut report: Vec<_> = tree
+            .root()
+            .descendants()
+            .filter(|node| node.has_children())
+            .map(|node| (node.id(), node.children().count(), node.value().n_visits))
+            .collect();
+        report.sort_by_key(|&(_, _, visits)| std::cmp::Reverse(visits));
+        report
+    }
+
+    /// Returns the average number of children of the internal nodes, or 0 if the root has not
+    /// been expanded. Compared to the number of legal moves it tells how selective the search
+    /// was.
+    pub fn effective_branching_factor(&self) -> f64 {
+        let tree = self.tree.lock().unwrap();
+        let (internal_nodes, children) = tree
+            .root()
+            .descendants()
+            .filter(|node| node.has_children())
+            .fold((0, 0), |(internal_nodes, children), node| {
+                (internal_nodes + 1, children + node.children().count())
+            });
+        if internal_nodes == 0 {
+            0.
+        } else {
+            children as f64 / internal_nodes as f64
+        }
+    }
+
+    /// Renders the tree, each node is labelled with its index among its siblings, its number of
+    /// visits and its sum of rewards.
+    pub fn write_tree(&self) -> String {
+        self.write_tree_labelled(&|index, _| index.to_string())
+    }
+
+    /// Renders the tree like `write_tree`, but labels each node with the move leading to it.
+    pub fn write_tree_with_moves(&self) -> String
+    where
+        State::Move: Display,
+    {
+        self.write_tree_labelled(&|_, node| node.value().state.last().unwrap().to_string())
+    }
+
+    /// Renders the tree like `write_tree`, but labels each node with the
+    /// `GameTrait::move_notation` of the move leading to it, which replays the moves of each node
+    /// from the root state.
+    pub fn write_tree_with_notation(&self) -> String {
+        self.write_tree_labelled(&|_, node| self.notation(node))
+    }
+
+    /// Renders the tree like `write_tree_with_notation`, down to the nodes `max_depth` moves
+    /// below the root whose children aren't rendered, to inspect the top of a large tree.
+    pub fn to_ascii(&self, max_depth: usize) -> String {
+        let tree = self.tree.lock().unwrap();
+        let ascii_tree = Self::dfs(tree.root(), None, max_depth, &|_, node| self.notation(node));
+        let mut output = String::new();
+        write_tree(&mut output, &ascii_tree).unwrap();
+        output
+    }
+
+    /// Renders the tree as a Graphviz DOT digraph, e.g. for `dot -Tsvg`: each node is labelled
+    /// with its visits and its mean reward, and each edge with the `GameTrait::move_notation` of
+    /// its move. The nodes deeper than `max_depth` moves below the root and the nodes with less
+    /// than `min_visits` visits are left out, with their subtrees.
+    pub fn to_dot(&self, max_depth: usize, min_visits: Nat) -> String {
+        let tree = self.tree.lock().unwrap();
+        let mut output = String::from("digraph mcts {\n    node [shape=box];\n");
+        let mut next_id = 0;
+        let mut stack = vec![(tree.root(), 0, None)];
+        while let Some((node, depth, parent)) = stack.pop() {
+            let id = next_id;
+            next_id += 1;
+            let value = node.value();
+            let mean = value
+                .mean_reward()
+                .map_or(String::from("-"), |mean| format!("{:.3}", mean));
+            output += &format!(
+                "    {} [label=\"{} visits\\nmean {}\"];\n",
+                id, value.n_visits, mean
+            );
+            if let Some(parent) = parent {
+                output += &format!(
+                    "    {} -> {} [label=\"{}\"];\n",
+                    parent,
+                    id,
+                    dot_field(&self.notation(node))
+                );
+            }
+            if depth < max_depth {
+                let mut children: Vec<_> = node
+                    .children()
+                    .filter(|child| child.value().n_visits >= min_visits)
+                    .map(|child| (child, depth + 1, Some(id)))
+                    .collect();
+                children.reverse();
+                stack.extend(children);
+            }
+        }
+        output += "}\n";
+        output
+    }
+
+    /// Returns the `GameTrait::move_notation` of the move leading to `node`, in the state of its
+    /// parent.
+    fn notation(&self, node: NodeRef<LazyMctsNode<State, R, A>>) -> String {
+        let parent = node.parent().unwrap();
+        let state = TP::update_state(self.root_state.clone_light(), &historic(parent));
+        state.move_notation(node.value().state.last().unwrap())
+    }
+
+    /// Renders the visits of the children of the root as a histogram, one line per move with the
+    /// move, a bar of unicode blocks proportional to its visits and its visits, e.g.:
+    ///
+    /// ```text
+    /// 1 ████████████████████ 120
+    /// 2 ███▌                 21
+    /// ```
+    pub fn visit_histogram_string(&self) -> String
+    where
+        State::Move: Display,
+    {
+        const WIDTH: usize = 20;
+        const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+        let move_visits = self.move_visits();
+        if move_visits.is_empty() {
+            return String::from("The root has no children, execute the search first\n");
+        }
+        let labels: Vec<String> = move_visits.iter().map(|(mv, _)| mv.to_string()).collect();
+        let label_width = labels
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap();
+        let max_visits = move_visits
+            .iter()
+            .map(|&(_, n_visits)| n_visits)
+            .max()
+            .unwrap();
+        let mut output = String::new();
+        for (label, (_, n_visits)) in labels.iter().zip(&move_visits) {
+            let eighths = (u64::from(*n_visits) * WIDTH as u64 * 8)
+                .checked_div(u64::from(max_visits))
+                .unwrap_or(0) as usize;
+            let bar = "█".repeat(eighths / 8) + EIGHTHS[eighths % 8];
+            output += &format!(
+                "{:<label_width$} {:<WIDTH$} {}\n",
+                label,
+                bar,
+                n_visits,
+                label_width = label_width
+            );
+        }
+        output
+    }
+
+    fn write_tree_labelled(&self, label: &Label<'_, State, R, A>) -> String {
+        let tree = self.tree.lock().unwrap();
+        let ascii_tree = Self::dfs(tree.root(), None, usize::MAX, label);
+        let mut output = String::new();
+        write_tree(&mut output, &ascii_tree).unwrap();
+        output
+    }
+
+    fn dfs(
+        node: NodeRef<LazyMctsNode<State, R, A>>,
+        index: Option<usize>,
+        max_depth: usize,
+        label: &Label<'_, State, R, A>,
+    ) -> Tree {
+        let mut text = format!("{};{}", node.value().n_visits, node.value().sum_rewards);
+        if let (Some(index), Some(_)) = (index, node.value().state.last()) {
+            text = format!("{}: {}", label(index, node), text);
+        }
+        if node.has_children() && max_depth > 0 {
+            let nodes = node
+                .children()
+                .enumerate()
+                .map(|(index, child)| Self::dfs(child, Some(index), max_depth - 1, label))
+                .collect();
+            Node(text, nodes)
+        } else {
+            Leaf(vec![text])
+        }
+    }
+
+    /// Returns the value of the root position, its mean reward for the player to move at the root
+    /// which is the player the leaves are evaluated for, or `None` before the first iteration.
+    pub fn root_value(&self) -> Option<Num> {
+        self.tree.lock().unwrap().root().value().mean_reward()
+    }
+
+    /// Returns a `confidence` confidence interval on the root value, e.g. 0.95 for a 95% one,
+    /// so the reports can tell how much the value can be trusted after the effort spent. It
+    /// assumes the mean reward of the root is normally distributed around the true value, with
+    /// the variance of the rewards divided by the visits, which holds for independent rewards
+    /// and enough visits. The iterations are neither independent nor identically distributed, as
+    /// the selection shifts towards the best moves while the tree grows, so it's an estimate of
+    /// the noise of the simulations rather than a guarantee. The interval is the single value of
+    /// the root below 2 visits, and `(0, 0)` before the first iteration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `confidence` isn't strictly between 0 and 1.
+    pub fn root_value_ci(&self, confidence: f64) -> (Num, Num) {
+        assert!(
+            confidence > 0. && confidence < 1.,
+            "The confidence must be strictly between 0 and 1"
+        );
+        let tree = self.tree.lock().unwrap();
+        let root = tree.root().value();
+        let mean = root.mean_reward().unwrap_or(n64(0.));
+        match root.reward_variance() {
+            Some(variance) => {
+                let z = normal_quantile((1. + confidence) / 2.);
+                let half_width = z * (variance / f64::from(root.n_visits)).sqrt();
+                (mean - half_width, mean + half_width)
+            }
+            None => (mean, mean),
+        }
+    }
+
+    /// Returns, for each child of the node `id`, its move and the exploitation and exploration
+    /// terms of its UCT score with the exploration constant `c`, see `uct_terms`, to see which one
+    /// drives the selection when tuning `c`. The terms are recomputed from the statistics of the
+    /// children whatever the evaluator of the search, and a child never visited has an infinite
+    /// exploration term.
+    ///
+    /// # Panics
+    ///
+    /// If `id` isn't in the tree.
+    pub fn node_selection_breakdown(&self, id: NodeId, c: f64) -> Vec<(State::Move, f64, f64)> {
+        let tree = self.tree.lock().unwrap();
+        let node = tree.get(id).expect("The node isn't in the tree");
+        let parent_visits = node.value().n_visits;
+        node.children()
+            .map(|child| {
+                let value = child.value();
+                let (exploitation, exploration) = if value.n_visits == 0 {
+                    (0., f64::INFINITY)
+                } else {
+                    let sum_rewards = value.sum_rewards.to_f64().unwrap();
+                    uct_terms(parent_visits, sum_rewards, value.n_visits, c)
+                };
+                (
+                    value.state.last().unwrap().clone(),
+                    exploitation,
+                    exploration,
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the mean reward of the node `id`, or `None` if it was never visited or isn't in
+    /// the tree.
+    pub fn node_mean_reward(&self, id: NodeId) -> Option<Num> {
+        self.tree.lock().unwrap().get(id)?.value().mean_reward()
+    }
+
+    /// Returns the moves from the root to the node `id`, rebuilt from its ancestors so it works
+    /// with compact historics too.
+    ///
+    /// # Panics
+    ///
+    /// If `id` isn't in the tree.
+    pub fn historic(&self, id: NodeId) -> Vec<State::Move> {
+        historic(
+            self.tree
+                .lock()
+                .unwrap()
+                .get(id)
+                .expect("The node isn't in the tree"),
+        )
+    }
+
+    /// Checks the structure of the tree and panics with a description of the first violation
+    /// found: the root has no parent, each node is a child of its parent and not its own
+    /// ancestor, its children have different moves which are not in its unvisited moves, and,
+    /// without compact historics, the historic of a child is the one of its node plus its move.
+    /// It's read only, for the tests checking that a search or a change of its tree left it
+    /// consistent. Needs the `test-utils` feature.
+    #[cfg(feature = "test-utils")]
+    pub fn assert_invariants(&self)
+    where
+        State::Move: PartialEq,
+    {
+        let tree = self.tree.lock().unwrap();
+        let root = tree.root();
+        assert!(
+            root.parent().is_none(),
+            "The root {:?} has a parent",
+            root.id()
+        );
+        for node in root.descendants() {
+            let id = node.id();
+            assert!(
+                node.ancestors().all(|ancestor| ancestor.id() != id),
+                "The node {:?} is its own ancestor",
+                id
+            );
+            let mut moves: Vec<&State::Move> = vec![];
+            for child in node.children() {
+                assert_eq!(
+                    child.parent().map(|parent| parent.id()),
+                    Some(id),
+                    "The child {:?} of {:?} has another parent",
+                    child.id(),
+                    id
+                );
+                let mv = child.value().state.last().unwrap_or_else(|| {
+                    panic!("The child {:?} of {:?} has no move", child.id(), id)
+                });
+                assert!(
+                    !node.value().unvisited_moves.contains(mv),
+                    "The move {:?} of the child {:?} is still unvisited in {:?}",
+                    mv,
+                    child.id(),
+                    id
+                );
+                assert!(
+                    !moves.contains(&mv),
+                    "The move {:?} has two children in {:?}",
+                    mv,
+                    id
+                );
+                moves.push(mv);
+                if !self.node_setup.compact_historics {
+                    let (last, parent_historic) = child.value().state.split_last().unwrap();
+                    assert!(
+                        parent_historic == node.value().state.as_slice() && last == mv,
+                        "The historic of the child {:?} doesn't extend the one of {:?}",
+                        child.id(),
+                        id
+                    );
+                }
+            }
+        }
+    }
+
+    /// Writes the tree as CSV with one row per node, in pre-order: the index of the node, the
+    /// index of its parent, the index of the node among its siblings, its visits, its sum of
+    /// rewards and its mean reward. The root has no parent nor move, and a node without visits
+    /// has no mean.
+    pub fn to_csv(&self, writer: impl Write) -> io::Result<()> {
+        self.write_csv(writer, &|index, _| index.to_string())
+    }
+
+    /// Writes the tree as CSV like `to_csv`, but the move column holds the move leading to the
+    /// node.
+    pub fn to_csv_with_moves(&self, writer: impl Write) -> io::Result<()>
+    where
+        State::Move: Display,
+    {
+        self.write_csv(writer, &|_, node| {
+            node.value().state.last().unwrap().to_string()
+        })
+    }
+
+    /// Writes the tree as CSV like `to_csv`, but the move column holds the
+    /// `GameTrait::move_notation` of the move leading to the node.
+    pub fn to_csv_with_notation(&self, writer: impl Write) -> io::Result<()> {
+        self.write_csv(writer, &|_, node| self.notation(node))
+    }
+
+    fn write_csv(&self, mut writer: impl Write, label: &Label<'_, State, R, A>) -> io::Result<()> {
+        let tree = self.tree.lock().unwrap();
+        let mut rows = HashMap::new();
+        writeln!(writer, "id,parent,move,visits,sum_rewards,mean")?;
+        for (row, node) in tree.root().descendants().enumerate() {
+            rows.insert(node.id(), row);
+            let value = node.value();
+            let parent = node
+                .parent()
+                .map_or(String::new(), |p| rows[&p.id()].to_string());
+            let mv = value.state.last().map_or(String::new(), |_| {
+                csv_field(&label(node.prev_siblings().count(), node))
+            });
+            let mean = value
+                .mean_reward()
+                .map_or(String::new(), |mean| mean.to_string());
+            writeln!(
+                writer,
+                "{},{},{},{},{},{}",
+                row, parent, mv, value.n_visits, value.sum_rewards, mean
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<State, TP, PP, BP, P, R>
+    LazyMcts<'_, State, TP, PP, BP, PuctEvaluator<P>, PuctInfo<State::Move>, R>
+where
+    State: GameTrait,
+    State::Move: PartialEq,
+    TP: LazyTreePolicy<State, PuctEvaluator<P>, PuctInfo<State::Move>, R>,
+    PP: Playout<State>,
+    BP: BackPropPolicy<Vec<State::Move>, State::Move, R, PuctInfo<State::Move>, f64>,
+    P: PriorPolicy<State>,
+    R: Clone + ToPrimitive + Zero,
+{
+    /// Mixes Dirichlet noise into the priors of the moves of the root of a search with the
+    /// `PuctTreePolicy`, like AlphaZero does in self-play so the search explores the moves its
+    /// prior policy dismisses: each prior becomes `(1 - epsilon) * prior + epsilon * noise`,
+    /// the noise being drawn from a symmetric Dirichlet distribution of concentration `alpha`
+    /// with the RNG of the search, e.g. `alpha = 0.3` and `epsilon = 0.25` for chess. The priors
+    /// of the root are computed first if no iteration did, so call it before each search, once
+    /// the root is set, e.g. after `advance_root`. Noising the same root again mixes more noise
+    /// in.
+    pub fn add_dirichlet_noise(&self, alpha: f64, epsilon: f64) {
+        let mut tree = self.tree.lock().unwrap();
+        let root_id = tree.root().id();
+        PuctTreePolicy::<State, P, R>::compute_priors(&mut tree, root_id, &self.root_state);
+        mix_root_noise::<State, R>(&mut tree, alpha, epsilon, &mut **self.rng.lock().unwrap());
+    }
+}
+
+impl<State, TP, PP, BP, EV, R> LazyMcts<'_, State, TP, PP, BP, EV, SolverInfo, R>
+where
+    State: GameTrait,
+    TP: LazyTreePolicy<State, EV, SolverInfo, R>,
+    PP: Playout<State>,
+    BP: BackPropPolicy<Vec<State::Move>, State::Move, R, SolverInfo, EV::EvalResult>,
+    EV: Evaluator<State, R, SolverInfo>,
+    R: Clone,
+{
+    /// Returns the proof of the root of a search with the MCTS-Solver, see the `SolverBackProp`:
+    /// once it's known, more iterations can't change the best move.
+    pub fn root_proof(&self) -> Proof {
+        self.tree
+            .lock()
+            .unwrap()
+            .root()
+            .value()
+            .additional_info
+            .proof
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a, State, TP, PP, BP, EV, A, R> LazyMcts<'a, State, TP, PP, BP, EV, A, R>
+where
+    State: GameTrait,
+    State::Move: Serialize + DeserializeOwned,
+    TP: LazyTreePolicy<State, EV, A, R>,
+    PP: Playout<State>,
+    BP: BackPropPolicy<Vec<State::Move>, State::Move, R, A, EV::EvalResult>,
+    EV: Evaluator<State, R, A>,
+    A: Clone + Default + Serialize + DeserializeOwned,
+    R: Clone + Div + ToPrimitive + Zero + Add + Display + Serialize + DeserializeOwned,
+{
+    /// Saves the tree in the file at `path`, the file is replaced atomically.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        checkpoint::write_tree(&self.tree.lock().unwrap(), path.as_ref())
+    }
+
+    /// Loads a tree saved by `save` or `checkpoint_every`, `root_state` must be the state from
+    /// which the saved search was started. The hash of the root is checked with
+    /// `GameTrait::hash`, the hasher must be set again after loading.
+    pub fn load<P: AsRef<Path>>(root_state: &'a State, path: P) -> io::Result<Self> {
+        Self::from_checkpoint(root_state, checkpoint::read_tree(path.as_ref())?)
+    }
+
+    /// Writes the tree to `writer` like `save`, e.g. to send it to another process which
+    /// resumes the search with `read_from`. The nodes are written as JSON in pre-order, the
+    /// reader must use the same types of moves, rewards and additional info.
+    pub fn write_to(&self, writer: impl Write) -> io::Result<()> {
+        checkpoint::write_nodes(&self.tree.lock().unwrap(), writer)
+    }
+
+    /// Reads a tree written by `write_to` or `save`, like `load`.
+    pub fn read_from(root_state: &'a State, reader: impl Read) -> io::Result<Self> {
+        Self::from_checkpoint(root_state, checkpoint::read_nodes(reader)?)
+    }
+
+    /// Resumes the search of `tree` from `root_state`, after checking that it was searched from
+    /// it.
+    fn from_checkpoint(root_state: &'a State, tree: LazyMctsTree<State, R, A>) -> io::Result<Self> {
+        if tree.root().value().hash != root_state.hash() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the saved tree was not searched from this root state",
+            ));
+        }
+        Ok(Self::from_tree(
+            Cow::Borrowed(root_state),
+            tree,
+            Box::new(StdRng::from_entropy()),
+        ))
+    }
+
+    /// Executes `iterations` iterations and saves the tree in the file at `path` every
+    /// `interval` iterations and at the end. To resume a search, `load` the file and call this
+    /// method again with the remaining iterations.
+    pub fn checkpoint_every<P: AsRef<Path>>(
+        &self,
+        iterations: usize,
+        evaluation_args: &EV::Args,
+        playout_args: PP::Args,
+        interval: usize,
+        path: P,
+    ) -> io::Result<()>
+    where
+        PP::Args: Clone,
+    {
+        assert!(interval > 0, "The checkpoint interval must be positive");
+        for i in 1..=iterations {
+            self.execute(evaluation_args, playout_args.clone());
+            if i.is_multiple_of(interval) {
+                self.save(&path)?;
+            }
+        }
+        if !iterations.is_multiple_of(interval) {
+            self.save(&path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sets the statistics of `value` to the sum of the statistics of `nodes`, each scaled by its
+/// weight.
+fn weigh<State, R, A>(
+    value: &mut LazyMctsNode<State, R, A>,
+    nodes: &[(&LazyMctsNode<State, R, A>, f64)],
+) where
+    State: GameTrait,
+    R: Clone + ToPrimitive + FromPrimitive,
+    A: Clone + Default,
+{
+    let visits: f64 = nodes
+        .iter()
+        .map(|(node, weight)| f64::from(node.n_visits) * weight)
+        .sum();
+    let sum_rewards: f64 = nodes
+        .iter()
+        .map(|(node, weight)| node.sum_rewards.to_f64().unwrap() * weight)
+        .sum();
+    value.n_visits = visits.round() as Nat;
+    value.sum_rewards = reward_from_f64(sum_rewards);
+    value.sum_squared_rewards = nodes
+        .iter()
+        .map(|(node, weight)| node.sum_squared_rewards * weight)
+        .sum();
+}
+
+/// Returns the statistics of `leaf` and of its ancestors, the root excluded.
+fn branch_statistics<State, R, A>(
+    tree: &LazyMctsTree<State, R, A>,
+    leaf: NodeId,
+) -> Vec<BranchStatistics>
+where
+    State: GameTrait,
+    R: Clone + ToPrimitive,
+    A: Clone + Default,
+{
+    let leaf = tree.get(leaf).unwrap();
+    std::iter::once(leaf)
+        .chain(leaf.ancestors())
+        .filter(|node| node.parent().is_some())
+        .map(|node| {
+            let value = node.value();
+            (
+                node.id(),
+                value.n_visits,
+                value.sum_rewards.to_f64().unwrap(),
+                value.sum_squared_rewards,
+            )
+        })
+        .collect()
+}
+
+/// Detaches the children of the root whose upper Hoeffding bound with the probability `delta`
+/// for rewards spread over `reward_range` is below the best lower bound, see
+/// `LazyMcts::prune_dominated_root_moves`. Returns the number of detached nodes, which stay in
+/// the arena of the tree.
+fn remove_dominated_root_moves<State, R, A>(
+    tree: &mut LazyMctsTree<State, R, A>,
+    delta: f64,
+    reward_range: f64,
+) -> usize
+where
+    State: GameTrait,
+    R: Clone + ToPrimitive,
+    A: Clone + Default,
+{
+    let bounds: Vec<(NodeId, f64, f64)> = tree
+        .root()
+        .children()
+        .filter_map(|child| {
+            let mean = child.value().mean_reward()?.raw();
+            let width = reward_range
+                * ((2. / delta).ln() / (2. * f64::from(child.value().n_visits))).sqrt();
+            Some((child.id(), mean - width, mean + width))
+        })
+        .collect();
+    let best_lower_bound = bounds
+        .iter()
+        .map(|&(_, lower, _)| lower)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let mut detached = 0;
+    for &(id, _, upper) in &bounds {
+        if upper < best_lower_bound {
+            detached += tree.get(id).unwrap().descendants().count();
+            tree.get_mut(id).unwrap().detach();
+        }
+    }
+    detached
+}
+
+/// Seeds `node` with the statistics of its move from its parent of hash `parent_hash`.
+fn seed_from_edge_table<State, R, A>(
+    table: &EdgeTable<State::Move>,
+    parent_hash: u64,
+    node: &mut LazyMctsNode<State, R, A>,
+) where
+    State: GameTrait,
+    State::Move: Hash + Eq,
+    R: Clone + FromPrimitive + ToPrimitive,
+    A: Clone + Default,
+{
+    if let Some(mv) = node.state.last().cloned() {
+        table.seed(parent_hash, &mv, node);
+    }
+}
+
+/// Adds to the edge of each node of the branch the statistics it gained since `before`, the
+/// statistics of the branch from the leaf to the child of the root.
+fn record_in_edge_table<State, R, A>(
+    table: &EdgeTable<State::Move>,
+    tree: &LazyMctsTree<State, R, A>,
+    before: &[BranchStatistics],
+) where
+    State: GameTrait,
+    State::Move: Hash + Eq,
+    R: Clone + ToPrimitive,
+    A: Clone + Default,
+{
+    for &(id, n_visits, sum_rewards, sum_squared_rewards) in before {
+        let node = tree.get(id).unwrap();
+        let value = node.value();
+        table.add(
+            node.parent().unwrap().value().hash,
+            value.state.last().unwrap().clone(),
+            (
+                value.n_visits - n_visits,
+                value.sum_rewards.to_f64().unwrap() - sum_rewards,
+                value.sum_squared_rewards - sum_squared_rewards,
+            ),
+        );
+    }
+}
+
+/// Adds to the symmetric siblings of each node of the branch the statistics it gained since
+/// `before`, the statistics of the branch from the leaf to the child of the root.
+fn share_with_symmetries<State, R, A>(
+    tree: &mut LazyMctsTree<State, R, A>,
+    root_state: &State,
+    before: &[BranchStatistics],
+) where
+    State: GameTrait,
+    State::Move: PartialEq,
+    R: Clone + ToPrimitive + FromPrimitive,
+    A: Clone + Default,
+{
+    let mut state = root_state.clone_light();
+    for &(id, n_visits, sum_rewards, sum_squared_rewards) in before.iter().rev() {
+        let node = tree.get(id).unwrap();
+        let value = node.value();
+        let mv = value.state.last().unwrap().clone();
+        let gained = (
+            value.n_visits - n_visits,
+            value.sum_rewards.to_f64().unwrap() - sum_rewards,
+            value.sum_squared_rewards - sum_squared_rewards,
+        );
+        let symmetries = state.symmetries(&mv);
+        let siblings: Vec<NodeId> = node
+            .parent()
+            .unwrap()
+            .children()
+            .filter(|sibling| {
+                sibling.id() != id
+                    && sibling
+                        .value()
+                        .state
+                        .last()
+                        .is_some_and(|sibling_move| symmetries.contains(sibling_move))
+            })
+            .map(|sibling| sibling.id())
+            .collect();
+        for sibling in siblings {
+            let mut sibling = tree.get_mut(sibling).unwrap();
+            let value = sibling.value();
+            value.n_visits += gained.0;
+            value.sum_rewards = reward_from_f64(value.sum_rewards.to_f64().unwrap() + gained.1);
+            value.sum_squared_rewards += gained.2;
+        }
+        state.do_move(&mv);
+    }
+}
+
+/// Removes the duplicates of `moves`, keeping the first occurrence of each move.
+fn dedup<Move: Hash + Eq + Clone>(moves: &mut Vec<Move>) {
+    let mut seen = HashSet::new();
+    moves.retain(|mv| seen.insert(mv.clone()));
+}
+
+/// Quotes `field` if it contains a character which has a meaning in CSV.
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Escapes the backslashes and the quotes of `field`, a quoted DOT string.
+fn dot_field(field: &str) -> String {
+    field.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Returns the child of `parent` chosen by `criterion`, or by the visits if `criterion` can't
+/// choose any, see `LazyMcts::best_move_by`.
+fn best_child_by<State, R, A>(
+    parent: NodeRef<'_, LazyMctsNode<State, R, A>>,
+    criterion: BestMoveCriterion,
+) -> Option<NodeRef<'_, LazyMctsNode<State, R, A>>>
+where
+    State: GameTrait,
+    R: Clone + ToPrimitive,
+    A: Clone + Default,
+{
+    let best_by = |criterion: BestMoveCriterion| {
+        // `max_by_key` returns the last maximum, the children are reversed to get the first.
+        parent
+            .children()
+            .rev()
+            .filter_map(|child| Some((criterion.score(child.value())?, child)))
+            .max_by_key(|&(score, child)| (score, child.value().mean_reward()))
+            .map(|(_, child)| child)
+    };
+    best_by(criterion).or_else(|| best_by(BestMoveCriterion::MostVisited))
+}
+
+/// Returns the first child of the root whose move matches `matches`, if any. The move of a child
+/// is the last move of its historic, stored at its expansion.
+fn child_matching<State, R, A>(
+    tree: &LazyMctsTree<State, R, A>,
+    matches: impl Fn(&State::Move) -> bool,
+) -> Option<NodeId>
+where
+    State: GameTrait,
+    R: Clone,
+    A: Clone + Default,
+{
+    tree.root()
+        .children()
+        .find(|child| child.value().state.last().is_some_and(&matches))
+        .map(|child| child.id())
+}
+
+/// Detaches the `count` least visited leaves of `tree`, the root excepted, or all its nodes but
+/// the root if it has fewer, and gives their move back to the unvisited moves of their parent.
+/// A parent whose children were all detached becomes a leaf in turn. Returns the number of
+/// detached nodes, which stay in the arena of the tree.
+fn evict_least_visited_leaves<State, R, A>(
+    tree: &mut LazyMctsTree<State, R, A>,
+    count: usize,
+    node_setup: &NodeSetup<State, R, A>,
+) -> usize
+where
+    State: GameTrait,
+    R: Clone,
+    A: Clone + Default,
+{
+    let mut evicted = 0;
+    while evicted < count {
+        let mut leaves: Vec<(Nat, NodeId)> = tree
+            .root()
+            .descendants()
+            .skip(1)
+            .filter(|node| !node.has_children())
+            .map(|node| (node.value().n_visits, node.id()))
+            .collect();
+        if leaves.is_empty() {
+            break;
+        }
+        leaves.sort_by_key(|&(n_visits, _)| n_visits);
+        leaves.truncate(count - evicted);
+        let mut parents = Vec::with_capacity(leaves.len());
+        for (_, id) in leaves {
+            let mut node = tree.get_mut(id).unwrap();
+            let mv = node.value().state.last().unwrap().clone();
+            let parent = node.parent().unwrap().id();
+            node.detach();
+            tree.get_mut(parent)
+                .unwrap()
+                .value()
+                .unvisited_moves
+                .push(mv);
+            parents.push(parent);
+            evicted += 1;
+        }
+        for parent in parents {
+            node_setup.order_moves(&mut tree.get_mut(parent).unwrap().value().unvisited_moves);
+        }
+    }
+    evicted
+}
+
+/// Whether `subtree` copies the nodes or moves them out of the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubtreeMode {
+    Copy,
+    /// Moves the historics and the unvisited moves of the nodes out of the tree, which is left
+    /// with empty ones, instead of copying them.
+    Take,
+}
+
+/// Builds a new tree from the subtree of `node_id` in `tree`, with room for `capacity` nodes, the
+/// historic of each node is rebased to start from `node_id`: a node at the depth `depth` below
+/// `node_id` keeps at most its `depth` last moves, so the compact historics are kept as they are.
+fn subtree<State, R, A>(
+    tree: &mut LazyMctsTree<State, R, A>,
+    node_id: NodeId,
+    capacity: usize,
+    mode: SubtreeMode,
+) -> LazyMctsTree<State, R, A>
+where
+    State: GameTrait,
+    R: Clone,
+    A: Clone + Default,
+{
+    let extract = |tree: &mut LazyMctsTree<State, R, A>, id: NodeId, depth: usize| {
+        let mut node = tree.get_mut(id).unwrap();
+        let value = node.value();
+        let (mut state, unvisited_moves) = match mode {
+            SubtreeMode::Copy => (value.state.clone(), value.unvisited_moves.clone()),
+            SubtreeMode::Take => (
+                std::mem::take(&mut value.state),
+                std::mem::take(&mut value.unvisited_moves),
+            ),
+        };
+        state.drain(..state.len().saturating_sub(depth));
+        LazyMctsNode::<State, R, A> {
+            sum_rewards: value.sum_rewards.clone(),
+            sum_squared_rewards: value.sum_squared_rewards,
+            weighted_visits: value.weighted_visits,
+            n_visits: value.n_visits,
+            unvisited_moves,
+            hash: value.hash,
+            static_eval: value.static_eval,
+            state,
+            additional_info: value.additional_info.clone(),
+        }
+    };
+    let mut new_tree =
+        LazyMctsTree::<State, R, A>::with_capacity(extract(tree, node_id, 0), capacity);
+    let mut to_copy = vec![(node_id, new_tree.root().id(), 0)];
+    while let Some((id, new_id, depth)) = to_copy.pop() {
+        let children: Vec<NodeId> = tree.get(id).unwrap().children().map(|c| c.id()).collect();
+        for child in children {
+            let new_child = new_tree
+                .get_mut(new_id)
+                .unwrap()
+                .append(extract(tree, child, depth + 1))
+                .id();
+            to_copy.push((child, new_child, depth + 1));
+        }
+    }
+    new_tree
+}
+
+impl<State, TP, PP, BP, EV, A, R> Debug for LazyMcts<'_, State, TP, PP, BP, EV, A, R>
+where
+    State: GameTrait,
+    TP: LazyTreePolicy<State, EV, A, R>,
+    PP: Playout<State>,
+    BP: BackPropPolicy<Vec<State::Move>, State::Move, R, A, EV::EvalResult>,
+    EV: Evaluator<State, R, A>,
+    EV::EvalResult: Debug,
+    A: Clone + Default + Debug,
+    R: Clone + Debug + Div + Add + Zero + ToPrimitive,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&format!("{:?}", self.tree))
+    }
+}
+
+impl<State, TP, PP, BP, EV, A, R> Clone for LazyMcts<'_, State, TP, PP, BP, EV, A, R>
+where
     State: GameTrait,
     TP: LazyTreePolicy<State, EV, A, R>,
     PP: Playout<State>,
@@ -158,12 +3484,2479 @@ where
 {
     fn clone(&self) -> Self {
         Self {
-            root_state: self.root_state,
+            root_state: self.root_state.clone(),
+            ponder: self.ponder.clone(),
             tree_policy: PhantomData,
             playout_policy: PhantomData,
             backprop_policy: PhantomData,
             evaluator: PhantomData,
             tree: Mutex::new(self.tree.lock().unwrap().clone()),
+            rng: Mutex::new(Box::new(
+                StdRng::from_rng(&mut **self.rng.lock().unwrap()).unwrap(),
+            )),
+            observation: Mutex::new(None),
+            on_expand: Mutex::new(None),
+            observed: false,
+            node_setup: self.node_setup.clone(),
+            min_visits: self.min_visits,
+            node_bound: NodeBound {
+                nodes: AtomicUsize::new(self.node_bound.nodes.load(Ordering::Relaxed)),
+                ..NodeBound::default()
+            },
+            #[cfg(feature = "debug-replay")]
+            recording: Mutex::new(None),
+            #[cfg(feature = "rayon")]
+            playout_pool: self.playout_pool.clone(),
+        }
+    }
+}
+
+#[test]
+fn test_branching_report() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(3);
+    let mcts = DefaultMcts::new(&state);
+    assert!(mcts.branching_report().is_empty());
+    for _ in 0..50 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    // The whole game is expanded: the root, then 2 sticks left, then 1 stick left twice.
+    let report = mcts.branching_report();
+    let root = mcts.tree.lock().unwrap().root().id();
+    assert_eq!(report[0], (root, 3, 50));
+    let mut children: Vec<usize> = report.iter().map(|&(_, children, _)| children).collect();
+    children.sort();
+    assert_eq!(children, [1, 1, 2, 3]);
+    assert!(report.windows(2).all(|pair| pair[0].2 >= pair[1].2));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_checkpoint_resume() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let path = std::env::temp_dir().join(format!("oxymcts_resume_{}.json", std::process::id()));
+    let mcts = DefaultMcts::new(&state);
+    mcts.checkpoint_every(100, &2f64.sqrt(), (), 30, &path).unwrap();
+
+    let shape = |mcts: &DefaultMcts<Nim>| -> Vec<_> {
+        let tree = mcts.tree.lock().unwrap();
+        tree.root()
+            .descendants()
+            .map(|n| (n.value().state.clone(), n.value().n_visits))
+            .collect()
+    };
+    let resumed = DefaultMcts::load(&state, &path).unwrap();
+    assert_eq!(shape(&resumed), shape(&mcts));
+    resumed
+        .checkpoint_every(50, &2f64.sqrt(), (), 30, &path)
+        .unwrap();
+    let resumed = DefaultMcts::load(&state, &path).unwrap();
+    assert_eq!(resumed.tree.lock().unwrap().root().value().n_visits, 150);
+    std::fs::remove_file(path).unwrap();
+
+    // The trees can be shipped without a file, e.g. between self-play workers.
+    let mut bytes = vec![];
+    resumed.write_to(&mut bytes).unwrap();
+    let shipped = DefaultMcts::read_from(&state, bytes.as_slice()).unwrap();
+    assert_eq!(shape(&shipped), shape(&resumed));
+    assert!(DefaultMcts::read_from(&Nim::new(9), bytes.as_slice()).is_err());
+    assert!(DefaultMcts::read_from(&state, &b"[]"[..]).is_err());
+}
+
+#[test]
+fn test_advance_root_and_ponder() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let root_visits = |mcts: &DefaultMcts<Nim>| mcts.tree.lock().unwrap().root().value().n_visits;
+
+    let mut mcts = DefaultMcts::new(&state);
+    for _ in 0..200 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let child_visits = mcts
+        .tree
+        .lock()
+        .unwrap()
+        .root()
+        .children()
+        .find(|child| child.value().state == [3])
+        .unwrap()
+        .value()
+        .n_visits;
+    mcts.advance_root(&3);
+    assert_eq!(root_visits(&mcts), child_visits);
+    assert_eq!(mcts.root_state.sticks, 7);
+    assert!(mcts.tree.lock().unwrap().root().value().state.is_empty());
+
+    let mut mcts = DefaultMcts::new(&state);
+    mcts.ponder(&1, &2f64.sqrt(), (), 100);
+    mcts.advance_root(&1);
+    assert_eq!(root_visits(&mcts), 100);
+    assert_eq!(mcts.root_state.sticks, 9);
+
+    let mut mcts = DefaultMcts::new(&state);
+    mcts.ponder(&1, &2f64.sqrt(), (), 100);
+    mcts.advance_root(&2);
+    assert_eq!(root_visits(&mcts), 0);
+    assert_eq!(mcts.root_state.sticks, 8);
+}
+
+#[test]
+fn test_effective_branching_factor() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(3);
+    let mcts = DefaultMcts::new(&state);
+    assert_eq!(mcts.effective_branching_factor(), 0.);
+    for _ in 0..50 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    // 7 children over the 4 internal nodes of the whole game.
+    assert_eq!(mcts.effective_branching_factor(), 1.75);
+}
+
+#[test]
+fn test_evaluate_move_searches_a_copy_of_the_move() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(4);
+    let mcts = DefaultMcts::new(&state);
+    assert_eq!(mcts.evaluate_move(&1, 0, &2f64.sqrt(), ()), 0.);
+    for _ in 0..50 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let shape = || -> Vec<_> {
+        let tree = mcts.tree.lock().unwrap();
+        tree.root()
+            .descendants()
+            .map(|n| (n.value().state.clone(), n.value().n_visits))
+            .collect()
+    };
+    let before = shape();
+    // Leaving a single stick, the opponent takes it.
+    assert_eq!(mcts.evaluate_move(&3, 100, &2f64.sqrt(), ()), 0.);
+    assert!(mcts.evaluate_move(&1, 100, &2f64.sqrt(), ()) > 0.);
+    assert_eq!(shape(), before);
+}
+
+#[test]
+fn test_deterministic_searches_are_equal() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(15);
+    let search = |seed| {
+        let mcts = DefaultMcts::deterministic(&state, seed);
+        for _ in 0..300 {
+            mcts.execute(&2f64.sqrt(), ());
+        }
+        let tree = mcts.tree.lock().unwrap();
+        tree.root()
+            .descendants()
+            .map(|n| {
+                (
+                    n.value().state.clone(),
+                    n.value().n_visits,
+                    n.value().sum_rewards,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(search(42), search(42));
+}
+
+#[test]
+fn test_multi_pv() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+    use ego_tree::NodeRef;
+
+    let state = Nim::new(10);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    assert!(mcts.multi_pv(2, 3).is_empty());
+    for _ in 0..300 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let lines = mcts.multi_pv(2, 3);
+    assert_eq!(lines.len(), 2);
+    fn child(
+        node: NodeRef<LazyMctsNode<Nim, u32, ()>>,
+        mv: u8,
+    ) -> NodeRef<LazyMctsNode<Nim, u32, ()>> {
+        node.children()
+            .find(|child| child.value().state.last() == Some(&mv))
+            .unwrap()
+    }
+    let tree = mcts.tree.lock().unwrap();
+    let visits = |mv| child(tree.root(), mv).value().n_visits;
+    assert!(visits(lines[0].0) >= visits(lines[1].0));
+    // Each line follows the most visited child.
+    for (mv, line) in &lines {
+        let mut node = child(tree.root(), *mv);
+        for &next in line {
+            let most_visited = node.children().map(|c| c.value().n_visits).max();
+            node = child(node, next);
+            assert_eq!(Some(node.value().n_visits), most_visited);
+        }
+        assert!(line.len() <= 3 && mv + line.iter().sum::<u8>() <= 10);
+    }
+    drop(tree);
+    assert_eq!(mcts.multi_pv(10, 3).len(), 3);
+}
+
+#[test]
+fn test_on_expand() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+    use std::sync::{Arc, Mutex};
+
+    /// Counts the new nodes, alongside the hook.
+    struct CountExpansions(Arc<AtomicUsize>);
+
+    impl SearchObserver<u8, Nat> for CountExpansions {
+        fn on_expand(&mut self, _node: NodeId, _parent: NodeId, _mv: &u8) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let state = Nim::new(3);
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    let expansions = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&expansions);
+    let counted = Arc::new(AtomicUsize::new(0));
+    mcts.set_observer(CountExpansions(Arc::clone(&counted)), 0);
+    mcts.set_on_expand(move |node, parent, &mv| recorded.lock().unwrap().push((node, parent, mv)));
+    for _ in 0..50 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let expansions = expansions.lock().unwrap();
+    let tree = mcts.tree.lock().unwrap();
+    // Each node of the whole game but the root, once.
+    assert_eq!(expansions.len(), 7);
+    assert_eq!(counted.load(Ordering::Relaxed), 7);
+    for &(node, parent, mv) in expansions.iter() {
+        let node = tree.get(node).unwrap();
+        assert_eq!(node.parent().unwrap().id(), parent);
+        assert_eq!(node.value().state.last(), Some(&mv));
+    }
+}
+
+#[test]
+fn test_forget_move() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    // Not expanded yet, then expanded with a subtree.
+    mcts.forget_move(&1);
+    for _ in 0..100 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    mcts.forget_move(&2);
+    for _ in 0..100 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    assert_eq!(mcts.best_move(&2f64.sqrt()), 3);
+    let tree = mcts.tree.lock().unwrap();
+    let moves: Vec<_> = tree
+        .root()
+        .children()
+        .map(|child| child.value().state.clone())
+        .collect();
+    assert_eq!(moves, [vec![3]]);
+    assert!(tree.root().value().unvisited_moves.is_empty());
+    assert_eq!(tree.root().value().n_visits, 200);
+}
+
+#[test]
+fn test_risk_adjusted_best_move() {
+    use crate::test_game::Nim;
+    use crate::{DefaultBackProp, DefaultLazyTreePolicy, DefaultPlayout, GameResultEvaluator};
+
+    type Mcts<'a> = LazyMcts<
+        'a,
+        Nim,
+        DefaultLazyTreePolicy<Nim, GameResultEvaluator, (), f64>,
+        DefaultPlayout,
+        DefaultBackProp,
+        GameResultEvaluator,
+        (),
+        f64,
+    >;
+
+    let state = Nim::new(10);
+    let mcts = Mcts::new(&state);
+    let mut tree = mcts.tree.lock().unwrap();
+    let mut add_child = |mv, sum_rewards, sum_squared_rewards| {
+        tree.root_mut().append(LazyMctsNode::<Nim, f64, ()> {
+            sum_rewards,
+            sum_squared_rewards,
+            n_visits: 10,
+            ..LazyMctsNode::<Nim, f64, ()>::new(vec![mv])
+        });
+    };
+    // Wins 6 games out of 10.
+    add_child(1, 6., 6.);
+    // Always draws.
+    add_child(2, 5., 2.5);
+    drop(tree);
+
+    assert_eq!(mcts.best_move_by(BestMoveCriterion::HighestMean), 1);
+    assert_eq!(mcts.best_move_by(BestMoveCriterion::RiskAdjusted(1.)), 2);
+    assert_eq!(mcts.best_move_by(BestMoveCriterion::RiskAdjusted(-1.)), 1);
+}
+
+#[test]
+fn test_set_hasher_hashes_the_nodes_again() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..50 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    mcts.set_hasher(|state| u64::from(state.sticks));
+    let all_sticks = |mcts: &DefaultMcts<Nim>| {
+        let tree = mcts.tree.lock().unwrap();
+        tree.root().descendants().all(|node| {
+            let taken: u8 = node.value().state.iter().sum();
+            node.value().hash == u64::from(10 - taken)
+        })
+    };
+    // The nodes already in the tree, then the new ones.
+    assert!(all_sticks(&mcts));
+    let nodes = |mcts: &DefaultMcts<Nim>| mcts.tree.lock().unwrap().nodes().count();
+    let before = nodes(&mcts);
+    for _ in 0..50 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    assert!(nodes(&mcts) > before);
+    assert!(all_sticks(&mcts));
+}
+
+#[test]
+fn test_write_tree_with_moves() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(3);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..50 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let indexed = mcts.write_tree();
+    let with_moves = mcts.write_tree_with_moves();
+    assert_eq!(with_moves.lines().count(), indexed.lines().count());
+    // The indices start at 0 and the root has 3 children, the moves take 1 to 3 sticks.
+    assert!(indexed.contains("0: ") && !indexed.contains("3: "));
+    assert!(with_moves.contains("3: ") && !with_moves.contains("0: "));
+}
+
+#[test]
+fn test_secure_child_discounts_the_few_visits() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mcts = DefaultMcts::new(&state);
+    let mut tree = mcts.tree.lock().unwrap();
+    let mut add_child = |mv, sum_rewards, n_visits| {
+        tree.root_mut().append(LazyMctsNode::<Nim, u32, ()> {
+            sum_rewards,
+            sum_squared_rewards: f64::from(sum_rewards),
+            n_visits,
+            ..LazyMctsNode::<Nim, u32, ()>::new(vec![mv])
+        });
+    };
+    add_child(1, 1, 1);
+    add_child(2, 85, 100);
+    add_child(3, 48, 120);
+    drop(tree);
+
+    assert_eq!(mcts.best_move_by(BestMoveCriterion::MostVisited), 3);
+    assert_eq!(
+        mcts.best_move_by(BestMoveCriterion::HighestLowerBound(0.)),
+        1
+    );
+    // 1 - 1 / 1 < 0.4 - 1 / sqrt(120) < 0.85 - 1 / 10
+    assert_eq!(
+        mcts.best_move_by(BestMoveCriterion::HighestLowerBound(1.)),
+        2
+    );
+}
+
+#[test]
+fn test_seed_from_copies_the_common_nodes() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let previous = DefaultMcts::deterministic(&state, 1);
+    for _ in 0..200 {
+        previous.execute(&2f64.sqrt(), ());
+    }
+    let mut mcts = DefaultMcts::deterministic(&state, 2);
+    for _ in 0..3 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    mcts.seed_from(&previous);
+
+    let visits = |mcts: &DefaultMcts<Nim>| {
+        let tree = mcts.tree.lock().unwrap();
+        tree.root()
+            .descendants()
+            .map(|n| (n.value().state.clone(), n.value().n_visits))
+            .collect::<Vec<_>>()
+    };
+    let previous_visits = visits(&previous);
+    let seeded_visits = visits(&mcts);
+    assert_eq!(seeded_visits.len(), 4);
+    for node in &seeded_visits {
+        assert!(previous_visits.contains(node));
+    }
+}
+
+#[test]
+fn test_min_visits_passes_over_a_lucky_child() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mut mcts = DefaultMcts::new(&state);
+    let mut tree = mcts.tree.lock().unwrap();
+    let mut add_child = |mv, sum_rewards, n_visits| {
+        tree.root_mut().append(LazyMctsNode::<Nim, u32, ()> {
+            sum_rewards,
+            sum_squared_rewards: f64::from(sum_rewards),
+            n_visits,
+            ..LazyMctsNode::<Nim, u32, ()>::new(vec![mv])
+        });
+    };
+    // Won its single playout.
+    add_child(1, 1, 1);
+    // Wins 35 games out of 50.
+    add_child(2, 35, 50);
+    add_child(3, 8, 20);
+    tree.root_mut().value().n_visits = 71;
+    drop(tree);
+
+    assert_eq!(mcts.best_move_by(BestMoveCriterion::HighestMean), 1);
+    let min_visits = BestMoveCriterion::HighestMeanWithMinVisits(10);
+    assert_eq!(mcts.best_move_by(min_visits), 2);
+    // No child qualifies, the most visited one is chosen.
+    let min_visits = BestMoveCriterion::HighestMeanWithMinVisits(100);
+    assert_eq!(mcts.best_move_by(min_visits), 2);
+
+    // Without exploration the tree policy chooses the highest mean.
+    assert_eq!(mcts.best_move(&0.), 1);
+    mcts.set_min_visits_for_selection(10);
+    assert_eq!(mcts.best_move(&0.), 2);
+}
+
+#[test]
+fn test_search_terminates_when_the_game_cycles() {
+    /// A token moves forward or backward on a ring of 4 cells, the game ends when it reaches the
+    /// cell 2, so the cells 0, 1 and 3 can be visited again and again.
+    #[derive(Debug, Clone)]
+    struct Ring {
+        cell: u8,
+        turn: u8,
+    }
+
+    impl GameTrait for Ring {
+        type Player = u8;
+        type Move = u8;
+
+        fn legals_moves(&self) -> Vec<Self::Move> {
+            vec![1, 3]
+        }
+
+        fn player_turn(&self) -> Self::Player {
+            self.turn
+        }
+
+        fn hash(&self) -> u64 {
+            self.cell as u64
+        }
+
+        fn is_final(&self) -> bool {
+            self.cell == 2
+        }
+
+        fn do_move(&mut self, m: &Self::Move) {
+            self.cell = (self.cell + m) % 4;
+            self.turn = 3 - self.turn;
+        }
+
+        fn get_winner(&self) -> Self::Player {
+            3 - self.turn
+        }
+    }
+
+    let state = Ring { cell: 0, turn: 1 };
+    let mcts = crate::DefaultMcts::deterministic(&state, 3);
+    for _ in 0..300 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let tree = mcts.tree.lock().unwrap();
+    // Nodes of states seen before are distinct nodes, each parent chain ends at the root.
+    let hashes: Vec<_> = tree.root().descendants().map(|n| n.value().hash).collect();
+    assert!(hashes.iter().filter(|&&hash| hash == 0).count() > 1);
+    for node in tree.root().descendants() {
+        assert_eq!(node.ancestors().count(), node.value().state.len());
+    }
+}
+
+#[test]
+fn test_deterministic_order_of_the_moves() {
+    use crate::test_game::{Nim, RotatingNim};
+    use crate::DefaultMcts;
+
+    let state = RotatingNim(Nim::new(15));
+    let search = || {
+        let mut mcts = DefaultMcts::deterministic(&state, 7);
+        mcts.ensure_deterministic_order(|&mv| mv as u64);
+        for _ in 0..300 {
+            mcts.execute(&2f64.sqrt(), ());
+        }
+        let tree = mcts.tree.lock().unwrap();
+        tree.root()
+            .descendants()
+            .map(|n| (n.value().state.clone(), n.value().n_visits))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(search(), search());
+}
+
+#[test]
+fn test_leaf_parallel_backpropagates_each_playout() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mcts = DefaultMcts::deterministic(&state, 5);
+    for _ in 0..10 {
+        mcts.execute_leaf_parallel(&2f64.sqrt(), (), 8, 3);
+    }
+    let tree = mcts.tree.lock().unwrap();
+    assert_eq!(tree.root().value().n_visits, 80);
+    let children_visits: Nat = tree.root().children().map(|c| c.value().n_visits).sum();
+    assert_eq!(children_visits, 80);
+}
+
+#[test]
+fn test_to_csv() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..50 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let mut csv = vec![];
+    mcts.to_csv_with_moves(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+    assert_eq!(
+        rows[0],
+        ["id", "parent", "move", "visits", "sum_rewards", "mean"]
+    );
+    assert_eq!(rows[1][..4], ["0", "", "", "50"]);
+    assert_eq!(
+        rows.len(),
+        mcts.tree.lock().unwrap().root().descendants().count() + 1
+    );
+    for row in &rows[2..] {
+        assert!(["1", "2", "3"].contains(&row[2]));
+    }
+    assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+}
+
+#[test]
+fn test_shared_table_seeds_the_next_search() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let shared_table = Arc::new(SharedTable::new());
+    let mcts = DefaultMcts::with_shared_table(&state, shared_table.clone());
+    for _ in 0..200 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    mcts.store_in_shared_table();
+    let stored = shared_table.get(state.hash()).unwrap();
+    assert_eq!(stored.0, 200);
+
+    let mcts = DefaultMcts::with_shared_table(&state, shared_table.clone());
+    assert_eq!(mcts.tree.lock().unwrap().root().value().n_visits, 200);
+    mcts.execute(&2f64.sqrt(), ());
+    let tree = mcts.tree.lock().unwrap();
+    let child = tree.root().first_child().unwrap();
+    let mut child_state = state.clone();
+    child_state.do_move(child.value().state.last().unwrap());
+    // The new child starts from the stored statistics plus the visit of this iteration.
+    assert_eq!(
+        child.value().n_visits,
+        shared_table.get(child_state.hash()).unwrap().0 + 1
+    );
+}
+
+#[test]
+fn test_shared_table_seeds_a_child_beyond_its_parent() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mut stored = state.clone();
+    stored.do_move(&3);
+    let shared_table = Arc::new(SharedTable::new());
+    let node = LazyMctsNode::<Nim, u32, ()> {
+        sum_rewards: 20,
+        sum_squared_rewards: 20.,
+        n_visits: 50,
+        hash: stored.hash(),
+        ..LazyMctsNode::<Nim, u32, ()>::new(vec![3])
+    };
+    shared_table.store(&node, None);
+
+    let mcts = DefaultMcts::with_shared_table(&state, shared_table);
+    for _ in 0..3 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let tree = mcts.tree.lock().unwrap();
+    assert_eq!(tree.root().value().n_visits, 3);
+    let visits: Vec<(u8, Nat)> = tree
+        .root()
+        .children()
+        .map(|child| (child.value().state[0], child.value().n_visits))
+        .collect();
+    assert!(visits.contains(&(3, 51)));
+    assert_eq!(visits.iter().map(|&(_, n)| n).sum::<Nat>(), 53);
+}
+
+#[test]
+fn test_verified_shared_table_detects_the_collisions() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    // Hashing the sticks alone makes the states of both players collide, the key tells them
+    // apart.
+    fn search<'a>(
+        state: &'a Nim,
+        shared_table: &Arc<SharedTable<u32>>,
+        verify: bool,
+    ) -> DefaultMcts<'a, Nim> {
+        let mut mcts = DefaultMcts::with_shared_table(state, shared_table.clone());
+        mcts.set_hasher(|state| u64::from(state.sticks));
+        if verify {
+            mcts.verify_shared_table(|state| u64::from(state.turn));
+        }
+        mcts
+    }
+    let shared_table = Arc::new(SharedTable::new());
+    let state = Nim::new(10);
+    let mcts = search(&state, &shared_table, true);
+    for _ in 0..100 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    mcts.store_in_shared_table();
+    // The only state with 9 sticks of the first search is the one of the second player.
+    let stored = shared_table.get(9).unwrap().0;
+
+    let other_player = Nim {
+        sticks: 10,
+        turn: 2,
+    };
+    let visits_of_nine = |verify| {
+        let mcts = search(&other_player, &shared_table, verify);
+        for _ in 0..3 {
+            mcts.execute(&2f64.sqrt(), ());
+        }
+        let tree = mcts.tree.lock().unwrap();
+        let nine = child_matching::<Nim, u32, ()>(&tree, |&mv| mv == 1).unwrap();
+        tree.get(nine).unwrap().value().n_visits
+    };
+    assert_eq!(visits_of_nine(false), stored + 1);
+    assert_eq!(shared_table.collisions(), 0);
+    assert_eq!(visits_of_nine(true), 1);
+    assert!(shared_table.collisions() >= 1);
+}
+
+#[test]
+fn test_dedup_moves() {
+    use crate::test_game::{DoubledNim, Nim};
+    use crate::DefaultMcts;
+
+    let state = DoubledNim(Nim::new(10));
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    mcts.execute(&2f64.sqrt(), ());
+    mcts.dedup_moves();
+    for _ in 1..200 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let tree = mcts.tree.lock().unwrap();
+    for node in tree.root().descendants() {
+        let mut moves: Vec<_> = node
+            .children()
+            .map(|child| *child.value().state.last().unwrap())
+            .collect();
+        let n_children = moves.len();
+        moves.sort_unstable();
+        moves.dedup();
+        assert_eq!(moves.len(), n_children);
+        assert!(node
+            .value()
+            .unvisited_moves
+            .iter()
+            .all(|mv| !moves.contains(mv)));
+    }
+    let root = tree.root();
+    assert_eq!(root.children().count(), 3);
+    let children_visits: Nat = root.children().map(|c| c.value().n_visits).sum();
+    assert_eq!(children_visits, root.value().n_visits);
+}
+
+#[test]
+fn test_node_mean_reward() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mcts = DefaultMcts::new(&state);
+    let root = mcts.tree.lock().unwrap().root().id();
+    assert_eq!(mcts.node_mean_reward(root), None);
+    for _ in 0..20 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let tree = mcts.tree.lock().unwrap();
+    let expected = tree.root().value().sum_rewards as f64 / 20.;
+    drop(tree);
+    assert_eq!(mcts.node_mean_reward(root), Some(n64(expected)));
+}
+
+#[test]
+fn test_most_visited_ties_are_broken_by_mean() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mcts = DefaultMcts::new(&state);
+    let mut tree = mcts.tree.lock().unwrap();
+    for (mv, sum_rewards) in [(1, 3), (2, 7), (3, 7)] {
+        tree.root_mut().append(LazyMctsNode::<Nim, u32, ()> {
+            sum_rewards,
+            sum_squared_rewards: sum_rewards as f64,
+            n_visits: 10,
+            ..LazyMctsNode::<Nim, u32, ()>::new(vec![mv])
+        });
+    }
+    drop(tree);
+    // 2 and 3 have the same mean, 2 was expanded first.
+    assert_eq!(mcts.best_move_by(BestMoveCriterion::MostVisited), 2);
+}
+
+#[test]
+fn test_execute_until_the_node_cap() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(15);
+    let mcts = DefaultMcts::new(&state);
+    let (iterations, stop) = mcts.execute_until(50, 10_000, &2f64.sqrt(), ());
+    assert_eq!(stop, SearchStop::NodeCap);
+    assert!(iterations < 10_000);
+    assert_eq!(mcts.tree.lock().unwrap().root().descendants().count(), 50);
+
+    let mcts = DefaultMcts::new(&state);
+    assert_eq!(
+        mcts.execute_until(1000, 20, &2f64.sqrt(), ()),
+        (20, SearchStop::Iterations)
+    );
+}
+
+#[test]
+fn test_merge_weighted() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let search = |seed, iterations| {
+        let mcts = DefaultMcts::deterministic(&state, seed);
+        for _ in 0..iterations {
+            mcts.execute(&2f64.sqrt(), ());
+        }
+        mcts
+    };
+    let root_visits = |mcts: &DefaultMcts<Nim>| mcts.tree.lock().unwrap().root().value().n_visits;
+
+    let other = search(2, 300);
+    let mut mcts = search(1, 100);
+    mcts.merge_weighted(&other, 1., 1.);
+    assert_eq!(root_visits(&mcts), 400);
+    let tree = mcts.tree.lock().unwrap();
+    for node in tree.root().descendants() {
+        let mut moves: Vec<_> = node.children().map(|c| c.value().state.clone()).collect();
+        let n_children = moves.len();
+        moves.sort_unstable();
+        moves.dedup();
+        assert_eq!(moves.len(), n_children);
+        for mv in &node.value().unvisited_moves {
+            assert!(node.children().all(|c| c.value().state.last() != Some(mv)));
+        }
+    }
+    drop(tree);
+
+    let mut mcts = search(1, 100);
+    mcts.merge_weighted(&other, 0.5, 0.5);
+    assert_eq!(root_visits(&mcts), 200);
+    let mut mcts = search(1, 100);
+    mcts.merge_weighted(&other, 0., 1.);
+    assert_eq!(root_visits(&mcts), 300);
+}
+
+#[test]
+#[should_panic]
+fn test_merge_weighted_with_zero_weights() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mut mcts = DefaultMcts::new(&state);
+    let other = DefaultMcts::new(&state);
+    mcts.merge_weighted(&other, 0., 0.);
+}
+
+#[test]
+fn test_catch_simulation_panics() {
+    use crate::test_game::Nim;
+    use crate::{DefaultBackProp, DefaultLazyTreePolicy, DefaultUctEvaluator};
+
+    /// A playout with a bug on the positions with 2 sticks.
+    struct BuggyPlayout;
+
+    impl Playout<Nim> for BuggyPlayout {
+        type Args = ();
+
+        fn playout(state: Nim, _args: (), rng: &mut dyn RngCore) -> Nim {
+            assert!(state.sticks != 2, "buggy playout");
+            crate::DefaultPlayout::playout(state, (), rng)
+        }
+    }
+
+    type Policy = DefaultLazyTreePolicy<Nim, DefaultUctEvaluator, (), u32>;
+    type Mcts<'a> =
+        LazyMcts<'a, Nim, Policy, BuggyPlayout, DefaultBackProp, DefaultUctEvaluator, (), u32>;
+
+    let state = Nim::new(3);
+    let mut mcts = Mcts::new(&state);
+    mcts.catch_simulation_panics();
+    let mut caught = 0;
+    for _ in 0..20 {
+        if let Err(panic) = mcts.try_execute(&2f64.sqrt(), ()) {
+            assert_eq!(panic.leaf, [1]);
+            assert_eq!(panic.message.as_deref(), Some("buggy playout"));
+            caught += 1;
+        }
+    }
+    assert!(caught > 0);
+    // The move whose playouts panic is never kept in the tree.
+    let tree = mcts.tree.lock().unwrap();
+    assert_eq!(tree.root().value().n_visits, 20 - caught);
+    assert_eq!(tree.root().value().unvisited_moves, [1]);
+    assert!(tree
+        .root()
+        .children()
+        .all(|child| child.value().state != [1]));
+    // The nodes of the dropped iterations were freed from the arena.
+    assert_eq!(tree.nodes().count(), tree.root().descendants().count());
+
+    // Taking 1 of 4 sticks, the search of `try_evaluate_move` reaches the positions with 2.
+    let state = Nim::new(4);
+    let mut mcts = Mcts::new(&state);
+    mcts.catch_simulation_panics();
+    let panic = mcts
+        .try_evaluate_move(&1, 10, &2f64.sqrt(), ())
+        .unwrap_err();
+    assert_eq!(panic.message.as_deref(), Some("buggy playout"));
+}
+
+#[test]
+fn test_execute_many() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    mcts.execute_many(10_000, 4, &2f64.sqrt(), ());
+    let tree = mcts.tree.lock().unwrap();
+    assert_eq!(tree.root().value().n_visits, 10_000);
+    let children_visits: Nat = tree.root().children().map(|c| c.value().n_visits).sum();
+    assert_eq!(children_visits, 10_000);
+}
+
+#[test]
+fn test_virtual_loss_spreads_the_selections() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(3);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..200 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let mut tree = mcts.tree.lock().unwrap();
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut select = |tree: &mut LazyMctsTree<Nim, u32, ()>| {
+        let (leaf, _, expanded) =
+            DefaultMcts::<Nim>::select_leaf(tree, &state, &2f64.sqrt(), &mut rng, &mcts.node_setup);
+        assert!(!expanded);
+        leaf
+    };
+    // The move of the root leading to a leaf.
+    let root_child = |tree: &LazyMctsTree<Nim, u32, ()>, leaf| historic(tree.get(leaf).unwrap())[0];
+    let first = select(&mut tree);
+    assert_eq!(select(&mut tree), first);
+    // A virtual loss on the branch of the first selection leads the next one elsewhere.
+    add_visits::<Nim, u32, ()>(&mut tree, first, 10, true);
+    let second = select(&mut tree);
+    assert_ne!(root_child(&tree, second), root_child(&tree, first));
+    add_visits::<Nim, u32, ()>(&mut tree, first, 10, false);
+    assert_eq!(select(&mut tree), first);
+    drop(tree);
+
+    let mut parallel = DefaultMcts::deterministic(&state, 0);
+    parallel.set_virtual_loss(3);
+    parallel.execute_many(1000, 4, &2f64.sqrt(), ());
+    let tree = parallel.tree.lock().unwrap();
+    assert_eq!(tree.root().value().n_visits, 1000);
+    assert!(tree.root().descendants().all(|node| {
+        node.children().count() == 0
+            || node.value().n_visits >= node.children().map(|c| c.value().n_visits).sum()
+    }));
+}
+
+#[test]
+fn test_execute_batch_evaluates_the_leaves_together() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    /// Evaluates Nim exactly: the player to move loses when the sticks are a multiple of 4.
+    struct Exact {
+        batches: Vec<Vec<u8>>,
+    }
+
+    impl BatchEvaluator<Nim, u32> for Exact {
+        fn evaluate_batch(&mut self, leaves: &[Nim], turn: &u8) -> Vec<u32> {
+            self.batches
+                .push(leaves.iter().map(|leaf| leaf.sticks).collect());
+            leaves
+                .iter()
+                .map(|leaf| u32::from((leaf.sticks % 4 != 0) == (leaf.turn == *turn)))
+                .collect()
+        }
+    }
+
+    let state = Nim::new(10);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    let mut exact = Exact { batches: vec![] };
+    let outcomes = mcts.execute_batch(3, &mut exact, &2f64.sqrt());
+    // The moves of the root are expanded by the batch, 8 sticks are lost for the next player.
+    assert!(outcomes.iter().all(|outcome| outcome.expanded));
+    let mut rewards: Vec<(u8, u32)> = exact.batches[0]
+        .iter()
+        .zip(&outcomes)
+        .map(|(&sticks, outcome)| (sticks, outcome.reward))
+        .collect();
+    rewards.sort();
+    assert_eq!(rewards, [(7, 0), (8, 1), (9, 0)]);
+    for _ in 0..10 {
+        mcts.execute_batch(8, &mut exact, &2f64.sqrt());
+    }
+    let tree = mcts.tree.lock().unwrap();
+    assert_eq!(tree.root().value().n_visits, 83);
+    assert!(tree.root().descendants().all(|node| {
+        node.children().count() == 0
+            || node.value().n_visits >= node.children().map(|c| c.value().n_visits).sum()
+    }));
+    drop(tree);
+
+    let (requests, received) = mpsc::channel::<BatchRequest<Nim, u32>>();
+    let server = thread::spawn(move || {
+        let mut exact = Exact { batches: vec![] };
+        for request in received {
+            let evals = exact.evaluate_batch(&request.leaves, &request.turn);
+            request.reply.send(evals).unwrap();
+        }
+        exact.batches.len()
+    });
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..5 {
+        mcts.execute_batch(4, &mut requests.clone(), &2f64.sqrt());
+    }
+    drop(requests);
+    assert_eq!(server.join().unwrap(), 5);
+    assert_eq!(mcts.tree.lock().unwrap().root().value().n_visits, 20);
+}
+
+#[test]
+fn test_run_stops_at_the_first_limit() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    let limits = SearchLimits {
+        iterations: Some(50),
+        nodes: Some(1000),
+        ..SearchLimits::default()
+    };
+    let run = mcts.run(limits, &2f64.sqrt(), ());
+    assert_eq!(run.iterations, 50);
+    assert_eq!(run.stop, SearchStop::Iterations);
+    let statistics = mcts.statistics();
+    assert_eq!(run.expanded, statistics.nodes - 1);
+    assert_eq!(run.max_depth, statistics.max_depth);
+
+    let limits = SearchLimits {
+        nodes: Some(statistics.nodes + 5),
+        ..SearchLimits::default()
+    };
+    let run = mcts.run(limits, &2f64.sqrt(), ());
+    assert_eq!((run.expanded, run.stop), (5, SearchStop::NodeCap));
+
+    let limits = SearchLimits {
+        time: Some(Duration::from_millis(10)),
+        ..SearchLimits::default()
+    };
+    let run = mcts.run(limits, &2f64.sqrt(), ());
+    assert_eq!(run.stop, SearchStop::Time);
+    assert!(run.elapsed >= Duration::from_millis(10));
+
+    let run = mcts.run_until(
+        SearchLimits::default(),
+        |iterations| iterations == 7,
+        &2f64.sqrt(),
+        (),
+    );
+    assert_eq!((run.iterations, run.stop), (7, SearchStop::Condition));
+}
+
+#[test]
+fn test_observer_receives_the_iterations() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    /// Streams the telemetry over a channel, as to a user interface, and stops the search once
+    /// the root has 20 visits.
+    struct Streaming {
+        expansions: usize,
+        iterations: usize,
+        telemetry: Sender<SearchTelemetry>,
+    }
+
+    impl SearchObserver<u8, Nat> for Streaming {
+        fn on_expand(&mut self, _node: NodeId, _parent: NodeId, _mv: &u8) {
+            self.expansions += 1;
+        }
+
+        fn on_iteration(&mut self, outcome: &IterationOutcome<Nat>) {
+            assert_eq!(outcome.expanded, self.expansions > self.iterations);
+            self.iterations += 1;
+        }
+
+        fn on_telemetry(&mut self, telemetry: &SearchTelemetry) {
+            self.telemetry.send(*telemetry).unwrap();
+        }
+
+        fn should_stop(&mut self) -> bool {
+            self.iterations == 20
+        }
+    }
+
+    let state = Nim::new(10);
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    let (sender, receiver) = mpsc::channel();
+    mcts.set_observer(
+        Streaming {
+            expansions: 0,
+            iterations: 0,
+            telemetry: sender,
+        },
+        5,
+    );
+    let limits = SearchLimits {
+        iterations: Some(100),
+        ..SearchLimits::default()
+    };
+    let run = mcts.run(limits, &2f64.sqrt(), ());
+    assert_eq!((run.iterations, run.stop), (20, SearchStop::Condition));
+    let telemetry: Vec<SearchTelemetry> = receiver.try_iter().collect();
+    assert_eq!(telemetry.len(), 4);
+    let last = telemetry.last().unwrap();
+    assert_eq!(last.iterations, 20);
+    assert_eq!(last.nodes, mcts.node_count());
+    assert_eq!(last.root_value, mcts.root_value());
+    assert!(last.mean_depth >= 1. && last.nodes_per_second > 0.);
+
+    mcts.clear_observer();
+    assert_eq!(mcts.run(limits, &2f64.sqrt(), ()).iterations, 100);
+}
+
+#[test]
+#[should_panic(expected = "At least one limit must be set")]
+fn test_run_needs_a_limit() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    DefaultMcts::new(&state).run(SearchLimits::default(), &2f64.sqrt(), ());
+}
+
+#[test]
+fn test_execute_with_outcome() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(2);
+    let mcts = DefaultMcts::new(&state);
+    let outcome = mcts.execute_with_outcome(&2f64.sqrt(), ());
+    assert!(outcome.expanded);
+    assert_eq!(outcome.depth, 1);
+    let tree = mcts.tree.lock().unwrap();
+    let leaf = tree.get(outcome.leaf).unwrap();
+    assert_eq!(leaf.parent().unwrap().id(), tree.root().id());
+    assert_eq!(leaf.value().sum_rewards, outcome.reward);
+}
+
+#[test]
+fn test_context_shared_behind_an_arc() {
+    use std::sync::Arc;
+
+    use crate::DefaultMcts;
+
+    /// A Nim whose allowed takes are a context shared by all the states.
+    #[derive(Debug, Clone)]
+    struct SubtractionGame {
+        takes: Arc<Vec<u8>>,
+        sticks: u8,
+        turn: u8,
+    }
+
+    impl GameTrait for SubtractionGame {
+        type Player = u8;
+        type Move = u8;
+
+        fn legals_moves(&self) -> Vec<u8> {
+            self.takes
+                .iter()
+                .copied()
+                .filter(|&take| take <= self.sticks)
+                .collect()
+        }
+
+        fn player_turn(&self) -> u8 {
+            self.turn
+        }
+
+        fn hash(&self) -> u64 {
+            self.sticks as u64 * 2 + self.turn as u64
+        }
+
+        fn is_final(&self) -> bool {
+            self.legals_moves().is_empty()
+        }
+
+        fn do_move(&mut self, m: &u8) {
+            self.sticks -= m;
+            self.turn = 3 - self.turn;
+        }
+
+        fn get_winner(&self) -> u8 {
+            3 - self.turn
+        }
+    }
+
+    let takes = Arc::new(vec![1, 4]);
+    let state = SubtractionGame {
+        takes: Arc::clone(&takes),
+        sticks: 10,
+        turn: 1,
+    };
+    let mcts = DefaultMcts::new(&state);
+    for _ in 0..200 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    {
+        let tree = mcts.tree.lock().unwrap();
+        let mut moves: Vec<u8> = tree
+            .root()
+            .children()
+            .map(|child| *child.value().state.last().unwrap())
+            .collect();
+        moves.sort();
+        assert_eq!(moves, [1, 4]);
+        assert!(tree
+            .nodes()
+            .all(|node| node.value().state.iter().all(|mv| takes.contains(mv))));
+    }
+    drop(mcts);
+    drop(state);
+    // The states of the search shared the context instead of cloning it.
+    assert_eq!(Arc::strong_count(&takes), 1);
+}
+
+#[test]
+fn test_determinized_agent() {
+    use crate::mcts_determinized_agent;
+    use crate::test_game::Nim;
+    use rand::Rng;
+
+    // Taking all the sticks wins, the hidden number of sticks is 2 in most worlds.
+    let agent = mcts_determinized_agent(
+        &(),
+        |_: &(), rng: &mut dyn RngCore| Nim::new(if rng.gen_bool(0.75) { 2 } else { 3 }),
+        &mut StdRng::seed_from_u64(0),
+        8,
+        300,
+        2f64.sqrt(),
+    );
+    assert_eq!(agent, 2);
+}
+
+#[test]
+fn test_compact_historics() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(12);
+    let full = DefaultMcts::deterministic(&state, 0);
+    let mut compact = DefaultMcts::deterministic(&state, 0);
+    compact.compact_historics();
+    for _ in 0..300 {
+        full.execute(&2f64.sqrt(), ());
+        compact.execute(&2f64.sqrt(), ());
+    }
+    {
+        let full_tree = full.tree.lock().unwrap();
+        let compact_tree = compact.tree.lock().unwrap();
+        for (full_node, compact_node) in full_tree
+            .root()
+            .descendants()
+            .zip(compact_tree.root().descendants())
+        {
+            assert!(compact_node.value().state.len() <= 1);
+            assert_eq!(historic(compact_node), full_node.value().state);
+            assert_eq!(compact_node.value().n_visits, full_node.value().n_visits);
+        }
+    }
+    assert_eq!(compact.best_move(&0.), full.best_move(&0.));
+
+    let mv = compact.best_move(&0.);
+    compact.advance_root(&mv);
+    let tree = compact.tree.lock().unwrap();
+    assert!(tree.root().value().state.is_empty());
+    for node in tree.root().descendants().skip(1) {
+        assert_eq!(historic(node).len(), node.ancestors().count());
+    }
+}
+
+#[test]
+#[should_panic(expected = "The evaluator needs the full historics of the nodes")]
+fn test_compact_historics_refuses_the_evaluators_reading_the_plies() {
+    use crate::test_game::Nim;
+    use crate::{DefaultBackProp, DefaultLazyTreePolicy, DefaultPlayout, ProgressiveBiasEvaluator};
+
+    type Mcts<'a> = LazyMcts<
+        'a,
+        Nim,
+        DefaultLazyTreePolicy<Nim, ProgressiveBiasEvaluator, (), u32>,
+        DefaultPlayout,
+        DefaultBackProp,
+        ProgressiveBiasEvaluator,
+        (),
+        u32,
+    >;
+    let state = Nim::new(12);
+    Mcts::new(&state).compact_historics();
+}
+
+#[test]
+fn test_static_eval_only_computed_for_the_evaluators_reading_it() {
+    use crate::test_game::{EvaluatedNim, Nim};
+    use crate::{
+        DefaultBackProp, DefaultLazyTreePolicy, DefaultPlayout, DefaultUctEvaluator,
+        ProgressiveBiasArgs, ProgressiveBiasEvaluator,
+    };
+
+    type Mcts<'a, EV> = LazyMcts<
+        'a,
+        EvaluatedNim,
+        DefaultLazyTreePolicy<EvaluatedNim, EV, (), u32>,
+        DefaultPlayout,
+        DefaultBackProp,
+        EV,
+        (),
+        u32,
+    >;
+
+    let state = EvaluatedNim(Nim::new(5));
+    let uct = Mcts::<DefaultUctEvaluator>::new(&state);
+    let biased = Mcts::<ProgressiveBiasEvaluator>::new(&state);
+    let args = ProgressiveBiasArgs {
+        c: 2f64.sqrt(),
+        weight: 1.,
+    };
+    for _ in 0..20 {
+        uct.execute(&2f64.sqrt(), ());
+        biased.execute(&args, ());
+    }
+    assert!(uct
+        .root_stats()
+        .iter()
+        .all(|info| info.static_eval.is_none()));
+    assert!(biased
+        .root_stats()
+        .iter()
+        .all(|info| info.static_eval == Some(0.5)));
+}
+
+#[test]
+fn test_historic_matches_the_full_historics() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(15);
+    let full = DefaultMcts::deterministic(&state, 3);
+    let mut compact = DefaultMcts::deterministic(&state, 3);
+    compact.compact_historics();
+    for _ in 0..500 {
+        full.execute(&2f64.sqrt(), ());
+        compact.execute(&2f64.sqrt(), ());
+    }
+    let full_historics: Vec<_> = full
+        .tree
+        .lock()
+        .unwrap()
+        .root()
+        .descendants()
+        .map(|node| (node.id(), node.value().state.clone()))
+        .collect();
+    for (id, full_historic) in full_historics {
+        assert_eq!(full.historic(id), full_historic);
+        assert_eq!(compact.historic(id), full_historic);
+    }
+}
+
+#[test]
+fn test_best_move_or_falls_back_without_iterations() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(7);
+    let first_legal = |state: &Nim| state.legals_moves()[0];
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    assert_eq!(mcts.best_move_or(first_legal, &2f64.sqrt()), 1);
+
+    for _ in 0..100 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    assert_eq!(
+        mcts.best_move_or(first_legal, &2f64.sqrt()),
+        mcts.best_move(&2f64.sqrt())
+    );
+}
+
+#[test]
+fn test_policy_target_array() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(9);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    assert_eq!(mcts.policy_target_array(4, |&mv| mv as usize), [0.; 4]);
+
+    for _ in 0..200 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let target = mcts.policy_target_array(5, |&mv| mv as usize);
+    assert_eq!(target.len(), 5);
+    assert_eq!(target[0], 0.);
+    assert_eq!(target[4], 0.);
+    assert!((target.iter().sum::<f32>() - 1.).abs() < 1e-6);
+    let visits = mcts.move_visits();
+    let total: Nat = visits.iter().map(|&(_, n_visits)| n_visits).sum();
+    for (mv, n_visits) in visits {
+        assert!((target[mv as usize] - n_visits as f32 / total as f32).abs() < 1e-6);
+    }
+
+    // All the moves are mapped to the same index, their shares are summed.
+    let target = mcts.policy_target_array(1, |_| 0);
+    assert!((target[0] - 1.).abs() < 1e-6);
+}
+
+#[test]
+fn test_visit_histogram_string() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(9);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    assert_eq!(
+        mcts.visit_histogram_string(),
+        "The root has no children, execute the search first\n"
+    );
+
+    for _ in 0..100 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let histogram = mcts.visit_histogram_string();
+    let visits = mcts.move_visits();
+    let max_visits = visits.iter().map(|&(_, n_visits)| n_visits).max().unwrap();
+    assert_eq!(histogram.lines().count(), visits.len());
+    for (line, (mv, n_visits)) in histogram.lines().zip(visits) {
+        assert!(line.starts_with(&format!("{} ", mv)));
+        assert!(line.ends_with(&format!(" {}", n_visits)));
+        let full_blocks = line.matches('█').count();
+        assert_eq!(full_blocks, (n_visits * 20 / max_visits) as usize);
+    }
+}
+
+#[test]
+fn test_root_analysis() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(5);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    mcts.execute(&2f64.sqrt(), ());
+    let analysis = mcts.root_analysis(&2f64.sqrt());
+    assert_eq!(analysis.len(), 3);
+    assert_eq!(analysis[0].visits, 1);
+    assert!(analysis[0].mean_reward.is_some() && analysis[0].score.is_some());
+    assert_eq!(analysis[0].variance, None);
+    assert!(analysis[1..]
+        .iter()
+        .all(|info| info.visits == 0 && info.score.is_none()));
+
+    for _ in 0..100 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let analysis = mcts.root_analysis(&2f64.sqrt());
+    let mut moves: Vec<u8> = analysis.iter().map(|info| info.mv).collect();
+    moves.sort();
+    assert_eq!(moves, [1, 2, 3]);
+    assert!(analysis
+        .windows(2)
+        .all(|pair| pair[0].visits >= pair[1].visits));
+    assert_eq!(
+        analysis[0].mv,
+        mcts.best_move_by(BestMoveCriterion::MostVisited)
+    );
+    let visits: Vec<(u8, Nat)> = analysis.iter().map(|info| (info.mv, info.visits)).collect();
+    for (mv, n_visits) in mcts.move_visits() {
+        assert!(visits.contains(&(mv, n_visits)));
+    }
+    assert!(analysis.iter().all(|info| info.variance.is_some()));
+    let stats = mcts.root_stats();
+    assert!(stats.iter().all(|info| info.score.is_none()));
+    let without_scores: Vec<_> = analysis
+        .into_iter()
+        .map(|info| RootMoveInfo {
+            score: None,
+            ..info
+        })
+        .collect();
+    assert_eq!(stats, without_scores);
+
+    let pv = mcts.pv(3);
+    let most_visited = stats.iter().find(|info| info.mv == pv[0]).unwrap();
+    assert_eq!(most_visited.visits, stats[0].visits);
+    assert!(pv.len() <= 3 && pv.iter().sum::<u8>() <= 5);
+    assert!(mcts.pv(0).is_empty());
+}
+
+#[test]
+fn test_root_value() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(3);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    assert_eq!(mcts.root_value(), None);
+    for _ in 0..50 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let root = mcts.tree.lock().unwrap().root().value().clone();
+    assert_eq!(
+        mcts.root_value(),
+        Some(n64(f64::from(root.sum_rewards) / f64::from(root.n_visits)))
+    );
+}
+
+#[test]
+fn test_root_value_ci() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(5);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    assert_eq!(mcts.root_value_ci(0.95), (n64(0.), n64(0.)));
+    mcts.execute(&2f64.sqrt(), ());
+    let value = mcts.root_value().unwrap();
+    assert_eq!(mcts.root_value_ci(0.95), (value, value));
+
+    for _ in 0..200 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let value = mcts.root_value().unwrap();
+    let (low, high) = mcts.root_value_ci(0.95);
+    assert!(low < value && value < high);
+    assert!(((value - low) - (high - value)).raw().abs() < 1e-9);
+    let (narrow_low, narrow_high) = mcts.root_value_ci(0.5);
+    assert!(low < narrow_low && narrow_high < high);
+}
+
+#[test]
+fn test_share_symmetric_statistics() {
+    use crate::test_game::{MirroredNim, Nim};
+    use crate::DefaultMcts;
+
+    let state = MirroredNim(Nim::new(2));
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    mcts.share_symmetric_statistics();
+    // Expands the 4 children of the root, then visits them again.
+    for _ in 0..4 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let statistics = |mcts: &DefaultMcts<MirroredNim>| {
+        let tree = mcts.tree.lock().unwrap();
+        let mut statistics: Vec<_> = tree
+            .root()
+            .children()
+            .map(|child| {
+                let value = child.value();
+                (value.state[0], value.n_visits, value.sum_rewards)
+            })
+            .collect();
+        statistics.sort();
+        statistics
+    };
+    let before = statistics(&mcts);
+    mcts.execute(&2f64.sqrt(), ());
+    let after = statistics(&mcts);
+    assert_eq!(after.len(), 4);
+    // The visited child and its mirror both gained the visit, the other two didn't.
+    let gained: Vec<_> = before
+        .iter()
+        .zip(&after)
+        .filter(|(before, after)| before != after)
+        .map(|(before, after)| (after.0, after.1 - before.1, after.2 - before.2))
+        .collect();
+    assert_eq!(gained.len(), 2);
+    assert_eq!(gained[0].0, -gained[1].0);
+    assert_eq!((gained[0].1, gained[0].2), (gained[1].1, gained[1].2));
+    assert_eq!(gained[0].1, 1);
+}
+
+#[test]
+fn test_is_converged() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(3);
+    let is_converged = |visits: &[Nat], threshold: f64| {
+        let mcts = DefaultMcts::new(&state);
+        {
+            let mut tree = mcts.tree.lock().unwrap();
+            tree.root_mut().value().n_visits = visits.iter().sum();
+            for (mv, &n_visits) in (1..).zip(visits) {
+                tree.root_mut().append(LazyMctsNode::<Nim, u32, ()> {
+                    n_visits,
+                    ..LazyMctsNode::<Nim, u32, ()>::new(vec![mv])
+                });
+            }
+        }
+        mcts.is_converged(threshold)
+    };
+    assert!(!is_converged(&[], 0.5));
+    assert!(is_converged(&[10], 0.5));
+    // Decisive, the gap is 85% of the visits.
+    assert!(is_converged(&[5, 90, 5], 0.5));
+    assert!(!is_converged(&[5, 90, 5], 0.9));
+    // Balanced, the gap is 1% of the visits.
+    assert!(!is_converged(&[34, 33, 33], 0.05));
+    assert!(is_converged(&[34, 33, 33], 0.));
+}
+
+#[test]
+fn test_analyze_positions() {
+    use crate::analyze_positions;
+    use crate::test_game::Nim;
+
+    let states = vec![
+        Nim::new(1),
+        Nim::new(2),
+        Nim::new(1),
+        Nim::new(2),
+        Nim::new(1),
+    ];
+    for threads in [1, 2, 8] {
+        let analyses = analyze_positions(states.clone(), 200, 2f64.sqrt(), threads);
+        let moves: Vec<u8> = analyses.iter().map(|&(mv, _)| mv).collect();
+        assert_eq!(moves, [1, 2, 1, 2, 1]);
+        assert_eq!(analyses[0].1, n64(1.));
+        assert!(analyses
+            .iter()
+            .all(|&(_, value)| (0. ..=1.).contains(&value.raw())));
+    }
+}
+
+#[test]
+fn test_statistics() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(8);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    let statistics = mcts.statistics();
+    assert_eq!(statistics.nodes, 1);
+    assert_eq!(statistics.max_depth, 0);
+    assert_eq!(statistics.best_move, None);
+
+    for _ in 0..200 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let statistics = mcts.statistics();
+    let tree = mcts.tree.lock().unwrap();
+    assert_eq!(statistics.nodes, tree.root().descendants().count());
+    let max_depth = tree
+        .root()
+        .descendants()
+        .map(|node| node.value().depth())
+        .max();
+    assert_eq!(Some(statistics.max_depth), max_depth);
+    assert_eq!(statistics.root_visits, 200);
+    drop(tree);
+    assert_eq!(
+        statistics.effective_branching_factor,
+        mcts.effective_branching_factor()
+    );
+    let (mv, value) = statistics.best_move.unwrap();
+    assert_eq!(mv, mcts.best_move_by(BestMoveCriterion::MostVisited));
+    assert!((0. ..=1.).contains(&value));
+}
+
+#[test]
+fn test_opening_book() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    assert!(mcts.opening_book(0).is_empty());
+    for _ in 0..500 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let book = mcts.opening_book(50);
+    assert_eq!(
+        book.get(&[]),
+        Some(&mcts.best_move_by(BestMoveCriterion::MostVisited))
+    );
+    let tree = mcts.tree.lock().unwrap();
+    let positions = tree
+        .root()
+        .descendants()
+        .filter(|node| node.value().n_visits >= 50 && node.has_children())
+        .count();
+    assert_eq!(book.len(), positions);
+    for node in tree.root().descendants() {
+        if let Some(mv) = book.get(&node.value().state) {
+            assert!(node.value().n_visits >= 50);
+            assert!(node
+                .children()
+                .any(|child| child.value().state.last() == Some(mv)));
+        }
+    }
+}
+
+#[cfg(feature = "debug-replay")]
+#[test]
+fn test_replay_rebuilds_a_parallel_search() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(12);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    mcts.start_recording();
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..100 {
+                    mcts.execute(&2f64.sqrt(), ());
+                }
+            });
+        }
+    });
+    mcts.execute_leaf_parallel(&2f64.sqrt(), (), 4, 2);
+    let records = mcts.take_recording();
+    assert_eq!(records.len(), 404);
+    assert!(mcts.take_recording().is_empty());
+
+    let mut replayed = DefaultMcts::new(&state);
+    replayed.replay(&records, &2f64.sqrt());
+    let statistics = |mcts: &DefaultMcts<Nim>| {
+        let mut statistics: Vec<_> = mcts
+            .tree
+            .lock()
+            .unwrap()
+            .root()
+            .descendants()
+            .map(|node| {
+                let value = node.value();
+                let mut unvisited_moves = value.unvisited_moves.clone();
+                unvisited_moves.sort();
+                (
+                    value.state.clone(),
+                    value.n_visits,
+                    value.sum_rewards,
+                    unvisited_moves,
+                )
+            })
+            .collect();
+        statistics.sort();
+        statistics
+    };
+    assert_eq!(statistics(&replayed), statistics(&mcts));
+}
+
+#[test]
+fn test_deterministic_order_makes_seeded_searches_reproducible() {
+    use crate::test_game::{Nim, ShuffledNim};
+    use crate::{DefaultUctEvaluator, NoPlayout, TreePolicyMcts};
+
+    // The leaves are evaluated without playout, whose moves would be drawn in a random order.
+    type Mcts<'a> = TreePolicyMcts<'a, ShuffledNim, DefaultUctEvaluator, NoPlayout>;
+    let state = ShuffledNim(Nim::new(12));
+    let search = |seed| {
+        let mut mcts = Mcts::deterministic(&state, seed);
+        mcts.ensure_deterministic_order(|&mv| mv.into());
+        for _ in 0..300 {
+            mcts.execute(&2f64.sqrt(), ());
+        }
+        let tree = mcts.tree.lock().unwrap();
+        tree.root()
+            .descendants()
+            .map(|node| (node.value().state.clone(), node.value().n_visits))
+            .collect::<Vec<_>>()
+    };
+    let first = search(7);
+    for _ in 0..5 {
+        assert_eq!(search(7), first);
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_leaf_pooled_is_reproducible() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(15);
+    let search = |mcts: DefaultMcts<Nim>| {
+        for _ in 0..20 {
+            mcts.execute_leaf_pooled(&2f64.sqrt(), (), 8);
+        }
+        let tree = mcts.tree.lock().unwrap();
+        assert_eq!(tree.root().value().n_visits, 160);
+        tree.root()
+            .descendants()
+            .map(|node| (node.value().state.clone(), node.value().n_visits))
+            .collect::<Vec<_>>()
+    };
+    let pooled = DefaultMcts::with_playout_pool(&state, 3, Some(0)).unwrap();
+    assert_eq!(
+        search(pooled),
+        search(DefaultMcts::deterministic(&state, 0))
+    );
+}
+
+#[test]
+fn test_navigation_with_a_matching_closure() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..100 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    mcts.forget_move_matching(|&mv| mv == 3);
+    {
+        let tree = mcts.tree.lock().unwrap();
+        assert!(tree
+            .root()
+            .children()
+            .all(|child| child.value().state != [3]));
+        assert!(!tree.root().value().unvisited_moves.contains(&3));
+    }
+
+    let visits = mcts
+        .move_visits()
+        .into_iter()
+        .find(|&(mv, _)| mv == 2)
+        .unwrap()
+        .1;
+    mcts.advance_root_matching(|&mv| mv == 2);
+    assert_eq!(mcts.root_state.sticks, 8);
+    assert_eq!(mcts.tree.lock().unwrap().root().value().n_visits, visits);
+
+    // A move which was never expanded is found among the legal moves.
+    let mut mcts = DefaultMcts::new(&state);
+    mcts.advance_root_matching(|&mv| mv == 1);
+    assert_eq!(mcts.root_state.sticks, 9);
+}
+
+#[test]
+fn test_node_selection_breakdown() {
+    use crate::test_game::Nim;
+    use crate::{uct_value, DefaultMcts};
+
+    let state = Nim::new(9);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..100 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let root = mcts.tree.lock().unwrap().root().id();
+    let breakdown = mcts.node_selection_breakdown(root, 2f64.sqrt());
+    assert_eq!(breakdown.len(), 3);
+    let tree = mcts.tree.lock().unwrap();
+    for ((mv, exploitation, exploration), child) in
+        breakdown.into_iter().zip(tree.root().children())
+    {
+        let value = child.value();
+        assert_eq!(value.state, [mv]);
+        let uct = uct_value(
+            100,
+            f64::from(value.sum_rewards),
+            value.n_visits,
+            2f64.sqrt(),
+        );
+        assert_eq!(n64(exploitation + exploration), uct);
+        assert_eq!(
+            exploitation,
+            f64::from(value.sum_rewards) / f64::from(value.n_visits)
+        );
+    }
+}
+
+#[test]
+fn test_prune_gives_the_moves_back() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(12);
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..300 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let nodes = mcts.statistics().nodes;
+    let pruned = mcts.prune(5);
+    assert!(pruned > 0);
+    assert_eq!(mcts.statistics().nodes, nodes - pruned);
+    {
+        let tree = mcts.tree.lock().unwrap();
+        for node in tree.root().descendants() {
+            assert!(node.value().n_visits >= 5);
+            // Each legal move is either a child or unvisited, never both.
+            let mut moves: Vec<u8> = node
+                .children()
+                .map(|child| *child.value().state.last().unwrap())
+                .chain(node.value().unvisited_moves.iter().copied())
+                .collect();
+            moves.sort();
+            let sticks = 12 - node.value().state.iter().sum::<u8>();
+            assert_eq!(moves, (1..=sticks.min(3)).collect::<Vec<_>>());
+        }
+    }
+
+    // The search resumes and expands the pruned moves again.
+    for _ in 0..300 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let tree = mcts.tree.lock().unwrap();
+    assert_eq!(tree.root().value().n_visits, 600);
+    for node in tree.root().descendants() {
+        let mut moves: Vec<u8> = node
+            .children()
+            .map(|child| *child.value().state.last().unwrap())
+            .collect();
+        let children = moves.len();
+        moves.sort();
+        moves.dedup();
+        assert_eq!(moves.len(), children);
+    }
+}
+
+#[test]
+fn test_tiny_budget_visits_each_root_move() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    for seed in 0..20 {
+        let state = Nim::new(10);
+        let mcts = DefaultMcts::deterministic(&state, seed);
+        for _ in 0..3 {
+            mcts.execute(&2f64.sqrt(), ());
+        }
+        let mut visits = mcts.move_visits();
+        visits.sort();
+        assert_eq!(visits, [(1, 1), (2, 1), (3, 1)]);
+    }
+}
+
+#[test]
+fn test_explain_best_move() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(2);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..50 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let explanation = mcts.explain_best_move(&2f64.sqrt());
+    assert_eq!(explanation.best.mv, mcts.best_move(&2f64.sqrt()));
+    assert_eq!(explanation.best.mv, 2);
+    assert_eq!(explanation.best.mean_reward, Some(n64(1.)));
+    let runner_up = explanation.runner_up.clone().unwrap();
+    assert_eq!(runner_up.mv, 1);
+    assert_eq!(explanation.best.visits + runner_up.visits, 50);
+    assert_eq!(explanation.principal_variation, [2]);
+    assert_eq!(explanation.root_value, mcts.root_value());
+
+    let text = explanation.to_string();
+    assert!(text.starts_with("best move 2: "));
+    assert!(text.contains("runner-up 1: "));
+    assert!(text.contains("principal variation: 2\n"));
+}
+
+#[test]
+fn test_leaf_parallel_playout_budget_grows_with_depth() {
+    use std::cell::RefCell;
+
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    let depths = RefCell::new(vec![]);
+    let budget = |depth: usize| {
+        depths.borrow_mut().push(depth);
+        2 * depth
+    };
+    // The 3 moves of the root are expanded first, then a child of one of them.
+    for _ in 0..4 {
+        mcts.execute_leaf_parallel_by(&2f64.sqrt(), (), budget, 2);
+    }
+    assert_eq!(*depths.borrow(), [1, 1, 1, 2]);
+    let tree = mcts.tree.lock().unwrap();
+    assert_eq!(tree.root().value().n_visits, 3 * 2 + 4);
+    let deep_leaf = tree
+        .root()
+        .descendants()
+        .find(|node| node.ancestors().count() == 2)
+        .unwrap();
+    assert_eq!(deep_leaf.value().n_visits, 4);
+
+    // A budget of 0 still gives the leaf a visit.
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    mcts.execute_leaf_parallel_by(&2f64.sqrt(), (), |_| 0, 2);
+    assert_eq!(mcts.tree.lock().unwrap().root().value().n_visits, 1);
+}
+
+#[test]
+fn test_first_visits_are_uniform() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mut first_moves = [0; 3];
+    for seed in 0..300 {
+        let mcts = DefaultMcts::deterministic(&state, seed);
+        mcts.execute(&2f64.sqrt(), ());
+        first_moves[usize::from(mcts.move_visits()[0].0) - 1] += 1;
+    }
+    for count in first_moves {
+        assert!((70..=130).contains(&count), "{:?}", first_moves);
+    }
+}
+
+#[cfg(feature = "test-utils")]
+#[test]
+fn test_assert_invariants() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..200 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    mcts.assert_invariants();
+    mcts.prune(3);
+    mcts.assert_invariants();
+    mcts.compact_historics();
+    mcts.execute(&2f64.sqrt(), ());
+    mcts.assert_invariants();
+
+    // A move expanded twice.
+    let mv = mcts.move_visits()[0].0;
+    mcts.tree
+        .get_mut()
+        .unwrap()
+        .root_mut()
+        .value()
+        .unvisited_moves
+        .push(mv);
+    let result =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| mcts.assert_invariants()));
+    let message = result.unwrap_err();
+    assert!(message
+        .downcast_ref::<String>()
+        .unwrap()
+        .contains("is still unvisited"));
+}
+
+#[test]
+fn test_skip_root_reward_keeps_the_children_statistics() {
+    use crate::test_game::Nim;
+    use crate::{DefaultPlayout, DefaultUctEvaluator, SkipRootRewardBackProp, TreePolicyMcts};
+
+    let state = Nim::new(10);
+    let default = TreePolicyMcts::<Nim, DefaultUctEvaluator>::deterministic(&state, 3);
+    let skipping = TreePolicyMcts::<
+        Nim,
+        DefaultUctEvaluator,
+        DefaultPlayout,
+        SkipRootRewardBackProp,
+    >::deterministic(&state, 3);
+    for _ in 0..200 {
+        default.execute(&2f64.sqrt(), ());
+        skipping.execute(&2f64.sqrt(), ());
+    }
+    let (default, skipping) = (default.tree.lock().unwrap(), skipping.tree.lock().unwrap());
+    let stats = |node: NodeRef<LazyMctsNode<Nim, u32, ()>>| {
+        (
+            node.value().n_visits,
+            node.value().sum_rewards,
+            node.value().state.clone(),
+        )
+    };
+    assert!(default.root().descendants().skip(1).map(stats).eq(skipping
+        .root()
+        .descendants()
+        .skip(1)
+        .map(stats)));
+    assert_eq!(skipping.root().value().n_visits, 200);
+    assert_eq!(skipping.root().value().sum_rewards, 0);
+    assert!(default.root().value().sum_rewards > 0);
+}
+
+#[test]
+fn test_execute_time_budgeted_counts_the_playout_time() {
+    use crate::test_game::Nim;
+    use crate::{DefaultBackProp, DefaultUctEvaluator, Playout, TreePolicyMcts};
+
+    /// Plays randomly and sleeps during the time given as argument.
+    struct SlowPlayout;
+
+    impl Playout<Nim> for SlowPlayout {
+        type Args = Duration;
+
+        fn playout(state: Nim, cost: Duration, rng: &mut dyn RngCore) -> Nim {
+            thread::sleep(cost);
+            crate::DefaultPlayout::playout(state, (), rng)
+        }
+    }
+
+    let state = Nim::new(10);
+    let mcts =
+        TreePolicyMcts::<Nim, DefaultUctEvaluator, SlowPlayout, DefaultBackProp>::new(&state);
+    let cost = Duration::from_millis(5);
+    let (iterations, spent) =
+        mcts.execute_time_budgeted(Duration::from_millis(50), &2f64.sqrt(), cost);
+    assert!(spent >= Duration::from_millis(50));
+    assert!(spent >= cost * iterations as u32);
+    // Sleeping may take longer than asked, never shorter.
+    assert!((1..=10).contains(&iterations));
+    assert_eq!(
+        mcts.tree.lock().unwrap().root().value().n_visits as usize,
+        iterations
+    );
+}
+
+#[test]
+fn test_advance_root_frees_the_dropped_nodes() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(20);
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    for mv in [1, 2, 3, 1, 2] {
+        for _ in 0..300 {
+            mcts.execute(&2f64.sqrt(), ());
+        }
+        let child_nodes = {
+            let tree = mcts.tree.lock().unwrap();
+            let child = child_matching::<Nim, u32, ()>(&tree, |other| *other == mv).unwrap();
+            tree.get(child).unwrap().descendants().count()
+        };
+        mcts.advance_root(&mv);
+        let tree = mcts.tree.lock().unwrap();
+        assert_eq!(tree.nodes().count(), child_nodes);
+        assert_eq!(tree.root().descendants().count(), child_nodes);
+    }
+}
+
+#[test]
+fn test_shrink_to_fit_frees_the_forgotten_nodes() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..300 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let nodes = mcts.statistics().nodes;
+    mcts.forget_move(&1);
+    let kept = mcts.statistics().nodes;
+    assert!(kept < nodes);
+    assert_eq!(mcts.tree.lock().unwrap().nodes().count(), nodes);
+
+    assert_eq!(mcts.shrink_to_fit(), nodes - kept);
+    assert_eq!(mcts.tree.lock().unwrap().nodes().count(), kept);
+    assert_eq!(mcts.statistics().nodes, kept);
+    assert_eq!(mcts.shrink_to_fit(), 0);
+    mcts.execute(&2f64.sqrt(), ());
+}
+
+#[test]
+fn test_recycle_nodes_keeps_the_tree() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mut recycled = DefaultMcts::deterministic(&state, 0);
+    recycled.recycle_nodes();
+    let mut copied = DefaultMcts::deterministic(&state, 0);
+    for mv in [1, 3, 2] {
+        for mcts in [&recycled, &copied] {
+            for _ in 0..200 {
+                mcts.execute(&2f64.sqrt(), ());
+            }
+        }
+        recycled.advance_root(&mv);
+        copied.advance_root(&mv);
+        assert_eq!(recycled.write_tree(), copied.write_tree());
+        assert_eq!(recycled.statistics(), copied.statistics());
+    }
+    // The historics and the unvisited moves were moved to the new tree.
+    let tree = recycled.tree.lock().unwrap();
+    for node in tree.root().descendants() {
+        let mut state = Nim::new(4);
+        for mv in &historic(node) {
+            state.do_move(mv);
+        }
+        assert_eq!(node.value().state.len(), node.ancestors().count());
+        assert_eq!(
+            node.value().unvisited_moves.len() + node.children().count(),
+            state.legal_moves_count()
+        );
+    }
+}
+
+#[test]
+fn test_edge_table_shares_the_moves_between_searches() {
+    use crate::test_game::Nim;
+    use crate::{DefaultMcts, TreePolicyMcts};
+
+    let state = Nim::new(10);
+    let edge_table = Arc::new(EdgeTable::new());
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    mcts.set_edge_table(Some(edge_table.clone()));
+    for _ in 0..100 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    // The children of the root are the only nodes played from its state, so their edges have
+    // their statistics, while the transpositions deeper share theirs.
+    {
+        let tree = mcts.tree.lock().unwrap();
+        for child in tree.root().children() {
+            let mv = *child.value().state.last().unwrap();
+            let (visits, sum_rewards) = edge_table.get(state.hash(), &mv).unwrap();
+            assert_eq!(visits, child.value().n_visits);
+            assert_eq!(sum_rewards, f64::from(child.value().sum_rewards));
         }
     }
+    let edges = edge_table.len();
+    assert!(edges > 0);
+
+    // A second search starts its children from the edges.
+    let mut second = TreePolicyMcts::<Nim, crate::DefaultUctEvaluator>::deterministic(&state, 1);
+    second.set_edge_table(Some(edge_table.clone()));
+    second.execute(&2f64.sqrt(), ());
+    let first_child = second.move_visits()[0];
+    assert!(first_child.1 > 1);
+    assert_eq!(
+        first_child.1,
+        edge_table.get(state.hash(), &first_child.0).unwrap().0
+    );
+
+    // Without the table, the search doesn't record nor seed anything.
+    second.set_edge_table(None);
+    let recorded = edge_table.get(state.hash(), &first_child.0).unwrap().0;
+    for _ in 0..10 {
+        second.execute(&2f64.sqrt(), ());
+    }
+    assert_eq!(
+        edge_table.get(state.hash(), &first_child.0).unwrap().0,
+        recorded
+    );
+}
+
+#[test]
+fn test_dominated_root_moves_stop_receiving_visits() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    // Taking the 2 sticks always wins, taking 1 always loses.
+    let state = Nim::new(2);
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    mcts.prune_dominated_root_moves(0.1, 1., 1);
+    for _ in 0..50 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    assert_eq!(mcts.move_visits().len(), 1);
+    let visits = mcts.move_visits()[0].1;
+    for _ in 0..100 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    assert_eq!(mcts.move_visits(), [(2, visits + 100)]);
+    assert_eq!(mcts.best_move(&2f64.sqrt()), 2);
+    // The node of the removed move was freed from the arena.
+    let tree = mcts.tree.lock().unwrap();
+    assert_eq!(tree.nodes().count(), tree.root().descendants().count());
+    drop(tree);
+
+    // Without the pruning, UCT keeps exploring the losing move.
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..120 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    assert!(mcts
+        .move_visits()
+        .iter()
+        .any(|&(mv, visits)| mv == 1 && visits > 5));
+
+    // Looking for the dominated moves every 200 visits, the losing move is kept until then.
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    mcts.prune_dominated_root_moves(0.1, 1., 200);
+    for _ in 0..120 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    assert_eq!(mcts.move_visits().len(), 2);
+}
+
+#[test]
+fn test_renderings_with_notation() {
+    use crate::test_game::{Nim, NotatedNim};
+    use crate::DefaultMcts;
+
+    let state = NotatedNim(Nim::new(3));
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..20 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let tree = mcts.write_tree_with_notation();
+    for sticks in 0..3 {
+        assert!(tree.contains(&format!("-> {}: ", sticks)));
+    }
+    // The children of the node with 2 sticks left leave 1 or 0 sticks.
+    assert!(!tree.contains("-> 3"));
+
+    let mut csv = vec![];
+    mcts.to_csv_with_notation(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv.lines().nth(2).unwrap().starts_with("1,0,-> "));
+    assert_eq!(Nim::new(3).move_notation(&2), "2");
+
+    let top = mcts.to_ascii(1);
+    assert_eq!(top.lines().count(), 4);
+    assert!(top.contains("-> 0: "));
+    assert_eq!(mcts.to_ascii(usize::MAX), tree);
+
+    let dot = mcts.to_dot(1, 0);
+    assert!(dot.starts_with("digraph mcts {\n") && dot.ends_with("}\n"));
+    assert!(dot.contains("    0 [label=\"20 visits\\nmean "));
+    assert_eq!(dot.matches(" -> ").count(), 3);
+    for sticks in 0..3 {
+        assert!(dot.contains(&format!("[label=\"-> {}\"]", sticks)));
+    }
+    assert_eq!(mcts.to_dot(0, 0).matches(" -> ").count(), 0);
+    assert_eq!(
+        mcts.to_dot(usize::MAX, 0).matches("visits").count(),
+        mcts.node_count()
+    );
+    assert_eq!(mcts.to_dot(usize::MAX, 21).matches("visits").count(), 1);
+    assert_eq!(dot_field("\"a\\b\""), "\\\"a\\\\b\\\"");
+}
+
+#[test]
+fn test_max_nodes_evicts_the_least_visited_leaves() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(30);
+    let mut mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..200 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    assert_eq!(mcts.evictions(), 0);
+    mcts.set_max_nodes(Some(50));
+    assert!(mcts.evictions() > 0);
+    assert!(mcts.node_count() <= 50);
+    for _ in 0..2000 {
+        mcts.execute(&2f64.sqrt(), ());
+        assert!(mcts.node_count() <= 50);
+    }
+    let tree = mcts.tree.lock().unwrap();
+    // The evicted nodes were freed, and their visits still count in the root.
+    assert!(tree.nodes().count() <= 51);
+    assert_eq!(tree.root().value().n_visits, 2200);
+    for node in tree.root().descendants() {
+        let mut moves: Vec<u8> = node
+            .children()
+            .map(|child| *child.value().state.last().unwrap())
+            .chain(node.value().unvisited_moves.iter().copied())
+            .collect();
+        moves.sort();
+        let sticks = 30 - node.value().state.iter().sum::<u8>();
+        assert_eq!(moves, (1..=sticks.min(3)).collect::<Vec<_>>());
+    }
+    drop(tree);
+
+    // The threads of `execute_many` always have iterations running, each waits for the eviction
+    // before its selection.
+    let evictions = mcts.evictions();
+    mcts.execute_many(2000, 4, &2f64.sqrt(), ());
+    assert!(mcts.evictions() > evictions);
+    assert!(mcts.node_count() <= 50);
+    // The evicted nodes don't count towards the node limit of a run.
+    let limits = SearchLimits {
+        iterations: Some(500),
+        nodes: Some(60),
+        ..SearchLimits::default()
+    };
+    assert_eq!(
+        mcts.run(limits, &2f64.sqrt(), ()).stop,
+        SearchStop::Iterations
+    );
+}
+
+#[test]
+fn test_sample_move_by_temperature() {
+    use crate::test_game::Nim;
+    use crate::DefaultMcts;
+
+    let state = Nim::new(10);
+    let mcts = DefaultMcts::deterministic(&state, 0);
+    for _ in 0..300 {
+        mcts.execute(&2f64.sqrt(), ());
+    }
+    let move_visits = mcts.move_visits();
+    let (mv, distribution) = mcts.sample_move(1.);
+    assert!(move_visits.iter().any(|&(other, _)| other == mv));
+    for ((mv, probability), (other, n_visits)) in distribution.iter().zip(&move_visits) {
+        assert_eq!(mv, other);
+        assert!((probability - f64::from(*n_visits) / 300.).abs() < 1e-9);
+    }
+
+    let most_visited = move_visits.iter().map(|&(_, n)| n).max().unwrap();
+    let (mv, distribution) = mcts.sample_move(0.);
+    assert!(move_visits.contains(&(mv, most_visited)));
+    assert_eq!(distribution.iter().filter(|&&(_, p)| p == 1.).count(), 1);
+    // A low temperature concentrates the distribution on the most visited move.
+    let (_, sharp) = mcts.sample_move(0.1);
+    assert!((sharp.iter().map(|&(_, p)| p).sum::<f64>() - 1.).abs() < 1e-9);
+    let top = |distribution: &[(u8, f64)]| distribution.iter().map(|&(_, p)| p).fold(0., f64::max);
+    assert!(top(&sharp) > top(&mcts.sample_move(1.).1));
+
+    // The seeded searches sample the same moves.
+    let samples = |seed| {
+        let mcts = DefaultMcts::deterministic(&state, seed);
+        for _ in 0..300 {
+            mcts.execute(&2f64.sqrt(), ());
+        }
+        (0..20).map(|_| mcts.sample_move(1.).0).collect::<Vec<_>>()
+    };
+    assert_eq!(samples(1), samples(1));
+}
+
+#[test]
+fn test_dirichlet_noise_mixes_into_the_root_priors() {
+    use crate::test_game::{Nim, TakeOnePrior};
+    use crate::{DefaultBackProp, DefaultPlayout};
+
+    type Mcts<'a> = LazyMcts<
+        'a,
+        Nim,
+        PuctTreePolicy<Nim, TakeOnePrior, f64>,
+        DefaultPlayout,
+        DefaultBackProp,
+        PuctEvaluator<TakeOnePrior>,
+        PuctInfo<u8>,
+        f64,
+    >;
+
+    let root_priors = |mcts: &Mcts| {
+        let tree = mcts.tree.lock().unwrap();
+        let mut priors = tree.root().value().additional_info.priors.clone();
+        priors.sort_by_key(|&(mv, _)| mv);
+        priors
+            .into_iter()
+            .map(|(_, prior)| prior)
+            .collect::<Vec<_>>()
+    };
+    let state = Nim::new(10);
+    let mcts = Mcts::deterministic(&state, 0);
+    // The priors of the root are computed before the first iteration to be noised.
+    mcts.add_dirichlet_noise(0.3, 0.);
+    assert_eq!(root_priors(&mcts), [0.8, 0.1, 0.1]);
+    mcts.execute(&1.5, ());
+    mcts.add_dirichlet_noise(0.3, 0.25);
+    let noisy = root_priors(&mcts);
+    assert_ne!(noisy, [0.8, 0.1, 0.1]);
+    assert!((noisy.iter().sum::<f64>() - 1.).abs() < 1e-9);
+    assert!(noisy.iter().all(|&prior| prior >= 0.75 * 0.1));
+    let stats = mcts.root_stats();
+    assert_eq!(stats.len(), 3);
+    assert_eq!(stats[0].prior, Some(noisy[stats[0].mv as usize - 1]));
+    assert!(stats[1..].iter().all(|info| info.prior.is_none()));
+    let tree = mcts.tree.lock().unwrap();
+    let child = tree.root().first_child().unwrap();
+    let mv = *child.value().state.last().unwrap();
+    assert_eq!(child.value().additional_info.prior, noisy[mv as usize - 1]);
 }
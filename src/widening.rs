@@ -0,0 +1,248 @@
+use std::marker::PhantomData;
+use std::ops::{Add, Div};
+
+use ego_tree::NodeId;
+use num_traits::{ToPrimitive, Zero};
+use rand::{Rng, RngCore};
+
+use crate::aliases::{LazyMctsNode, LazyMctsTree};
+use crate::defaults::DefaultLazyTreePolicy;
+use crate::mcts_node::historic;
+use crate::traits::{Evaluator, GameTrait, LazyTreePolicy};
+use crate::{Nat, Num};
+
+/// The arguments of the `WideningEvaluator`: a node with `n` visits can have up to
+/// `k * n^alpha` children, and its first child whatever its visits.
+#[derive(Debug, Clone, Copy)]
+pub struct WideningArgs<Args> {
+    pub k: f64,
+    /// The widening exponent, between 0 and 1: the lower it is, the slower the nodes widen.
+    pub alpha: f64,
+    /// The arguments of the widened evaluator.
+    pub args: Args,
+}
+
+/// Evaluates everything with the evaluator `EV`, and gives the `ProgressiveWideningTreePolicy`
+/// its constants through the `WideningArgs`.
+pub struct WideningEvaluator<EV> {
+    _marker: PhantomData<EV>,
+}
+
+impl<State, Reward, A, EV> Evaluator<State, Reward, A> for WideningEvaluator<EV>
+where
+    State: GameTrait,
+    Reward: Clone,
+    A: Clone + Default,
+    EV: Evaluator<State, Reward, A>,
+{
+    type Args = WideningArgs<EV::Args>;
+    type EvalResult = EV::EvalResult;
+
+    const NEEDS_LEAF: bool = EV::NEEDS_LEAF;
+    const NEEDS_FULL_HISTORIC: bool = EV::NEEDS_FULL_HISTORIC;
+    const USES_PLAYOUT_MOVES: bool = EV::USES_PLAYOUT_MOVES;
+    const USES_STATIC_EVAL: bool = EV::USES_STATIC_EVAL;
+
+    fn eval_child(
+        child: &LazyMctsNode<State, Reward, A>,
+        turn: &State::Player,
+        parent_visits: Nat,
+        args: &Self::Args,
+    ) -> Num {
+        EV::eval_child(child, turn, parent_visits, &args.args)
+    }
+
+    fn evaluate_leaf(child: State, turn: &State::Player) -> Self::EvalResult {
+        EV::evaluate_leaf(child, turn)
+    }
+
+    fn prior(child: &LazyMctsNode<State, Reward, A>) -> Option<f64> {
+        EV::prior(child)
+    }
+
+    fn evaluate_simulation(
+        leaf: &State,
+        final_state: State,
+        turn: &State::Player,
+        args: &Self::Args,
+    ) -> Self::EvalResult {
+        EV::evaluate_simulation(leaf, final_state, turn, &args.args)
+    }
+
+    fn evaluate_simulation_with_moves(
+        leaf: &State,
+        final_state: State,
+        playout_moves: Vec<State::Move>,
+        turn: &State::Player,
+        args: &Self::Args,
+    ) -> Self::EvalResult {
+        EV::evaluate_simulation_with_moves(leaf, final_state, playout_moves, turn, &args.args)
+    }
+
+    fn record_leaf(historic: &[State::Move], eval: &Self::EvalResult, args: &Self::Args) {
+        EV::record_leaf(historic, eval, &args.args)
+    }
+}
+
+/// Progressive widening, for the games with hundreds of moves per state whose nodes the
+/// `DefaultLazyTreePolicy` would spend all their visits expanding: a node with `n` visits only
+/// expands a new move while it has less than `k * n^alpha` children, see the `WideningArgs`,
+/// and the selection goes down to its best child otherwise. The expanded move is the unvisited
+/// move with the highest `GameTrait::expansion_priority`, the ties being broken with the random
+/// generator of the search, so the priorities must not be NaN. Use it with the
+/// `WideningEvaluator` wrapping the evaluator of the search.
+pub struct ProgressiveWideningTreePolicy<State, EV, A, Reward> {
+    _marker: PhantomData<(State, EV, A, Reward)>,
+}
+
+impl<State, EV, A, Reward> ProgressiveWideningTreePolicy<State, EV, A, Reward>
+where
+    State: GameTrait,
+{
+    /// Returns the index of the unvisited move of `state` to expand among `unvisited_moves`.
+    fn move_to_expand(
+        state: &State,
+        unvisited_moves: &[State::Move],
+        rng: &mut dyn RngCore,
+    ) -> usize {
+        let priorities: Vec<f64> = unvisited_moves
+            .iter()
+            .map(|mv| state.expansion_priority(mv))
+            .collect();
+        let best = priorities.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let ties: Vec<usize> = (0..priorities.len())
+            .filter(|&index| priorities[index] == best)
+            .collect();
+        ties[rng.gen_range(0..ties.len())]
+    }
+}
+
+impl<State, EV, A, Reward> LazyTreePolicy<State, WideningEvaluator<EV>, A, Reward>
+    for ProgressiveWideningTreePolicy<State, EV, A, Reward>
+where
+    State: GameTrait,
+    EV: Evaluator<State, Reward, A>,
+    A: Clone + Default,
+    Reward: Clone + Div + Add + ToPrimitive + Zero,
+{
+    fn tree_policy(
+        tree: &mut LazyMctsTree<State, Reward, A>,
+        mut state: State,
+        args: &WideningArgs<EV::Args>,
+        rng: &mut dyn RngCore,
+    ) -> (NodeId, State) {
+        let turn = state.player_turn();
+        let mut node_id = tree.root().id();
+        loop {
+            let node = tree.get(node_id).unwrap();
+            let value = node.value();
+            let children = node.children().count();
+            let widened = children == 0
+                || (children as f64) < args.k * f64::from(value.n_visits).powf(args.alpha);
+            if value.can_add_child() && widened {
+                let index = Self::move_to_expand(&state, &value.unvisited_moves, rng);
+                let historic = historic(node);
+                return DefaultLazyTreePolicy::<State, EV, A, Reward>::expand_move(
+                    tree.get_mut(node_id).unwrap(),
+                    state,
+                    historic,
+                    index,
+                );
+            }
+            if children == 0 {
+                return (node_id, state);
+            }
+            node_id = Self::best_child(tree, &turn, node_id, args);
+            state.do_move(tree.get(node_id).unwrap().value().state.last().unwrap());
+        }
+    }
+
+    fn best_child(
+        tree: &LazyMctsTree<State, Reward, A>,
+        turn: &State::Player,
+        parent_id: NodeId,
+        args: &WideningArgs<EV::Args>,
+    ) -> NodeId {
+        let parent = tree.get(parent_id).unwrap();
+        let n_visits = parent.value().n_visits;
+        parent
+            .children()
+            .max_by_key(|child| EV::eval_child(child.value(), turn, n_visits, &args.args))
+            .unwrap()
+            .id()
+    }
+}
+
+#[test]
+fn test_progressive_widening_expands_the_priority_moves() {
+    use crate::{DefaultBackProp, DefaultPlayout, DefaultUctEvaluator, LazyMcts};
+
+    /// The first player picks one of 100 moves and wins with the 99, which the game expects to
+    /// be the best.
+    #[derive(Debug, Clone)]
+    struct Wide(Option<u8>);
+
+    impl GameTrait for Wide {
+        type Player = u8;
+        type Move = u8;
+
+        fn legals_moves(&self) -> Vec<Self::Move> {
+            match self.0 {
+                None => (0..100).collect(),
+                Some(_) => vec![],
+            }
+        }
+
+        fn player_turn(&self) -> Self::Player {
+            if self.0.is_none() {
+                1
+            } else {
+                2
+            }
+        }
+
+        fn hash(&self) -> u64 {
+            self.0.map_or(0, |mv| u64::from(mv) + 1)
+        }
+
+        fn is_final(&self) -> bool {
+            self.0.is_some()
+        }
+
+        fn do_move(&mut self, m: &Self::Move) {
+            self.0 = Some(*m);
+        }
+
+        fn get_winner(&self) -> Self::Player {
+            if self.0 == Some(99) {
+                1
+            } else {
+                2
+            }
+        }
+
+        fn expansion_priority(&self, mv: &Self::Move) -> f64 {
+            f64::from(*mv / 10)
+        }
+    }
+
+    type EV = WideningEvaluator<DefaultUctEvaluator>;
+    type Policy = ProgressiveWideningTreePolicy<Wide, DefaultUctEvaluator, (), u32>;
+    type Mcts<'a> = LazyMcts<'a, Wide, Policy, DefaultPlayout, DefaultBackProp, EV, (), u32>;
+
+    let state = Wide(None);
+    let mcts = Mcts::deterministic(&state, 0);
+    let args = WideningArgs {
+        k: 1.,
+        alpha: 0.5,
+        args: 2f64.sqrt(),
+    };
+    for _ in 0..100 {
+        mcts.execute(&args, ());
+    }
+    let moves: Vec<u8> = mcts.move_visits().into_iter().map(|(mv, _)| mv).collect();
+    // 100 visits widen the root to 10 children, the moves of the highest priority.
+    assert_eq!(moves.len(), 10);
+    assert!(moves.iter().all(|mv| (90..100).contains(mv)));
+    assert_eq!(mcts.best_move(&args), 99);
+}